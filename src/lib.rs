@@ -1,2 +1,11 @@
 pub extern crate vo_table as table;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
 pub extern crate vo_sia as sia;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+pub extern crate vo_scs as scs;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+pub extern crate vo_tap as tap;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+pub extern crate vo_uws as uws;
+pub extern crate vo_client as client;
+pub extern crate vo_coords as coords;
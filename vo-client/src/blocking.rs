@@ -0,0 +1,137 @@
+//! Blocking requests, with ureq.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::{self, Read};
+use std::time::Duration;
+
+use http::header::{HeaderName, HeaderValue};
+use http::{Response, StatusCode};
+
+use crate::Error;
+
+/// Body of a response to a blocking request.
+pub struct BlockingBody(Box<dyn Read + Send + Sync>);
+
+impl Read for BlockingBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl fmt::Debug for BlockingBody {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BlockingBody")
+    }
+}
+
+/// Agents sending HTTP and HTTPS requests. They differ if the requests go
+/// through different proxies.
+#[derive(Clone)]
+pub(crate) struct Agents {
+    http: ureq::Agent,
+    https: ureq::Agent,
+}
+
+pub(crate) struct Config<'a> {
+    pub user_agent: &'a str,
+    pub connect_timeout: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub http_proxy: Option<&'a str>,
+    pub https_proxy: Option<&'a str>,
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Agents {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let agent = |proxy: Option<&str>| -> Result<ureq::Agent, Error> {
+            // Redirections are followed by the callers, as with the
+            // asynchronous client
+            let mut builder = ureq::AgentBuilder::new()
+                .user_agent(config.user_agent)
+                .redirects(0)
+                .max_idle_connections_per_host(config.pool_max_idle_per_host);
+            if let Some(timeout) = config.connect_timeout {
+                builder = builder.timeout_connect(timeout);
+            }
+            if let Some(timeout) = config.timeout {
+                builder = builder.timeout_read(timeout);
+            }
+            if let Some(proxy) = proxy {
+                let proxy = ureq::Proxy::new(proxy)
+                    .map_err(|e| Error::InvalidConfig(format!("Invalid proxy. {}", e)))?;
+                builder = builder.proxy(proxy);
+            }
+            Ok(builder.build())
+        };
+        Ok(Agents {
+            http: agent(config.http_proxy)?,
+            https: agent(config.https_proxy)?,
+        })
+    }
+
+    /// Send a GET request to `url`. Responses with an error status are
+    /// returned as any other response.
+    pub fn get(&self, url: &str) -> Result<Response<BlockingBody>, Error> {
+        response(self.agent(url).get(url).call())
+    }
+
+    /// Send a POST request with `body` of type `content_type` to `url`.
+    pub fn post(
+        &self,
+        url: &str,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<Response<BlockingBody>, Error> {
+        let request = self.agent(url).post(url).set("Content-Type", content_type);
+        response(request.send_bytes(body))
+    }
+
+    fn agent(&self, url: &str) -> &ureq::Agent {
+        if url.starts_with("https:") {
+            &self.https
+        } else {
+            &self.http
+        }
+    }
+}
+
+/// Responses with an error status are returned as any other response.
+fn response(result: Result<ureq::Response, ureq::Error>) -> Result<Response<BlockingBody>, Error> {
+    match result {
+        Ok(res) | Err(ureq::Error::Status(_, res)) => convert(res),
+        Err(ureq::Error::Transport(e)) => Err(transport_error(e)),
+    }
+}
+
+fn convert(res: ureq::Response) -> Result<Response<BlockingBody>, Error> {
+    let status = StatusCode::from_u16(res.status()).map_err(|e| Error::Io(io::Error::other(e)))?;
+    let mut response = Response::builder().status(status);
+    for name in res.headers_names() {
+        let header = match HeaderName::from_bytes(name.as_bytes()) {
+            Ok(header) => header,
+            Err(_) => continue,
+        };
+        for value in res.all(&name) {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                response = response.header(header.clone(), value);
+            }
+        }
+    }
+    response
+        .body(BlockingBody(res.into_reader()))
+        .map_err(|e| Error::Io(io::Error::other(e)))
+}
+
+fn transport_error(e: ureq::Transport) -> Error {
+    let timed_out = e
+        .source()
+        .and_then(|source| source.downcast_ref::<io::Error>())
+        .map(|e| e.kind() == io::ErrorKind::TimedOut || e.kind() == io::ErrorKind::WouldBlock)
+        .unwrap_or(false);
+    if timed_out {
+        Error::Timeout
+    } else {
+        Error::Transport(Box::new(e))
+    }
+}
@@ -0,0 +1,181 @@
+//! Connection to HTTP servers, directly or through a proxy.
+
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use hyper::client::connect::{Connected, Connection};
+use hyper::client::HttpConnector;
+use hyper::service::Service;
+use hyper::Uri;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::TcpStream;
+
+/// Maximum size of the response of a proxy to a CONNECT request
+const MAX_CONNECT_RESPONSE_LEN: usize = 8192;
+
+/// Connector used by the client, with TLS if supported.
+#[cfg(feature = "rustls-tls")]
+pub(crate) type ClientConnector = hyper_rustls::HttpsConnector<Connector>;
+#[cfg(not(feature = "rustls-tls"))]
+pub(crate) type ClientConnector = Connector;
+
+/// Open TCP connections to servers, or to the proxy.
+///
+/// Connections to HTTPS servers through a proxy go through a tunnel, over
+/// which TLS is negotiated with the server.
+#[derive(Clone)]
+pub(crate) struct Connector {
+    pub http: HttpConnector,
+    pub http_proxy: Option<Uri>,
+    pub https_proxy: Option<Uri>,
+}
+
+/// A TCP stream, to a server or to a proxy.
+pub(crate) struct Stream {
+    tcp: TcpStream,
+    /// Whether requests are sent to a proxy in absolute form
+    proxied: bool,
+}
+
+impl Connector {
+    pub fn into_client_connector(self) -> ClientConnector {
+        #[cfg(feature = "rustls-tls")]
+        {
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_webpki_roots()
+                .https_or_http()
+                .enable_http1()
+                .wrap_connector(self)
+        }
+        #[cfg(not(feature = "rustls-tls"))]
+        {
+            self
+        }
+    }
+}
+
+impl Service<Uri> for Connector {
+    type Response = Stream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Stream, io::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.http.poll_ready(cx).map_err(other)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let https = dst.scheme_str() == Some("https");
+        let proxy = if https {
+            self.https_proxy.clone()
+        } else {
+            self.http_proxy.clone()
+        };
+        let mut http = self.http.clone();
+
+        Box::pin(async move {
+            if https && cfg!(not(feature = "rustls-tls")) {
+                return Err(other(
+                    "HTTPS is not supported without the rustls-tls feature",
+                ));
+            }
+            let proxy = match proxy {
+                Some(proxy) => proxy,
+                None => {
+                    let tcp = http.call(dst).await.map_err(other)?;
+                    return Ok(Stream {
+                        tcp,
+                        proxied: false,
+                    });
+                }
+            };
+            let tcp = http.call(proxy).await.map_err(other)?;
+            if !https {
+                // Requests to HTTP servers are sent to the proxy in absolute form
+                return Ok(Stream { tcp, proxied: true });
+            }
+            let authority = format!(
+                "{}:{}",
+                dst.host().unwrap_or_default(),
+                dst.port_u16().unwrap_or(443)
+            );
+            let tcp = tunnel(tcp, &authority).await?;
+            Ok(Stream {
+                tcp,
+                proxied: false,
+            })
+        })
+    }
+}
+
+/// Open a tunnel to `authority` through the HTTP proxy connected to `tcp`.
+async fn tunnel(mut tcp: TcpStream, authority: &str) -> io::Result<TcpStream> {
+    let request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", authority);
+    tcp.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    let mut buf = [0; 512];
+    while !response.windows(4).any(|w| w == b"\r\n\r\n") {
+        if response.len() > MAX_CONNECT_RESPONSE_LEN {
+            return Err(other("response of proxy is too long"));
+        }
+        let n = tcp.read(&mut buf).await?;
+        if n == 0 {
+            return Err(other("proxy closed the connection"));
+        }
+        response.extend_from_slice(&buf[..n]);
+    }
+
+    if response.starts_with(b"HTTP/1.1 200") || response.starts_with(b"HTTP/1.0 200") {
+        Ok(tcp)
+    } else {
+        let status = response.split(|b| *b == b'\r').next().unwrap_or_default();
+        Err(other(format!(
+            "proxy refused the tunnel: {}",
+            String::from_utf8_lossy(status)
+        )))
+    }
+}
+
+fn other<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    io::Error::other(e)
+}
+
+impl Connection for Stream {
+    fn connected(&self) -> Connected {
+        Connected::new().proxy(self.proxied)
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.tcp).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.tcp).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.tcp).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.tcp).poll_shutdown(cx)
+    }
+}
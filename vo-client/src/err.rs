@@ -0,0 +1,110 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "tokio")]
+    Hyper(hyper::Error),
+    /// Error of the blocking client while sending a request.
+    #[cfg(feature = "blocking")]
+    Transport(Box<ureq::Transport>),
+    /// Error while reading the body of a response.
+    Io(io::Error),
+    InvalidUri(http::uri::InvalidUri),
+    /// Invalid configuration of the client.
+    InvalidConfig(String),
+    /// No response was received before the timeout.
+    Timeout,
+    /// The server answered with an error status.
+    HttpStatus {
+        code: http::StatusCode,
+        body_excerpt: String,
+    },
+    /// The service reported an error in a VOTable, in its QUERY_STATUS INFO
+    /// or in an INFO named "Error".
+    ServiceError {
+        code: http::StatusCode,
+        message: String,
+    },
+    /// The response to a query is not a VOTable.
+    UnexpectedContentType {
+        content_type: String,
+        body_excerpt: String,
+    },
+    TooManyRedirects(String),
+    InvalidRedirect(String),
+    VOTable(vo_table::Error),
+    /// The Tokio runtime of blocking functions could not be initialized.
+    #[cfg(feature = "tokio")]
+    Runtime(io::Error),
+    /// A blocking function was called from a single-threaded asynchronous
+    /// runtime, which it would block.
+    #[cfg(feature = "tokio")]
+    BlockingInAsyncContext,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+        match self {
+            #[cfg(feature = "tokio")]
+            Hyper(e) => write!(f, "HTTP error. {}", e),
+            #[cfg(feature = "blocking")]
+            Transport(e) => write!(f, "HTTP error. {}", e),
+            Io(e) => write!(f, "I/O error. {}", e),
+            InvalidUri(e) => write!(f, "Invalid URL. {}", e),
+            InvalidConfig(msg) => write!(f, "Invalid client configuration. {}", msg),
+            Timeout => write!(f, "Request timed out"),
+            HttpStatus { code, body_excerpt } => {
+                write!(f, "Unexpected HTTP status: {}", code)?;
+                if !body_excerpt.is_empty() {
+                    write!(f, ". {}", body_excerpt)?;
+                }
+                Ok(())
+            }
+            ServiceError { message, .. } => write!(f, "Service error. {}", message),
+            UnexpectedContentType {
+                content_type,
+                body_excerpt,
+            } => write!(
+                f,
+                "Expected a VOTable, but received {}. {}",
+                content_type, body_excerpt
+            ),
+            TooManyRedirects(url) => write!(f, "Too many redirections from {}", url),
+            InvalidRedirect(location) => write!(f, "Invalid redirection to '{}'", location),
+            VOTable(e) => write!(f, "VOTable error. {}", e),
+            #[cfg(feature = "tokio")]
+            Runtime(e) => write!(
+                f,
+                "Runtime error. Could not initialize a Tokio runtime, caused by {}",
+                e
+            ),
+            #[cfg(feature = "tokio")]
+            BlockingInAsyncContext => write!(
+                f,
+                "Blocking function called from a single-threaded Tokio runtime. \
+                 Use the asynchronous version instead."
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            #[cfg(feature = "tokio")]
+            Hyper(e) => Some(e),
+            #[cfg(feature = "blocking")]
+            Transport(e) => Some(e),
+            Io(e) => Some(e),
+            InvalidUri(e) => Some(e),
+            VOTable(e) => Some(e),
+            #[cfg(feature = "tokio")]
+            Runtime(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! HTTP client shared by the implementations of the Virtual Observatory
+//! protocols.
+//!
+//! A [`VoClient`](struct.VoClient.html) is cheap to clone, and clones share
+//! the same pool of connections. Create one client and pass it to all the
+//! services instead of creating a client for each request.
+//!
+//! The client can send requests asynchronously on a Tokio runtime, with the
+//! `tokio` feature enabled by default, or block the current thread, with the
+//! `blocking` feature which does not depend on any asynchronous runtime.
+//!
+//! HTTPS is supported with [rustls](https://github.com/rustls/rustls),
+//! through the `rustls-tls` feature enabled by default.
+
+#[cfg(feature = "blocking")]
+mod blocking;
+#[cfg(feature = "tokio")]
+mod connect;
+mod err;
+mod multipart;
+#[cfg(feature = "tokio")]
+mod runtime;
+pub mod service;
+
+use std::fmt;
+#[cfg(feature = "tokio")]
+use std::io;
+use std::time::Duration;
+
+use http::header::HeaderValue;
+use http::Uri;
+#[cfg(feature = "tokio")]
+use http::{
+    header::{CONTENT_TYPE, USER_AGENT},
+    Method, Request, Response,
+};
+
+#[cfg(feature = "blocking")]
+pub use crate::blocking::BlockingBody;
+#[cfg(feature = "tokio")]
+use crate::connect::{ClientConnector, Connector};
+pub use crate::err::Error;
+pub use crate::multipart::Multipart;
+#[cfg(feature = "tokio")]
+pub use crate::runtime::Runtime;
+pub use crate::service::{RawResponse, ResponseHook};
+#[cfg(feature = "tokio")]
+pub use hyper::Body;
+
+/// Default value of the User-Agent header
+pub const DEFAULT_USER_AGENT: &str = concat!("vo-rs/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP client used to query Virtual Observatory services.
+#[derive(Clone)]
+pub struct VoClient {
+    #[cfg(feature = "tokio")]
+    client: hyper::Client<ClientConnector>,
+    #[cfg(feature = "blocking")]
+    blocking: blocking::Agents,
+    user_agent: HeaderValue,
+    timeout: Option<Duration>,
+}
+
+/// Configuration of a [`VoClient`](struct.VoClient.html).
+#[derive(Debug, Clone)]
+pub struct VoClientBuilder {
+    user_agent: String,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    http_proxy: Option<String>,
+    https_proxy: Option<String>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+}
+
+impl VoClient {
+    /// Create a client with the default configuration.
+    pub fn new() -> Self {
+        VoClientBuilder::default()
+            .build()
+            .expect("Default configuration is valid")
+    }
+
+    pub fn builder() -> VoClientBuilder {
+        VoClientBuilder::default()
+    }
+
+    /// Timeout to receive the headers of a response, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl VoClient {
+    /// Send a GET request to `uri`.
+    ///
+    /// Must be called from a Tokio runtime.
+    pub async fn get(&self, uri: Uri) -> Result<Response<Body>, Error> {
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = uri;
+        self.request(request).await
+    }
+
+    /// Send `request`, with the User-Agent header of the client unless the
+    /// request already has one.
+    ///
+    /// The timeout of the client applies until the headers of the response
+    /// are received.
+    pub async fn request(&self, mut request: Request<Body>) -> Result<Response<Body>, Error> {
+        if !request.headers().contains_key(USER_AGENT) {
+            request
+                .headers_mut()
+                .insert(USER_AGENT, self.user_agent.clone());
+        }
+        let response = self.client.request(request);
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, response)
+                .await
+                .map_err(|_| Error::Timeout)?
+                .map_err(Error::Hyper),
+            None => response.await.map_err(Error::Hyper),
+        }
+    }
+
+    /// Parse `url` and send a GET request to it.
+    pub async fn get_url(&self, url: &str) -> Result<Response<Body>, Error> {
+        let uri = url.parse::<Uri>().map_err(Error::InvalidUri)?;
+        self.get(uri).await
+    }
+
+    /// Parse `url` and send a POST request with `body` of type
+    /// `content_type` to it.
+    pub async fn post_url<B: Into<Body>>(
+        &self,
+        url: &str,
+        content_type: &str,
+        body: B,
+    ) -> Result<Response<Body>, Error> {
+        let uri = url.parse::<Uri>().map_err(Error::InvalidUri)?;
+        let content_type = HeaderValue::from_str(content_type)
+            .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::InvalidInput, e)))?;
+        let mut request = Request::new(body.into());
+        *request.method_mut() = Method::POST;
+        *request.uri_mut() = uri;
+        request.headers_mut().insert(CONTENT_TYPE, content_type);
+        self.request(request).await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl VoClient {
+    /// Send a GET request to `url`, blocking the current thread until the
+    /// headers of the response are received.
+    ///
+    /// The timeout of the client applies to each read from the connection.
+    pub fn get_blocking(&self, url: &str) -> Result<http::Response<BlockingBody>, Error> {
+        url.parse::<Uri>().map_err(Error::InvalidUri)?;
+        self.blocking.get(url)
+    }
+
+    /// Send a POST request with `body` of type `content_type` to `url`,
+    /// blocking the current thread until the headers of the response are
+    /// received.
+    pub fn post_blocking(
+        &self,
+        url: &str,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<http::Response<BlockingBody>, Error> {
+        url.parse::<Uri>().map_err(Error::InvalidUri)?;
+        self.blocking.post(url, content_type, body)
+    }
+}
+
+impl Default for VoClient {
+    fn default() -> Self {
+        VoClient::new()
+    }
+}
+
+impl fmt::Debug for VoClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VoClient")
+            .field("user_agent", &self.user_agent)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Default for VoClientBuilder {
+    fn default() -> Self {
+        VoClientBuilder {
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            connect_timeout: Some(Duration::from_secs(30)),
+            timeout: Some(Duration::from_secs(300)),
+            http_proxy: None,
+            https_proxy: None,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle_per_host: usize::MAX,
+        }
+    }
+}
+
+impl VoClientBuilder {
+    pub fn with_user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Timeout to establish a connection. Defaults to 30 seconds.
+    pub fn with_connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Timeout to receive the headers of a response. Defaults to 5 minutes,
+    /// as services may take some time to answer complex queries.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send HTTP and HTTPS requests through the HTTP proxy at `url`.
+    pub fn with_proxy<S: Into<String>>(self, url: S) -> Self {
+        let url = url.into();
+        self.with_http_proxy(url.clone()).with_https_proxy(url)
+    }
+
+    /// Send HTTP requests through the HTTP proxy at `url`.
+    pub fn with_http_proxy<S: Into<String>>(mut self, url: S) -> Self {
+        self.http_proxy = Some(url.into());
+        self
+    }
+
+    /// Send HTTPS requests through a tunnel opened by the HTTP proxy at
+    /// `url`.
+    pub fn with_https_proxy<S: Into<String>>(mut self, url: S) -> Self {
+        self.https_proxy = Some(url.into());
+        self
+    }
+
+    /// Use the proxies set by the `http_proxy` and `https_proxy`
+    /// environment variables, if any.
+    pub fn with_proxy_from_env(mut self) -> Self {
+        use std::env::var;
+
+        if let Ok(url) = var("http_proxy").or_else(|_| var("HTTP_PROXY")) {
+            self.http_proxy = Some(url);
+        }
+        if let Ok(url) = var("https_proxy").or_else(|_| var("HTTPS_PROXY")) {
+            self.https_proxy = Some(url);
+        }
+        self
+    }
+
+    /// Close idle connections after `timeout`. Defaults to 90 seconds.
+    pub fn with_pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = timeout;
+        self
+    }
+
+    /// Maximum number of idle connections kept for each host. Set to 0 to
+    /// disable connection pooling.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    pub fn build(self) -> Result<VoClient, Error> {
+        fn proxy(url: Option<&str>) -> Result<Option<Uri>, Error> {
+            match url {
+                None => Ok(None),
+                Some(url) => {
+                    let uri = url.parse::<Uri>().map_err(Error::InvalidUri)?;
+                    if uri.scheme_str() != Some("http") {
+                        return Err(Error::InvalidConfig(format!(
+                            "Only HTTP proxies are supported, got {}",
+                            url
+                        )));
+                    }
+                    Ok(Some(uri))
+                }
+            }
+        }
+
+        let user_agent = HeaderValue::from_str(&self.user_agent).map_err(|_| {
+            Error::InvalidConfig(format!("Invalid User-Agent {:?}", self.user_agent))
+        })?;
+        let http_proxy = proxy(self.http_proxy.as_deref())?;
+        let https_proxy = proxy(self.https_proxy.as_deref())?;
+
+        #[cfg(feature = "tokio")]
+        let client = {
+            let mut http = hyper::client::HttpConnector::new();
+            http.enforce_http(false);
+            http.set_connect_timeout(self.connect_timeout);
+            let connector = Connector {
+                http,
+                http_proxy,
+                https_proxy,
+            };
+            hyper::Client::builder()
+                .pool_idle_timeout(self.pool_idle_timeout)
+                .pool_max_idle_per_host(self.pool_max_idle_per_host)
+                .build(connector.into_client_connector())
+        };
+        #[cfg(not(feature = "tokio"))]
+        let _ = (http_proxy, https_proxy);
+
+        Ok(VoClient {
+            #[cfg(feature = "tokio")]
+            client,
+            #[cfg(feature = "blocking")]
+            blocking: blocking::Agents::new(&blocking::Config {
+                user_agent: &self.user_agent,
+                connect_timeout: self.connect_timeout,
+                timeout: self.timeout,
+                http_proxy: self.http_proxy.as_deref(),
+                https_proxy: self.https_proxy.as_deref(),
+                pool_max_idle_per_host: self.pool_max_idle_per_host,
+            })?,
+            user_agent,
+            timeout: self.timeout,
+        })
+    }
+}
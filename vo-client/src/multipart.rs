@@ -0,0 +1,127 @@
+//! Bodies of type multipart/form-data, e.g. to upload files to services.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Body of a request of type multipart/form-data.
+///
+/// ```
+/// use vo_client::Multipart;
+///
+/// let (content_type, body) = Multipart::new()
+///     .with_text("LANG", "ADQL")
+///     .with_file("t1", "t1.xml", "application/x-votable+xml", b"<VOTABLE/>".to_vec())
+///     .encode();
+/// assert!(content_type.starts_with("multipart/form-data; boundary="));
+/// assert!(String::from_utf8_lossy(&body).contains("name=\"LANG\""));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Part {
+    name: String,
+    /// File name and content type, for files
+    file: Option<(String, String)>,
+    content: Vec<u8>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Multipart::default()
+    }
+
+    /// Add a form field with the value `value`.
+    pub fn with_text(mut self, name: &str, value: &str) -> Self {
+        self.parts.push(Part {
+            name: name.to_owned(),
+            file: None,
+            content: value.as_bytes().to_vec(),
+        });
+        self
+    }
+
+    /// Add a file named `filename`, with `content` of type `content_type`.
+    pub fn with_file(
+        mut self,
+        name: &str,
+        filename: &str,
+        content_type: &str,
+        content: Vec<u8>,
+    ) -> Self {
+        self.parts.push(Part {
+            name: name.to_owned(),
+            file: Some((filename.to_owned(), content_type.to_owned())),
+            content,
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The content type, with its boundary, and the body of the request.
+    ///
+    /// The boundary is derived from the content of the parts, so that the
+    /// same parts are always encoded the same way.
+    pub fn encode(&self) -> (String, Vec<u8>) {
+        let boundary = self.boundary();
+        let mut body = Vec::new();
+        for part in &self.parts {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            let name = escape(&part.name);
+            match &part.file {
+                Some((filename, content_type)) => body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                         Content-Type: {}\r\n\r\n",
+                        name,
+                        escape(filename),
+                        content_type
+                    )
+                    .as_bytes(),
+                ),
+                None => body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes(),
+                ),
+            }
+            body.extend_from_slice(&part.content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+        (format!("multipart/form-data; boundary={}", boundary), body)
+    }
+
+    /// A boundary which does not appear in any part.
+    fn boundary(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.parts.hash(&mut hasher);
+        loop {
+            let boundary = format!("vo-rs-{:016x}", hasher.finish());
+            if !self
+                .parts
+                .iter()
+                .any(|part| contains(&part.content, boundary.as_bytes()))
+            {
+                return boundary;
+            }
+            boundary.hash(&mut hasher);
+        }
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Names are quoted strings, which must not contain quotes nor line breaks.
+fn escape(name: &str) -> String {
+    name.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
@@ -0,0 +1,82 @@
+//! Execution of asynchronous requests from blocking functions.
+
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+use log::debug;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+use crate::Error;
+
+/// Tokio runtime running the requests of blocking functions. Clones share
+/// the same runtime.
+///
+/// ```no_run
+/// # extern crate vo_client;
+/// # use vo_client::{Runtime, VoClient};
+/// # fn main() {
+/// let client = VoClient::new();
+/// let res = Runtime::shared()
+///     .and_then(|runtime| runtime.block_on(client.get_url("http://example.com")))
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Runtime(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner(Option<tokio::runtime::Runtime>);
+
+impl Runtime {
+    pub fn new() -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::Runtime)?;
+        Ok(Runtime(Arc::new(Inner(Some(runtime)))))
+    }
+
+    /// Runtime shared by the blocking functions which are not given one,
+    /// created on first use.
+    pub fn shared() -> Result<Self, Error> {
+        static SHARED: OnceLock<Runtime> = OnceLock::new();
+
+        if let Some(runtime) = SHARED.get() {
+            return Ok(runtime.clone());
+        }
+        let runtime = Runtime::new()?;
+        Ok(SHARED.get_or_init(|| runtime).clone())
+    }
+
+    /// Run `future` to completion, blocking the current thread.
+    ///
+    /// From a worker thread of a multi-threaded Tokio runtime, the other
+    /// tasks of the worker are moved to another thread while blocking. A
+    /// single-threaded runtime cannot be blocked without blocking all its
+    /// tasks, so an error is returned instead.
+    pub fn block_on<F: Future>(&self, future: F) -> Result<F::Output, Error> {
+        let runtime = self.0 .0.as_ref().expect("Runtime is only taken on drop");
+        match Handle::try_current() {
+            Err(_) => Ok(runtime.block_on(future)),
+            Ok(handle) => match handle.runtime_flavor() {
+                RuntimeFlavor::MultiThread => {
+                    debug!("Blocking a worker thread of the current Tokio runtime");
+                    Ok(tokio::task::block_in_place(|| runtime.block_on(future)))
+                }
+                _ => Err(Error::BlockingInAsyncContext),
+            },
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // A runtime dropped from an asynchronous context would panic while
+        // waiting for its blocking tasks
+        if let Some(runtime) = self.0.take() {
+            if Handle::try_current().is_ok() {
+                runtime.shutdown_background();
+            }
+        }
+    }
+}
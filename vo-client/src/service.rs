@@ -0,0 +1,720 @@
+//! Requests to Virtual Observatory services, with redirections and error
+//! responses.
+//!
+//! Services answer queries with VOTables, and report errors in VOTables
+//! too, whatever the HTTP status. [`VoClient::query`] parses the VOTable of
+//! the response and turns error documents into an
+//! [`Error::ServiceError`](../enum.Error.html).
+//!
+//! [`VoClient::query`]: ../struct.VoClient.html#method.query
+
+use std::fmt;
+use std::io::Cursor;
+#[cfg(feature = "blocking")]
+use std::io::{self, Read};
+#[cfg(feature = "tokio")]
+use std::mem;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(feature = "tokio")]
+use std::sync::Mutex;
+#[cfg(feature = "tokio")]
+use std::task::{ready, Context, Poll};
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use std::time::Instant;
+
+#[cfg(feature = "tokio")]
+use futures_core::Stream;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use http::header::CONTENT_TYPE;
+use http::header::LOCATION;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use http::response::Parts;
+use http::{HeaderMap, StatusCode};
+#[cfg(feature = "tokio")]
+use hyper::body::{Bytes, HttpBody};
+use log::debug;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use log::trace;
+use vo_table::{Info, ParseOptions, VOTable};
+
+#[cfg(feature = "blocking")]
+use crate::BlockingBody;
+#[cfg(feature = "tokio")]
+use crate::Body;
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use crate::VoClient;
+use crate::{Error, Multipart};
+
+/// Maximum number of redirections followed for a single request
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+const MAX_REDIRECTS: usize = 10;
+
+/// Maximum number of bytes read from the body of an error response
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+const MAX_ERROR_BODY_LEN: usize = 64 * 1024;
+
+/// Maximum number of characters of a body kept in errors
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+const MAX_EXCERPT_LEN: usize = 512;
+
+/// Content type of the parameters posted to a service
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// A response of a service, as received before being parsed.
+#[derive(Debug)]
+pub struct RawResponse<'a> {
+    /// URL of the request, before any redirection
+    pub url: &'a str,
+    pub status: StatusCode,
+    pub headers: &'a HeaderMap,
+    /// Body of the response. Only the beginning of the body is available
+    /// for error responses.
+    pub body: &'a [u8],
+}
+
+/// Function called with the raw responses to requests.
+#[derive(Clone)]
+pub struct ResponseHook(Arc<dyn Fn(&RawResponse) + Send + Sync>);
+
+impl ResponseHook {
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&RawResponse) + Send + Sync + 'static,
+    {
+        ResponseHook(Arc::new(hook))
+    }
+
+    pub fn call(&self, response: &RawResponse) {
+        (self.0)(response)
+    }
+}
+
+impl fmt::Debug for ResponseHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ResponseHook")
+    }
+}
+
+/// Hooks are compared by identity.
+impl PartialEq for ResponseHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ResponseHook {}
+
+/// A request to a service.
+///
+/// ```
+/// # extern crate vo_client;
+/// # use vo_client::service::Request;
+/// # fn main() {
+/// let request = Request::post_form(
+///     "http://example.com/tap/sync",
+///     vec![("LANG", "ADQL"), ("QUERY", "SELECT * FROM stars")],
+/// );
+/// assert_eq!(request.url(), "http://example.com/tap/sync");
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    url: String,
+    /// Content type and body posted to the service, or `None` for a GET
+    /// request
+    body: Option<(String, Vec<u8>)>,
+    /// Whether a redirection is followed, or returned as the response
+    follow: bool,
+    hook: Option<ResponseHook>,
+}
+
+impl Request {
+    pub fn get<S: Into<String>>(url: S) -> Self {
+        Request {
+            url: url.into(),
+            body: None,
+            follow: true,
+            hook: None,
+        }
+    }
+
+    /// Post `body` of type `content_type`.
+    pub fn post<S, T>(url: S, content_type: T, body: Vec<u8>) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        Request {
+            body: Some((content_type.into(), body)),
+            ..Request::get(url)
+        }
+    }
+
+    /// Post `parameters` as application/x-www-form-urlencoded.
+    pub fn post_form<'a, S, I>(url: S, parameters: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let form = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(parameters)
+            .finish();
+        Request::post(url, FORM_CONTENT_TYPE, form.into_bytes())
+    }
+
+    /// Post parameters and files as multipart/form-data.
+    pub fn post_multipart<S: Into<String>>(url: S, multipart: &Multipart) -> Self {
+        let (content_type, body) = multipart.encode();
+        Request::post(url, content_type, body)
+    }
+
+    /// Return redirections instead of following them, e.g. to read the
+    /// location of a new job.
+    pub fn without_redirects(mut self) -> Self {
+        self.follow = false;
+        self
+    }
+
+    /// Call `hook` with the raw response of the service, or with the error
+    /// response if the request fails.
+    pub fn with_response_hook(mut self, hook: Option<ResponseHook>) -> Self {
+        self.hook = hook;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// A successful response with its whole body, or a redirection which was
+/// not followed.
+#[derive(Debug)]
+pub struct Response {
+    /// URL of the response, after redirections
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The location to which the response redirects, if any.
+    pub fn location(&self) -> Result<Option<String>, Error> {
+        if is_redirect(self.status) {
+            resolve(&self.url, &self.headers).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl VoClient {
+    /// Send `request`, following redirections unless disabled, and return
+    /// the response once its headers are received.
+    ///
+    /// Redirections are followed with a GET request, e.g. to the results of
+    /// a query, except for 307 and 308 redirections which repeat the POST
+    /// request. Responses with an error status are turned into an
+    /// [`Error::ServiceError`](../enum.Error.html) if their body is a
+    /// VOTable with an error message, or an
+    /// [`Error::HttpStatus`](../enum.Error.html) otherwise.
+    pub async fn open(&self, request: &Request) -> Result<http::Response<Body>, Error> {
+        let (_, res) = self.follow(request).await?;
+        Ok(res)
+    }
+
+    /// Send `request` and return the URL of the response, after
+    /// redirections, with the response.
+    async fn follow(&self, request: &Request) -> Result<(String, http::Response<Body>), Error> {
+        let mut current = request.url.clone();
+        let mut body = request.body.as_ref();
+        let mut redirects = 0;
+        loop {
+            let start = Instant::now();
+            let res = match body {
+                Some((content_type, content)) => {
+                    debug!("POST {} ({})", current, describe(content_type, content));
+                    self.post_url(&current, content_type, content.clone())
+                        .await?
+                }
+                None => {
+                    debug!("GET {}", current);
+                    self.get_url(&current).await?
+                }
+            };
+            let status = res.status();
+            debug!("{} from {} in {:?}", status, current, start.elapsed());
+            if is_redirect(status) && request.follow {
+                current = redirect(&current, redirects, res.headers())?;
+                redirects += 1;
+                if !keeps_method(status) {
+                    body = None;
+                }
+            } else if status.is_success() || is_redirect(status) {
+                return Ok((current, res));
+            } else {
+                let (parts, body) = res.into_parts();
+                let body = read_prefix(body, MAX_ERROR_BODY_LEN).await?;
+                return Err(error_response(request, &parts, &body));
+            }
+        }
+    }
+
+    /// Send `request` and read the whole body of the response, e.g. a VOSI
+    /// or UWS document.
+    pub async fn send(&self, request: &Request) -> Result<Response, Error> {
+        let start = Instant::now();
+        let (url, res) = self.follow(request).await?;
+        let (parts, body) = res.into_parts();
+        let body = hyper::body::to_bytes(body).await.map_err(Error::Hyper)?;
+        Ok(received(request, url, parts, body.to_vec(), start))
+    }
+
+    /// Send the query `request` and parse the VOTable it returns as its
+    /// body is received, on a thread where blocking is acceptable.
+    pub async fn query(&self, request: &Request) -> Result<VOTable, Error> {
+        let start = Instant::now();
+        let (url, res) = self.follow(request).await?;
+        let (parts, body) = res.into_parts();
+        if let Some(content_type) = unexpected_content_type(&parts) {
+            let body = read_prefix(body, MAX_ERROR_BODY_LEN).await?;
+            return Err(content_type_error(request, &parts, content_type, &body));
+        }
+        let copy = Arc::new(Mutex::new(BodyCopy::new(request)));
+        let body = CopiedBody {
+            body,
+            copy: copy.clone(),
+        };
+        let result = vo_table::parse_async(body, ParseOptions::default()).await;
+        let copy = mem::take(&mut *copy.lock().unwrap());
+        parsed(request, url, parts, copy, start, result)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl VoClient {
+    /// Blocking version of [`open`](#method.open).
+    pub fn open_blocking(&self, request: &Request) -> Result<http::Response<BlockingBody>, Error> {
+        let (_, res) = self.follow_blocking(request)?;
+        Ok(res)
+    }
+
+    /// Blocking version of [`follow`](#method.follow).
+    fn follow_blocking(
+        &self,
+        request: &Request,
+    ) -> Result<(String, http::Response<BlockingBody>), Error> {
+        let mut current = request.url.clone();
+        let mut body = request.body.as_ref();
+        let mut redirects = 0;
+        loop {
+            let start = Instant::now();
+            let res = match body {
+                Some((content_type, content)) => {
+                    debug!("POST {} ({})", current, describe(content_type, content));
+                    self.post_blocking(&current, content_type, content)?
+                }
+                None => {
+                    debug!("GET {}", current);
+                    self.get_blocking(&current)?
+                }
+            };
+            let status = res.status();
+            debug!("{} from {} in {:?}", status, current, start.elapsed());
+            if is_redirect(status) && request.follow {
+                current = redirect(&current, redirects, res.headers())?;
+                redirects += 1;
+                if !keeps_method(status) {
+                    body = None;
+                }
+            } else if status.is_success() || is_redirect(status) {
+                return Ok((current, res));
+            } else {
+                let (parts, body) = res.into_parts();
+                let body = read_prefix_blocking(body, MAX_ERROR_BODY_LEN)?;
+                return Err(error_response(request, &parts, &body));
+            }
+        }
+    }
+
+    /// Blocking version of [`send`](#method.send).
+    pub fn send_blocking(&self, request: &Request) -> Result<Response, Error> {
+        let start = Instant::now();
+        let (url, res) = self.follow_blocking(request)?;
+        let (parts, mut body) = res.into_parts();
+        let mut buf = Vec::new();
+        body.read_to_end(&mut buf).map_err(Error::Io)?;
+        Ok(received(request, url, parts, buf, start))
+    }
+
+    /// Blocking version of [`query`](#method.query).
+    pub fn query_blocking(&self, request: &Request) -> Result<VOTable, Error> {
+        let start = Instant::now();
+        let (url, res) = self.follow_blocking(request)?;
+        let (parts, mut body) = res.into_parts();
+        if let Some(content_type) = unexpected_content_type(&parts) {
+            let body = read_prefix_blocking(body, MAX_ERROR_BODY_LEN)?;
+            return Err(content_type_error(request, &parts, content_type, &body));
+        }
+        let mut copy = BodyCopy::new(request);
+        let result = vo_table::parse(CopiedReader {
+            body: &mut body,
+            copy: &mut copy,
+        });
+        parsed(request, url, parts, copy, start, result)
+    }
+}
+
+/// Parse the VOTable of the results of the query `url`, and check whether
+/// it reports an error.
+pub fn parse_results(url: &str, status: StatusCode, body: &[u8]) -> Result<VOTable, Error> {
+    check_results(url, status, vo_table::parse(Cursor::new(body)))
+}
+
+/// Check whether the parsed results of the query `url` report an error.
+fn check_results(
+    url: &str,
+    status: StatusCode,
+    result: Result<VOTable, vo_table::Error>,
+) -> Result<VOTable, Error> {
+    let table = result.map_err(|e| {
+        debug!("Could not parse the response from {}: {}", url, e);
+        Error::VOTable(e)
+    })?;
+    if let Some(info) = error_info(&table) {
+        return Err(Error::ServiceError {
+            code: status,
+            message: info_message(info),
+        });
+    }
+    debug!(
+        "Parsed {} records from {} (status: {:?})",
+        table.len(),
+        url,
+        query_status(&table).map(Info::value)
+    );
+    Ok(table)
+}
+
+/// The error message of `body` if it is a VOTable reporting an error, e.g.
+/// a TAP error document.
+pub fn error_message(body: &[u8]) -> Option<String> {
+    vo_table::parse_with_options(Cursor::new(body), ParseOptions::lenient())
+        .ok()
+        .and_then(|table| error_info(&table).map(info_message))
+}
+
+/// The QUERY_STATUS INFO of `table`, either at the top level or in one of
+/// its resources.
+pub fn query_status(table: &VOTable) -> Option<&Info> {
+    find_info(table, |info| info.name() == "QUERY_STATUS")
+}
+
+/// The INFO reporting an error in `table`: a QUERY_STATUS INFO with value
+/// "ERROR", as specified by DALI, or an INFO named "Error", as specified by
+/// Cone Search 1.03.
+fn error_info(table: &VOTable) -> Option<&Info> {
+    find_info(table, |info| {
+        info.name() == "Error" || (info.name() == "QUERY_STATUS" && info.value() == "ERROR")
+    })
+}
+
+/// First INFO of `table` matching `predicate`, either at the top level or
+/// in one of its resources.
+fn find_info<F: Fn(&Info) -> bool>(table: &VOTable, predicate: F) -> Option<&Info> {
+    table
+        .infos()
+        .iter()
+        .find(|info| predicate(info))
+        .or_else(|| {
+            table
+                .resources()
+                .iter()
+                .flat_map(|resource| {
+                    resource
+                        .infos()
+                        .iter()
+                        .chain(resource.children().iter().flat_map(|child| child.infos()))
+                })
+                .find(|info| predicate(info))
+        })
+}
+
+/// The error message of an INFO, in its content or else in its value.
+fn info_message(info: &Info) -> String {
+    match info.content().map(str::trim) {
+        Some(content) if !content.is_empty() => content.to_owned(),
+        _ => info.value().to_owned(),
+    }
+}
+
+/// Posted parameters, or the size of other bodies, for logging.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn describe(content_type: &str, content: &[u8]) -> String {
+    if content_type == FORM_CONTENT_TYPE {
+        String::from_utf8_lossy(content).into_owned()
+    } else {
+        format!("{} bytes of {}", content.len(), content_type)
+    }
+}
+
+/// Log the whole body of a response to `request` from `url`, and call the
+/// hook of the request.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn received(
+    request: &Request,
+    url: String,
+    parts: Parts,
+    body: Vec<u8>,
+    start: Instant,
+) -> Response {
+    debug!(
+        "Received {} bytes from {} in {:?}",
+        body.len(),
+        url,
+        start.elapsed()
+    );
+    trace!("Response from {}: {}", url, excerpt(&body));
+    call_hook(request, &parts, &body);
+    Response {
+        url,
+        status: parts.status,
+        headers: parts.headers,
+        body,
+    }
+}
+
+/// Log the body of a response to the query `request` from `url` once it is
+/// parsed, call the hook of the request, and check the results.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn parsed(
+    request: &Request,
+    url: String,
+    parts: Parts,
+    copy: BodyCopy,
+    start: Instant,
+    result: Result<VOTable, vo_table::Error>,
+) -> Result<VOTable, Error> {
+    debug!(
+        "Received {} bytes from {} in {:?}",
+        copy.len,
+        url,
+        start.elapsed()
+    );
+    trace!("Response from {}: {}", url, excerpt(&copy.body));
+    call_hook(request, &parts, &copy.body);
+    check_results(&url, parts.status, result)
+}
+
+/// Copy of a body streamed to the parser, for the logs and the hook of the
+/// request. Only the beginning of the body is kept if the request has no
+/// hook.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+#[derive(Default)]
+struct BodyCopy {
+    /// Number of bytes received
+    len: usize,
+    body: Vec<u8>,
+    whole: bool,
+}
+
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+impl BodyCopy {
+    fn new(request: &Request) -> Self {
+        BodyCopy {
+            len: 0,
+            body: Vec::new(),
+            whole: request.hook.is_some(),
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        self.len += chunk.len();
+        let kept = if self.whole {
+            chunk.len()
+        } else {
+            chunk
+                .len()
+                .min(MAX_ERROR_BODY_LEN.saturating_sub(self.body.len()))
+        };
+        self.body.extend_from_slice(&chunk[..kept]);
+    }
+}
+
+/// Body of a response, copied as it is streamed.
+#[cfg(feature = "tokio")]
+struct CopiedBody {
+    body: Body,
+    copy: Arc<Mutex<BodyCopy>>,
+}
+
+#[cfg(feature = "tokio")]
+impl Stream for CopiedBody {
+    type Item = Result<Bytes, hyper::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let chunk = ready!(Pin::new(&mut this.body).poll_data(cx));
+        if let Some(Ok(chunk)) = &chunk {
+            this.copy.lock().unwrap().push(chunk);
+        }
+        Poll::Ready(chunk)
+    }
+}
+
+/// Body of a response, copied as it is read.
+#[cfg(feature = "blocking")]
+struct CopiedReader<'a> {
+    body: &'a mut BlockingBody,
+    copy: &'a mut BodyCopy,
+}
+
+#[cfg(feature = "blocking")]
+impl Read for CopiedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.body.read(buf)?;
+        self.copy.push(&buf[..len]);
+        Ok(len)
+    }
+}
+
+fn is_redirect(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    )
+}
+
+/// Whether a redirection with `status` must be followed with the same
+/// method and body.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn keeps_method(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+    )
+}
+
+/// The URL to which a response to `url` redirects.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn redirect(url: &str, redirects: usize, headers: &HeaderMap) -> Result<String, Error> {
+    if redirects == MAX_REDIRECTS {
+        return Err(Error::TooManyRedirects(url.to_owned()));
+    }
+    let next = resolve(url, headers)?;
+    debug!("Redirected from {} to {}", url, next);
+    Ok(next)
+}
+
+/// The location of a redirection, which may be relative to `url`.
+fn resolve(url: &str, headers: &HeaderMap) -> Result<String, Error> {
+    let location = headers
+        .get(LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .ok_or_else(|| Error::InvalidRedirect(String::new()))?;
+    url::Url::parse(url)
+        .and_then(|base| base.join(location))
+        .map(url::Url::into_string)
+        .map_err(|_| Error::InvalidRedirect(location.to_owned()))
+}
+
+/// Make an error from a response with an error status.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn error_response(request: &Request, parts: &Parts, body: &[u8]) -> Error {
+    debug!("Error response from {}: {}", request.url, excerpt(body));
+    call_hook(request, parts, body);
+    let code = parts.status;
+    match error_message(body) {
+        Some(message) => Error::ServiceError { code, message },
+        None => Error::HttpStatus {
+            code,
+            body_excerpt: excerpt(body),
+        },
+    }
+}
+
+/// The content type of a successful response to a query, if it cannot be
+/// a VOTable.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn unexpected_content_type(parts: &Parts) -> Option<String> {
+    parts
+        .headers
+        .get(CONTENT_TYPE)
+        .map(|content_type| String::from_utf8_lossy(content_type.as_bytes()).into_owned())
+        .filter(|content_type| !is_votable(content_type))
+}
+
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn content_type_error(
+    request: &Request,
+    parts: &Parts,
+    content_type: String,
+    body: &[u8],
+) -> Error {
+    call_hook(request, parts, body);
+    Error::UnexpectedContentType {
+        content_type,
+        body_excerpt: excerpt(body),
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn call_hook(request: &Request, parts: &Parts, body: &[u8]) {
+    if let Some(hook) = &request.hook {
+        hook.call(&RawResponse {
+            url: &request.url,
+            status: parts.status,
+            headers: &parts.headers,
+            body,
+        });
+    }
+}
+
+/// Whether `content_type` may be the type of a VOTable. Some services still
+/// send VOTables as generic XML.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn is_votable(content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    content_type.contains("xml") || content_type.contains("votable")
+}
+
+/// Read at most about `max` bytes of `body`.
+#[cfg(feature = "tokio")]
+async fn read_prefix(mut body: Body, max: usize) -> Result<Vec<u8>, Error> {
+    let mut prefix = Vec::new();
+    while prefix.len() < max {
+        match body.data().await {
+            Some(chunk) => prefix.extend_from_slice(&chunk.map_err(Error::Hyper)?),
+            None => break,
+        }
+    }
+    Ok(prefix)
+}
+
+/// Read at most `max` bytes of `body`.
+#[cfg(feature = "blocking")]
+fn read_prefix_blocking(body: BlockingBody, max: usize) -> Result<Vec<u8>, Error> {
+    let mut prefix = Vec::new();
+    body.take(max as u64)
+        .read_to_end(&mut prefix)
+        .map_err(Error::Io)?;
+    Ok(prefix)
+}
+
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+fn excerpt(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    let body = body.trim();
+    match body.char_indices().nth(MAX_EXCERPT_LEN) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.to_owned(),
+    }
+}
@@ -0,0 +1,116 @@
+//! Configuration of the client: user agent, timeouts and proxies.
+
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use std::time::Duration;
+
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use vo_client::DEFAULT_USER_AGENT;
+use vo_client::{Error, VoClient};
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+use vo_mock::{MockService, Reply};
+
+/// Address where nothing answers. Requests to it only succeed through a
+/// proxy.
+#[cfg(any(feature = "tokio", feature = "blocking"))]
+const UNREACHABLE: &str = "http://unreachable.invalid/data?x=1";
+
+#[test]
+fn rejects_invalid_configuration() {
+    match VoClient::builder().with_user_agent("vo\nrs").build() {
+        Err(Error::InvalidConfig(_)) => (),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    match VoClient::builder()
+        .with_https_proxy("https://proxy.example.com")
+        .build()
+    {
+        Err(Error::InvalidConfig(_)) => (),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn sends_user_agent() {
+    let service = MockService::start(|_| Reply::ok(""));
+    VoClient::new().get_url(&service.url).await.unwrap();
+    VoClient::builder()
+        .with_user_agent("my-app/1.0")
+        .build()
+        .unwrap()
+        .get_url(&service.url)
+        .await
+        .unwrap();
+    // Requests may set their own
+    let request = http::Request::get(service.url.as_str())
+        .header("User-Agent", "other/2.0")
+        .body(vo_client::Body::empty())
+        .unwrap();
+    VoClient::new().request(request).await.unwrap();
+
+    let agents: Vec<_> = service
+        .requests()
+        .iter()
+        .map(|request| request.header("user-agent").unwrap().to_owned())
+        .collect();
+    assert_eq!(agents, vec![DEFAULT_USER_AGENT, "my-app/1.0", "other/2.0"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn times_out_waiting_for_response() {
+    let service = MockService::start(|_| Reply::ok("").with_delay(Duration::from_secs(2)));
+    let client = VoClient::builder()
+        .with_timeout(Some(Duration::from_millis(100)))
+        .build()
+        .unwrap();
+    match client.get_url(&service.url).await {
+        Err(Error::Timeout) => (),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn sends_requests_through_proxy() {
+    let proxy = MockService::start(|_| Reply::ok("proxied"));
+    let client = VoClient::builder()
+        .with_http_proxy(proxy.url.as_str())
+        .build()
+        .unwrap();
+    let response = client.get_url(UNREACHABLE).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let requests = proxy.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/data");
+    assert_eq!(requests[0].param("x"), Some("1"));
+    assert_eq!(requests[0].header("host"), Some("unreachable.invalid"));
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn configures_blocking_requests() {
+    let service = MockService::start(|request| match request.path.as_str() {
+        "/slow" => Reply::ok("").with_delay(Duration::from_secs(2)),
+        _ => Reply::ok(""),
+    });
+    let client = VoClient::builder()
+        .with_user_agent("my-app/1.0")
+        .with_timeout(Some(Duration::from_millis(100)))
+        .with_http_proxy(service.url.as_str())
+        .build()
+        .unwrap();
+    client.get_blocking(UNREACHABLE).unwrap();
+    VoClient::new().get_blocking(&service.url).unwrap();
+    let requests = service.requests();
+    assert_eq!(requests[0].path, "/data");
+    assert_eq!(requests[0].header("host"), Some("unreachable.invalid"));
+    assert_eq!(requests[0].header("user-agent"), Some("my-app/1.0"));
+    assert_eq!(requests[1].header("user-agent"), Some(DEFAULT_USER_AGENT));
+
+    match client.get_blocking("http://unreachable.invalid/slow") {
+        Err(Error::Timeout) => (),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
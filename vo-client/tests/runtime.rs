@@ -0,0 +1,35 @@
+//! Blocking on the runtime shared by blocking functions.
+#![cfg(feature = "tokio")]
+
+use vo_client::{Error, Runtime};
+
+#[test]
+fn blocks_outside_async_context() {
+    let runtime = Runtime::shared().unwrap();
+    assert_eq!(runtime.block_on(async { 1 + 1 }).unwrap(), 2);
+    // Clones share the runtime
+    let clone = runtime.clone();
+    drop(runtime);
+    assert_eq!(clone.block_on(async { 3 }).unwrap(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocks_worker_of_multi_threaded_runtime() {
+    let runtime = Runtime::new().unwrap();
+    let value = runtime.block_on(async {
+        tokio::task::yield_now().await;
+        42
+    });
+    assert_eq!(value.unwrap(), 42);
+    // Dropped from an asynchronous context without panicking
+    drop(runtime);
+}
+
+#[tokio::test]
+async fn refuses_to_block_single_threaded_runtime() {
+    let runtime = Runtime::new().unwrap();
+    match runtime.block_on(async { 42 }) {
+        Err(Error::BlockingInAsyncContext) => {}
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
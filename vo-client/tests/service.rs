@@ -0,0 +1,252 @@
+//! Requests to a mock service, with redirections and error responses.
+#![cfg(any(feature = "tokio", feature = "blocking"))]
+
+#[cfg(feature = "tokio")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "tokio")]
+use vo_client::service;
+use vo_client::service::Request;
+#[cfg(feature = "tokio")]
+use vo_client::ResponseHook;
+use vo_client::{Error, VoClient};
+use vo_mock::{MockService, Reply};
+
+const RESULTS: &str = r#"<?xml version="1.0"?>
+<VOTABLE version="1.4">
+  <RESOURCE type="results">
+    <INFO name="QUERY_STATUS" value="OVERFLOW"/>
+    <TABLE>
+      <FIELD name="name" datatype="char" arraysize="*"/>
+      <DATA><TABLEDATA><TR><TD>alpha</TD></TR></TABLEDATA></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#;
+
+fn error_document(status: &str, message: &str) -> String {
+    format!(
+        r#"<VOTABLE version="1.4">
+  <RESOURCE type="results">
+    <INFO name="{}" value="ERROR">{}</INFO>
+  </RESOURCE>
+</VOTABLE>"#,
+        status, message
+    )
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn follows_relative_redirects() {
+    let service = MockService::start(|request| match request.path.as_str() {
+        "/query" => Reply::see_other("results/1"),
+        "/results/1" => Reply::status(302).with_header("Location", "/results/2"),
+        _ => Reply::votable(RESULTS),
+    });
+    let table = VoClient::new()
+        .query(&Request::get(service.endpoint("/query")))
+        .await
+        .unwrap();
+    assert_eq!(service::query_status(&table).unwrap().value(), "OVERFLOW");
+    let paths: Vec<_> = service
+        .requests()
+        .into_iter()
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(paths, vec!["/query", "/results/1", "/results/2"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn repeats_posts_only_for_307_and_308() {
+    let service = MockService::start(|request| match request.path.as_str() {
+        "/sync" => Reply::status(307).with_header("Location", "/sync2"),
+        "/sync2" => Reply::see_other("/results"),
+        _ => Reply::votable(RESULTS),
+    });
+    let request = Request::post_form(service.endpoint("/sync"), vec![("QUERY", "SELECT 1")]);
+    VoClient::new().query(&request).await.unwrap();
+
+    let requests = service.requests();
+    let methods: Vec<_> = requests.iter().map(|req| req.method.as_str()).collect();
+    assert_eq!(methods, vec!["POST", "POST", "GET"]);
+    assert_eq!(requests[1].param("QUERY"), Some("SELECT 1"));
+    assert_eq!(requests[2].param("QUERY"), None);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stops_after_too_many_redirects() {
+    let service = MockService::start(|_| Reply::see_other("/loop"));
+    let url = service.endpoint("/loop");
+    match VoClient::new().send(&Request::get(url.as_str())).await {
+        Err(Error::TooManyRedirects(from)) => assert_eq!(from, url),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    assert_eq!(service.requests().len(), 11);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn returns_redirects_when_not_followed() {
+    let service = MockService::start(|_| Reply::see_other("jobs/1"));
+    let request = Request::post_form(service.endpoint("/jobs"), vec![("PHASE", "RUN")]);
+    let response = VoClient::new()
+        .send(&request.without_redirects())
+        .await
+        .unwrap();
+    assert_eq!(response.status.as_u16(), 303);
+    assert_eq!(
+        response.location().unwrap(),
+        Some(service.endpoint("/jobs/1"))
+    );
+    assert_eq!(service.requests().len(), 1);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn reports_error_responses() {
+    let service = MockService::start(|request| match request.path.as_str() {
+        "/document" => {
+            Reply::votable(error_document("QUERY_STATUS", "No such table")).with_status(400)
+        }
+        "/text" => Reply::text(format!("  {}  ", "x".repeat(1000))).with_status(500),
+        _ => Reply::votable(error_document("Error", "Invalid radius")),
+    });
+    let client = VoClient::new();
+    match client
+        .query(&Request::get(service.endpoint("/document")))
+        .await
+    {
+        Err(Error::ServiceError { code, message }) => {
+            assert_eq!(code.as_u16(), 400);
+            assert_eq!(message, "No such table");
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    match client.query(&Request::get(service.endpoint("/text"))).await {
+        Err(Error::HttpStatus { code, body_excerpt }) => {
+            assert_eq!(code.as_u16(), 500);
+            assert_eq!(body_excerpt, format!("{}...", "x".repeat(512)));
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    // Cone search services report errors with a successful status
+    match client.query(&Request::get(service.endpoint("/scs"))).await {
+        Err(Error::ServiceError { code, message }) => {
+            assert_eq!(code.as_u16(), 200);
+            assert_eq!(message, "Invalid radius");
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn rejects_other_content_types() {
+    let service = MockService::start(|_| {
+        Reply::ok("<html>Maintenance</html>").with_header("Content-Type", "text/html")
+    });
+    match VoClient::new()
+        .query(&Request::get(service.endpoint("/query")))
+        .await
+    {
+        Err(Error::UnexpectedContentType {
+            content_type,
+            body_excerpt,
+        }) => {
+            assert_eq!(content_type, "text/html");
+            assert_eq!(body_excerpt, "<html>Maintenance</html>");
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn calls_response_hook() {
+    let service = MockService::start(|request| match request.path.as_str() {
+        "/missing" => Reply::text("Not found").with_status(404),
+        _ => Reply::votable(RESULTS),
+    });
+    let responses = Arc::new(Mutex::new(Vec::new()));
+    let hook = {
+        let responses = responses.clone();
+        ResponseHook::new(move |response| {
+            responses.lock().unwrap().push((
+                response.url.to_owned(),
+                response.status.as_u16(),
+                response.body.to_vec(),
+            ))
+        })
+    };
+    let client = VoClient::new();
+    let request = Request::get(service.endpoint("/query")).with_response_hook(Some(hook.clone()));
+    client.query(&request).await.unwrap();
+    let request = Request::get(service.endpoint("/missing")).with_response_hook(Some(hook));
+    client.query(&request).await.unwrap_err();
+
+    let responses = responses.lock().unwrap();
+    assert_eq!(
+        *responses,
+        vec![
+            (service.endpoint("/query"), 200, RESULTS.as_bytes().to_vec()),
+            (service.endpoint("/missing"), 404, b"Not found".to_vec()),
+        ]
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn streams_large_results() {
+    let rows = "<TR><TD>alpha</TD></TR>".repeat(10_000);
+    let results = RESULTS.replace("<TR><TD>alpha</TD></TR>", &rows);
+    let service = MockService::start({
+        let results = results.clone();
+        move |_| Reply::votable(results.clone())
+    });
+    let received = Arc::new(Mutex::new(0));
+    let hook = {
+        let received = received.clone();
+        ResponseHook::new(move |response| *received.lock().unwrap() = response.body.len())
+    };
+    let request = Request::get(service.endpoint("/query")).with_response_hook(Some(hook));
+    let table = VoClient::new().query(&request).await.unwrap();
+    assert_eq!(table.len(), 10_000);
+    // The hook gets the whole body, which was streamed to the parser
+    assert_eq!(*received.lock().unwrap(), results.len());
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn sends_blocking_requests() {
+    let service = MockService::start(|request| match request.path.as_str() {
+        "/sync" => Reply::see_other("/results"),
+        "/results" => Reply::votable(RESULTS),
+        _ => Reply::votable(error_document("QUERY_STATUS", "No such table")).with_status(400),
+    });
+    let client = VoClient::new();
+    let request = Request::post_form(service.endpoint("/sync"), vec![("QUERY", "SELECT 1")]);
+    let table = client.query_blocking(&request).unwrap();
+    assert_eq!(table.len(), 1);
+    match client.query_blocking(&Request::get(service.endpoint("/missing"))) {
+        Err(Error::ServiceError { code, message }) => {
+            assert_eq!(code.as_u16(), 400);
+            assert_eq!(message, "No such table");
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[cfg(all(feature = "tokio", not(feature = "rustls-tls")))]
+#[tokio::test]
+async fn rejects_https_without_tls() {
+    let service = MockService::start(|_| Reply::votable(RESULTS));
+    let url = service.url.replace("http:", "https:");
+    let err = VoClient::new().get_url(&url).await.unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("HTTPS is not supported"),
+        "{:?}",
+        err
+    );
+    assert!(service.requests().is_empty());
+}
@@ -0,0 +1,11 @@
+use std::env;
+
+use vo_coords::{parse_target, Sesame};
+
+fn main() {
+    let input = env::args().skip(1).collect::<Vec<_>>().join(" ");
+    match parse_target(&input, &Sesame::new()) {
+        Ok(coord) => println!("RA {} Dec {}", coord.ra(), coord.dec()),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
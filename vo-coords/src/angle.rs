@@ -0,0 +1,80 @@
+//! Parsing of angles in decimal or sexagesimal notation.
+
+use crate::Error;
+
+/// Unit of the first component of a sexagesimal angle without unit
+/// markers, such as "10:44:06.6" or "10 44 06.6".
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Sexagesimal {
+    Hours,
+    Degrees,
+}
+
+/// Parse an angle and return its value in degrees.
+///
+/// Accepted notations are decimal degrees ("161.0275"), hours with markers
+/// ("10h44m06.6s", "10.735h"), degrees with markers ("-59d41m04s",
+/// "-59°41′04″", "-59°41'04\"") and sexagesimal components separated by
+/// colons or spaces ("10:44:06.6", "-59 41 04"), whose first component is
+/// in `sexagesimal` units.
+pub(crate) fn parse(s: &str, sexagesimal: Sexagesimal) -> Result<f64, Error> {
+    let invalid = || Error::InvalidCoord(format!("Invalid angle {:?}", s));
+
+    let s = s.trim();
+    let (negative, unsigned) = match s.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let mut hours = false;
+    let mut markers = false;
+    let mut separators = false;
+    let mut normalized = String::with_capacity(unsigned.len());
+    for c in unsigned.chars() {
+        match c {
+            'h' | 'H' => {
+                hours = true;
+                markers = true;
+                normalized.push(' ');
+            }
+            'd' | 'D' | '°' | 'm' | 'M' | 's' | 'S' | '\'' | '"' | '′' | '″' => {
+                markers = true;
+                normalized.push(' ');
+            }
+            ':' | ' ' | '\t' => {
+                separators = true;
+                normalized.push(' ');
+            }
+            '+' | '-' => return Err(invalid()),
+            c => normalized.push(c),
+        }
+    }
+
+    let components = normalized
+        .split_whitespace()
+        .map(|component| component.parse::<f64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    if components.is_empty() || components.len() > 3 {
+        return Err(invalid());
+    }
+    if components.iter().any(|value| !value.is_finite()) {
+        return Err(invalid());
+    }
+    if components[1..]
+        .iter()
+        .any(|value| !(0.0..60.0).contains(value))
+    {
+        return Err(invalid());
+    }
+    let unit = if hours || (!markers && separators && sexagesimal == Sexagesimal::Hours) {
+        15.0
+    } else {
+        1.0
+    };
+    let value = components
+        .iter()
+        .zip(&[1.0, 60.0, 3600.0])
+        .map(|(component, divisor)| component / divisor)
+        .sum::<f64>()
+        * unit;
+    Ok(if negative { -value } else { value })
+}
@@ -0,0 +1,31 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not a valid position.
+    InvalidCoord(String),
+    UnknownFrame(String),
+    /// The name resolver does not know the target.
+    NotFound(String),
+    /// The name resolver failed, e.g. because the service is not available.
+    Resolver(String),
+    #[cfg(feature = "sesame")]
+    Client(vo_client::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+        match self {
+            InvalidCoord(msg) => write!(f, "Invalid coordinates. {}", msg),
+            UnknownFrame(frame) => write!(f, "Unknown frame '{}'", frame),
+            NotFound(name) => write!(f, "Could not resolve '{}'", name),
+            Resolver(msg) => write!(f, "Name resolver error. {}", msg),
+            #[cfg(feature = "sesame")]
+            Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
@@ -0,0 +1,467 @@
+//! Celestial reference frames, and transformations between them.
+//!
+//! Transformations follow the Explanatory Supplement to the Astronomical
+//! Almanac, as implemented by astropy: IAU 1976 precession for FK5 and
+//! ecliptic coordinates, Newcomb's precession and the E-terms of
+//! aberration for FK4, and the Hipparcos definition of galactic
+//! coordinates. Proper motions are assumed to be zero.
+
+use std::str::FromStr;
+
+use crate::Error;
+
+/// Reference frame of coordinates.
+///
+/// Equinoxes are in Julian years for FK5 and ecliptic coordinates, and in
+/// Besselian years for FK4.
+///
+/// ```
+/// # extern crate vo_coords;
+/// # use vo_coords::Frame;
+/// # fn main() {
+/// // The galactic center, in FK4 B1950
+/// let (ra, dec) = Frame::Galactic.transform(Frame::FK4_B1950, 0.0, 0.0);
+/// assert!((ra - 265.6108).abs() < 1e-3 && (dec + 28.9167).abs() < 1e-3);
+///
+/// // The north ecliptic pole
+/// let (ra, dec) = Frame::ECLIPTIC_J2000.to_icrs(0.0, 90.0);
+/// assert!((ra - 270.0).abs() < 1e-3 && (dec - 66.5607).abs() < 1e-3);
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Frame {
+    ICRS,
+    FK5 {
+        equinox: f64,
+    },
+    FK4 {
+        equinox: f64,
+    },
+    Galactic,
+    Supergalactic,
+    /// Mean ecliptic and equinox
+    Ecliptic {
+        equinox: f64,
+    },
+}
+
+type Vector = [f64; 3];
+type Matrix = [[f64; 3]; 3];
+
+/// Rotation from ICRS to galactic coordinates, from the Hipparcos
+/// catalogue (ESA 1997, Vol. 1, §1.5.3).
+const ICRS_TO_GALACTIC: Matrix = [
+    [
+        -0.054_875_560_416_215_4,
+        -0.873_437_090_234_885,
+        -0.483_835_015_548_713_2,
+    ],
+    [
+        0.494_109_427_875_583_7,
+        -0.444_829_629_960_011_2,
+        0.746_982_244_497_219,
+    ],
+    [
+        -0.867_666_149_019_004_7,
+        -0.198_076_373_431_201_5,
+        0.455_983_776_175_066_9,
+    ],
+];
+
+/// Rotation from FK4 B1950 without E-terms to FK5 J2000 (Standish 1982).
+const B1950_TO_J2000: Matrix = [
+    [
+        0.999_925_679_495_687_7,
+        -0.011_181_483_220_466_2,
+        -0.004_859_003_815_359_2,
+    ],
+    [
+        0.011_181_483_239_171_7,
+        0.999_937_484_893_313_5,
+        -0.000_027_162_594_714_2,
+    ],
+    [
+        0.004_859_003_772_314_3,
+        -0.000_027_170_293_744,
+        0.999_988_194_602_374_2,
+    ],
+];
+
+/// Galactic coordinates of the north supergalactic pole (de Vaucouleurs
+/// et al. 1991).
+const SUPERGALACTIC_POLE: (f64, f64) = (47.37, 6.32);
+
+const JD_J2000: f64 = 2_451_545.0;
+const JD_B1950: f64 = 2_433_282.423_5;
+
+impl Frame {
+    pub const FK5_J2000: Frame = Frame::FK5 { equinox: 2000.0 };
+    pub const FK4_B1950: Frame = Frame::FK4 { equinox: 1950.0 };
+    pub const ECLIPTIC_J2000: Frame = Frame::Ecliptic { equinox: 2000.0 };
+
+    /// Frame of a COOSYS element of a VOTable, from its `system` and
+    /// `equinox` attributes, e.g. "eq_FK4" and "B1950".
+    ///
+    /// ```
+    /// # extern crate vo_coords;
+    /// # use vo_coords::Frame;
+    /// # fn main() {
+    /// assert_eq!(Frame::from_coosys("eq_FK5", None).unwrap(), Frame::FK5_J2000);
+    /// assert_eq!(
+    ///     Frame::from_coosys("eq_FK4", Some("B1900")).unwrap(),
+    ///     Frame::FK4 { equinox: 1900.0 }
+    /// );
+    /// assert!(Frame::from_coosys("geo_app", None).is_err());
+    /// # }
+    /// ```
+    pub fn from_coosys(system: &str, equinox: Option<&str>) -> Result<Self, Error> {
+        let frame = match system {
+            "ICRS" => Frame::ICRS,
+            "eq_FK5" => Frame::FK5_J2000,
+            "eq_FK4" => Frame::FK4_B1950,
+            "ecl_FK5" => Frame::ECLIPTIC_J2000,
+            "galactic" | "galactic_II" => Frame::Galactic,
+            "supergalactic" => Frame::Supergalactic,
+            _ => return Err(Error::UnknownFrame(system.to_owned())),
+        };
+        match equinox {
+            Some(equinox) if frame.has_equinox() => frame.with_equinox(equinox),
+            _ => Ok(frame),
+        }
+    }
+
+    /// Same frame at `equinox`, e.g. "J1975" or "B1900". Years without a
+    /// prefix are Besselian for FK4 and Julian otherwise.
+    ///
+    /// Fails for frames without an equinox.
+    ///
+    /// ```
+    /// # extern crate vo_coords;
+    /// # use vo_coords::Frame;
+    /// # fn main() {
+    /// let fk5 = Frame::FK5_J2000.with_equinox("J2050").unwrap();
+    /// // Precession over 50 years
+    /// let (ra, dec) = Frame::FK5_J2000.transform(fk5, 0.0, 0.0);
+    /// assert!((ra - 0.6404).abs() < 1e-3 && (dec - 0.2784).abs() < 1e-3);
+    ///
+    /// assert!(Frame::Galactic.with_equinox("J2000").is_err());
+    /// # }
+    /// ```
+    pub fn with_equinox(self, equinox: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidCoord(format!("Invalid equinox {:?}", equinox));
+
+        let (besselian, year) = match equinox.chars().next() {
+            Some('J') | Some('j') => (false, &equinox[1..]),
+            Some('B') | Some('b') => (true, &equinox[1..]),
+            _ => (matches!(self, Frame::FK4 { .. }), equinox),
+        };
+        let year = year.parse::<f64>().map_err(|_| invalid())?;
+        if !year.is_finite() {
+            return Err(invalid());
+        }
+        let (julian, besselian) = if besselian {
+            (julian_year(besselian_jd(year)), year)
+        } else {
+            (year, besselian_year(julian_jd(year)))
+        };
+        match self {
+            Frame::FK5 { .. } => Ok(Frame::FK5 { equinox: julian }),
+            Frame::FK4 { .. } => Ok(Frame::FK4 { equinox: besselian }),
+            Frame::Ecliptic { .. } => Ok(Frame::Ecliptic { equinox: julian }),
+            _ => Err(Error::InvalidCoord(format!(
+                "Frame {:?} has no equinox",
+                self
+            ))),
+        }
+    }
+
+    /// Convert `(longitude, latitude)` in this frame to ICRS right
+    /// ascension and declination, all in degrees.
+    pub fn to_icrs(self, longitude: f64, latitude: f64) -> (f64, f64) {
+        if self == Frame::ICRS {
+            return (longitude, latitude);
+        }
+        to_spherical(self.vector_to_icrs(to_cartesian(longitude, latitude)))
+    }
+
+    /// Convert ICRS `(ra, dec)` to longitude and latitude in this frame,
+    /// all in degrees.
+    pub fn from_icrs(self, ra: f64, dec: f64) -> (f64, f64) {
+        if self == Frame::ICRS {
+            return (ra, dec);
+        }
+        to_spherical(self.vector_from_icrs(to_cartesian(ra, dec)))
+    }
+
+    /// Convert `(longitude, latitude)` in this frame to the frame `to`.
+    pub fn transform(self, to: Frame, longitude: f64, latitude: f64) -> (f64, f64) {
+        if self == to {
+            return (longitude, latitude);
+        }
+        let icrs = self.vector_to_icrs(to_cartesian(longitude, latitude));
+        to_spherical(to.vector_from_icrs(icrs))
+    }
+
+    /// Whether sexagesimal longitudes without unit markers are in hours
+    /// rather than degrees.
+    pub(crate) fn hours(self) -> bool {
+        matches!(self, Frame::ICRS | Frame::FK5 { .. } | Frame::FK4 { .. })
+    }
+
+    pub(crate) fn has_equinox(self) -> bool {
+        matches!(
+            self,
+            Frame::FK5 { .. } | Frame::FK4 { .. } | Frame::Ecliptic { .. }
+        )
+    }
+
+    fn vector_to_icrs(self, v: Vector) -> Vector {
+        match self {
+            Frame::ICRS => v,
+            Frame::FK5 { equinox } => {
+                let j2000 = mul(&fk5_precession(equinox, 2000.0), v);
+                mul_transposed(&icrs_to_fk5(), j2000)
+            }
+            Frame::FK4 { equinox } => {
+                let v = remove_e_terms(v, equinox);
+                let b1950 = mul(&fk4_precession(equinox, 1950.0), v);
+                Frame::FK5_J2000.vector_to_icrs(mul(&B1950_TO_J2000, b1950))
+            }
+            Frame::Galactic => mul_transposed(&ICRS_TO_GALACTIC, v),
+            Frame::Supergalactic => {
+                Frame::Galactic.vector_to_icrs(mul_transposed(&galactic_to_supergalactic(), v))
+            }
+            Frame::Ecliptic { equinox } => {
+                let equatorial = mul_transposed(&rotation_x(obliquity(julian_jd(equinox))), v);
+                Frame::FK5 { equinox }.vector_to_icrs(equatorial)
+            }
+        }
+    }
+
+    fn vector_from_icrs(self, v: Vector) -> Vector {
+        match self {
+            Frame::ICRS => v,
+            Frame::FK5 { equinox } => {
+                let j2000 = mul(&icrs_to_fk5(), v);
+                mul(&fk5_precession(2000.0, equinox), j2000)
+            }
+            Frame::FK4 { equinox } => {
+                let j2000 = Frame::FK5_J2000.vector_from_icrs(v);
+                let b1950 = mul_transposed(&B1950_TO_J2000, j2000);
+                add_e_terms(mul(&fk4_precession(1950.0, equinox), b1950), equinox)
+            }
+            Frame::Galactic => mul(&ICRS_TO_GALACTIC, v),
+            Frame::Supergalactic => mul(
+                &galactic_to_supergalactic(),
+                Frame::Galactic.vector_from_icrs(v),
+            ),
+            Frame::Ecliptic { equinox } => {
+                let equatorial = Frame::FK5 { equinox }.vector_from_icrs(v);
+                mul(&rotation_x(obliquity(julian_jd(equinox))), equatorial)
+            }
+        }
+    }
+}
+
+/// Parse the name of a frame, case-insensitively: "icrs", "fk5" or
+/// "j2000", "fk4" or "b1950", "galactic" or "gal", "supergalactic" or
+/// "sgal", "ecliptic" or "ecl". FK5 and ecliptic coordinates are at
+/// equinox J2000, FK4 coordinates at equinox B1950.
+impl FromStr for Frame {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "icrs" => Ok(Frame::ICRS),
+            "fk5" | "j2000" => Ok(Frame::FK5_J2000),
+            "fk4" | "b1950" => Ok(Frame::FK4_B1950),
+            "galactic" | "gal" => Ok(Frame::Galactic),
+            "supergalactic" | "sgal" => Ok(Frame::Supergalactic),
+            "ecliptic" | "ecl" => Ok(Frame::ECLIPTIC_J2000),
+            _ => Err(Error::UnknownFrame(s.to_owned())),
+        }
+    }
+}
+
+/// Frame bias from ICRS to FK5 J2000 (Feissel & Mignard 1998).
+fn icrs_to_fk5() -> Matrix {
+    let mas = |value: f64| (value / 3_600_000.0).to_radians();
+    let (eta0, xi0, da0) = (mas(-19.9), mas(9.1), mas(-22.9));
+    product(&[rotation_x(-eta0), rotation_y(xi0), rotation_z(da0)])
+}
+
+/// IAU 1976 precession between Julian equinoxes (Lieske et al. 1977).
+fn fk5_precession(from: f64, to: f64) -> Matrix {
+    let big_t = (from - 2000.0) / 100.0;
+    let t = (to - from) / 100.0;
+    let arcsec = |value: f64| (value / 3600.0).to_radians();
+    let rate = 2306.2181 + 1.39656 * big_t - 0.000139 * big_t * big_t;
+    let zeta = arcsec(rate * t + (0.30188 - 0.000344 * big_t) * t * t + 0.017998 * t * t * t);
+    let z = arcsec(rate * t + (1.09468 + 0.000066 * big_t) * t * t + 0.018203 * t * t * t);
+    let theta = arcsec(
+        (2004.3109 - 0.85330 * big_t - 0.000217 * big_t * big_t) * t
+            - (0.42665 + 0.000217 * big_t) * t * t
+            - 0.041833 * t * t * t,
+    );
+    product(&[rotation_z(-z), rotation_y(theta), rotation_z(-zeta)])
+}
+
+/// Newcomb's precession between Besselian equinoxes, as used for FK4
+/// (Andoyer 1911).
+fn fk4_precession(from: f64, to: f64) -> Matrix {
+    let t1 = (from - 1850.0) / 1000.0;
+    let dt = (to - from) / 1000.0;
+    let angle = |c1: f64, c2: f64, c3: f64| (((c3 * dt + c2) * dt + c1) * dt / 3600.0).to_radians();
+    let rate = 23035.545 + 139.720 * t1 + 0.060 * t1 * t1;
+    let zeta = angle(rate, 30.240 - 0.27 * t1, 17.995);
+    let z = angle(rate, 109.480 + 0.39 * t1, 18.325);
+    let theta = angle(
+        20051.12 - 85.29 * t1 - 0.37 * t1 * t1,
+        -42.65 - 0.37 * t1,
+        -41.8,
+    );
+    product(&[rotation_z(-z), rotation_y(theta), rotation_z(-zeta)])
+}
+
+/// E-terms of aberration included in FK4 positions at the Besselian
+/// `equinox`.
+fn e_terms(equinox: f64) -> Vector {
+    let jd = besselian_jd(equinox);
+    let t = (jd - JD_B1950) / 36525.0;
+    let k = 0.005_693_2_f64.to_radians();
+    let eccentricity = (-0.000_000_126 * t - 0.000_041_93) * t + 0.016_730_11;
+    let perigee = ((((0.012 * t + 1.65) * t + 6190.67) * t + 1_015_489.951) / 3600.0).to_radians();
+    let obliquity = obliquity(jd);
+    let ek = eccentricity * k;
+    [
+        ek * perigee.sin(),
+        -ek * perigee.cos() * obliquity.cos(),
+        -ek * perigee.cos() * obliquity.sin(),
+    ]
+}
+
+fn remove_e_terms(v: Vector, equinox: f64) -> Vector {
+    let a = e_terms(equinox);
+    let projection = dot(a, v);
+    normalize([
+        v[0] - a[0] + projection * v[0],
+        v[1] - a[1] + projection * v[1],
+        v[2] - a[2] + projection * v[2],
+    ])
+}
+
+/// Inverse of `remove_e_terms`, by fixed-point iteration.
+fn add_e_terms(v: Vector, equinox: f64) -> Vector {
+    let a = e_terms(equinox);
+    let mut result = v;
+    for _ in 0..10 {
+        let scale = 1.0 + dot(a, result);
+        result = [
+            (a[0] + v[0]) / scale,
+            (a[1] + v[1]) / scale,
+            (a[2] + v[2]) / scale,
+        ];
+    }
+    normalize(result)
+}
+
+fn galactic_to_supergalactic() -> Matrix {
+    let (l, b) = SUPERGALACTIC_POLE;
+    product(&[
+        rotation_z(90f64.to_radians()),
+        rotation_y((90.0 - b).to_radians()),
+        rotation_z(l.to_radians()),
+    ])
+}
+
+/// Mean obliquity of the ecliptic at the Julian date `jd`, in radians
+/// (IAU 1980).
+fn obliquity(jd: f64) -> f64 {
+    let t = (jd - JD_J2000) / 36525.0;
+    ((((0.001_813 * t - 0.000_59) * t - 46.815) * t + 84_381.448) / 3600.0).to_radians()
+}
+
+fn julian_jd(year: f64) -> f64 {
+    JD_J2000 + (year - 2000.0) * 365.25
+}
+
+fn julian_year(jd: f64) -> f64 {
+    2000.0 + (jd - JD_J2000) / 365.25
+}
+
+fn besselian_jd(year: f64) -> f64 {
+    2_415_020.313_52 + (year - 1900.0) * 365.242_198_781
+}
+
+fn besselian_year(jd: f64) -> f64 {
+    1900.0 + (jd - 2_415_020.313_52) / 365.242_198_781
+}
+
+/// Rotation of the axes by `angle` radians around the x axis.
+fn rotation_x(angle: f64) -> Matrix {
+    let (s, c) = angle.sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, c, s], [0.0, -s, c]]
+}
+
+/// Rotation of the axes by `angle` radians around the y axis.
+fn rotation_y(angle: f64) -> Matrix {
+    let (s, c) = angle.sin_cos();
+    [[c, 0.0, -s], [0.0, 1.0, 0.0], [s, 0.0, c]]
+}
+
+/// Rotation of the axes by `angle` radians around the z axis.
+fn rotation_z(angle: f64) -> Matrix {
+    let (s, c) = angle.sin_cos();
+    [[c, s, 0.0], [-s, c, 0.0], [0.0, 0.0, 1.0]]
+}
+
+/// Product of `matrices`, the last one being applied first.
+fn product(matrices: &[Matrix]) -> Matrix {
+    let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    matrices.iter().fold(identity, |a, b| {
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+            }
+        }
+        m
+    })
+}
+
+fn mul(m: &Matrix, v: Vector) -> Vector {
+    [dot(m[0], v), dot(m[1], v), dot(m[2], v)]
+}
+
+/// Inverse rotation, by the transposed matrix
+fn mul_transposed(m: &Matrix, v: Vector) -> Vector {
+    [
+        m[0][0] * v[0] + m[1][0] * v[1] + m[2][0] * v[2],
+        m[0][1] * v[0] + m[1][1] * v[1] + m[2][1] * v[2],
+        m[0][2] * v[0] + m[1][2] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: Vector) -> Vector {
+    let norm = dot(v, v).sqrt();
+    [v[0] / norm, v[1] / norm, v[2] / norm]
+}
+
+fn to_cartesian(longitude: f64, latitude: f64) -> Vector {
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    [
+        latitude.cos() * longitude.cos(),
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+    ]
+}
+
+fn to_spherical(v: Vector) -> (f64, f64) {
+    let longitude = v[1].atan2(v[0]).to_degrees().rem_euclid(360.0);
+    let latitude = v[2].atan2(v[0].hypot(v[1])).to_degrees();
+    (longitude, latitude)
+}
@@ -0,0 +1,213 @@
+//! Parsing of celestial coordinates typed by users, and resolution of
+//! target names.
+//!
+//! Positions are parsed from decimal degrees or sexagesimal notation, in
+//! any supported [`Frame`](enum.Frame.html), and converted to ICRS:
+//!
+//! ```
+//! # extern crate vo_coords;
+//! # use vo_coords::Coord;
+//! # fn main() {
+//! let eta_car: Coord = "10h44m06.6s -59d41m04s".parse().unwrap();
+//! assert!((eta_car.ra() - 161.0275).abs() < 1e-9);
+//! assert!((eta_car.dec() + 59.684_444).abs() < 1e-6);
+//!
+//! for input in &[
+//!     "10:44:06.6 -59:41:04",
+//!     "10 44 06.6, -59 41 04",
+//!     "10h44m06.6s, -59°41′04″",
+//!     "161.0275 -59.684444",
+//! ] {
+//!     let coord: Coord = input.parse().unwrap();
+//!     assert!((coord.ra() - eta_car.ra()).abs() < 1e-5, "{}", input);
+//!     assert!((coord.dec() - eta_car.dec()).abs() < 1e-5, "{}", input);
+//! }
+//!
+//! let galactic_center: Coord = "galactic 0 0".parse().unwrap();
+//! assert!((galactic_center.ra() - 266.405).abs() < 1e-3);
+//! assert!((galactic_center.dec() + 28.936).abs() < 1e-3);
+//!
+//! // Equinoxes follow the name of the frame
+//! let fk4: Coord = "fk4 B1950 17h42m26.6s -28d55m00s".parse().unwrap();
+//! assert!((fk4.ra() - galactic_center.ra()).abs() < 1e-3);
+//! assert!((fk4.dec() - galactic_center.dec()).abs() < 1e-3);
+//! # }
+//! ```
+//!
+//! Names of objects are resolved by a [`NameResolver`](trait.NameResolver.html),
+//! such as [`Sesame`](struct.Sesame.html) with the `sesame` feature enabled
+//! by default.
+
+mod angle;
+mod err;
+mod frame;
+mod resolve;
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::angle::Sexagesimal;
+
+pub use crate::err::Error;
+pub use crate::frame::Frame;
+pub use crate::resolve::NameResolver;
+#[cfg(feature = "sesame")]
+pub use crate::resolve::Sesame;
+
+/// Position in ICRS, in degrees.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Coord {
+    ra: f64,
+    dec: f64,
+}
+
+impl Coord {
+    /// Position at right ascension `ra` and declination `dec`. `ra` is
+    /// wrapped to [0, 360[.
+    pub fn new(ra: f64, dec: f64) -> Result<Self, Error> {
+        if !ra.is_finite() {
+            return Err(Error::InvalidCoord(format!(
+                "Invalid right ascension {}",
+                ra
+            )));
+        }
+        if !(-90.0..=90.0).contains(&dec) {
+            return Err(Error::InvalidCoord(format!(
+                "Declination must be in [-90, 90], got {}",
+                dec
+            )));
+        }
+        Ok(Coord {
+            ra: ra.rem_euclid(360.0),
+            dec,
+        })
+    }
+
+    /// Position at `longitude` and `latitude` in `frame`, in degrees.
+    pub fn from_frame(frame: Frame, longitude: f64, latitude: f64) -> Result<Self, Error> {
+        Coord::new(longitude, latitude)?;
+        let (ra, dec) = frame.to_icrs(longitude, latitude);
+        Coord::new(ra, dec)
+    }
+
+    /// Longitude and latitude of this position in `frame`, in degrees.
+    pub fn to_frame(&self, frame: Frame) -> (f64, f64) {
+        frame.from_icrs(self.ra, self.dec)
+    }
+
+    /// Right ascension, in degrees.
+    pub fn ra(&self) -> f64 {
+        self.ra
+    }
+
+    /// Declination, in degrees.
+    pub fn dec(&self) -> f64 {
+        self.dec
+    }
+}
+
+/// Parse a position, optionally preceded by the name of its frame and by
+/// its equinox, e.g. "fk5 J1975 10:44:06.6 -59:41:04".
+///
+/// The longitude and latitude are separated by spaces or a comma, and each
+/// is in decimal degrees or in sexagesimal notation. Sexagesimal right
+/// ascensions are in hours, unless marked with "d" or "°".
+impl FromStr for Coord {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidCoord(format!("Invalid position {:?}", s));
+
+        let s = s.trim();
+        let (mut frame, mut position) = match s.split_whitespace().next() {
+            Some(word) if word.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                (word.parse::<Frame>()?, s[word.len()..].trim_start())
+            }
+            _ => (Frame::ICRS, s),
+        };
+        if frame.has_equinox() {
+            if let Some(word) = position.split_whitespace().next() {
+                let mut chars = word.chars();
+                if matches!(chars.next(), Some('J' | 'j' | 'B' | 'b'))
+                    && chars.next().is_some_and(|c| c.is_ascii_digit())
+                {
+                    frame = frame.with_equinox(word)?;
+                    position = position[word.len()..].trim_start();
+                }
+            }
+        }
+        let sexagesimal = if frame.hours() {
+            Sexagesimal::Hours
+        } else {
+            Sexagesimal::Degrees
+        };
+
+        let (longitude, latitude) = match position.find(',') {
+            Some(index) => (&position[..index], &position[index + 1..]),
+            None => {
+                // Split before the sign of the latitude, or else split the
+                // components evenly, e.g. "10 44 06.6 59 41 04"
+                let starts: Vec<usize> = position
+                    .char_indices()
+                    .filter(|&(index, c)| {
+                        !c.is_whitespace()
+                            && (index == 0 || position[..index].ends_with(char::is_whitespace))
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+                let middle = starts
+                    .iter()
+                    .skip(1)
+                    .find(|&&index| position[index..].starts_with(['+', '-']))
+                    .or_else(|| {
+                        if starts.len().is_multiple_of(2) {
+                            starts.get(starts.len() / 2)
+                        } else {
+                            None
+                        }
+                    })
+                    .ok_or_else(invalid)?;
+                (&position[..*middle], &position[*middle..])
+            }
+        };
+        let longitude = angle::parse(longitude, sexagesimal)?;
+        let latitude = angle::parse(latitude, Sexagesimal::Degrees)?;
+        if !(0.0..=360.0).contains(&longitude) {
+            return Err(Error::InvalidCoord(format!(
+                "Longitude must be in [0, 360], got {}",
+                longitude
+            )));
+        }
+        Coord::from_frame(frame, longitude, latitude)
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.ra, self.dec)
+    }
+}
+
+/// Parse `input` as a position, or else resolve it as the name of an
+/// object with `resolver`.
+///
+/// ```
+/// # extern crate vo_coords;
+/// # use vo_coords::{parse_target, Coord, Error};
+/// # fn main() {
+/// let catalogue = |name: &str| match name {
+///     "Eta Car" => Coord::new(161.0275, -59.684444),
+///     _ => Err(Error::NotFound(name.to_owned())),
+/// };
+/// let by_name = parse_target("Eta Car", &catalogue).unwrap();
+/// let by_position = parse_target("161.0275 -59.684444", &catalogue).unwrap();
+/// assert_eq!(by_name, by_position);
+/// # }
+/// ```
+pub fn parse_target<R: NameResolver + ?Sized>(input: &str, resolver: &R) -> Result<Coord, Error> {
+    match input.parse() {
+        Ok(coord) => Ok(coord),
+        Err(Error::InvalidCoord(_)) | Err(Error::UnknownFrame(_)) => resolver.resolve(input),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,196 @@
+//! Resolution of target names to positions.
+
+#[cfg(feature = "sesame")]
+use std::io::Read;
+
+#[cfg(feature = "sesame")]
+use log::debug;
+#[cfg(feature = "sesame")]
+use vo_client::VoClient;
+
+use crate::{Coord, Error};
+
+/// Service resolving the names of astronomical objects, e.g. "M31", to
+/// their position.
+///
+/// Implemented by [`Sesame`](struct.Sesame.html) with the `sesame` feature,
+/// and by closures, e.g. to look names up in a local catalogue.
+pub trait NameResolver {
+    /// ICRS position of the object `name`.
+    ///
+    /// Fails with [`Error::NotFound`](enum.Error.html#variant.NotFound) if
+    /// the object is not known.
+    fn resolve(&self, name: &str) -> Result<Coord, Error>;
+}
+
+impl<F> NameResolver for F
+where
+    F: Fn(&str) -> Result<Coord, Error>,
+{
+    fn resolve(&self, name: &str) -> Result<Coord, Error> {
+        self(name)
+    }
+}
+
+/// Maximum size of a response of Sesame
+#[cfg(feature = "sesame")]
+const MAX_RESPONSE_LEN: u64 = 1024 * 1024;
+
+/// Name resolver querying the
+/// [Sesame](https://cds.unistra.fr/cgi-bin/Sesame) service of the CDS,
+/// which looks names up in Simbad, NED and VizieR.
+///
+/// Requests are sent by the blocking client of `VoClient`, so this resolver
+/// does not need any asynchronous runtime.
+///
+/// ```
+/// # extern crate vo_coords;
+/// # use std::io::{Read, Write};
+/// # use std::net::TcpListener;
+/// # use vo_coords::{Error, NameResolver, Sesame};
+/// # fn main() {
+/// # // Stub of the Sesame service
+/// # let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// # let mirror = format!("http://{}/cgi-bin/nph-sesame", listener.local_addr().unwrap());
+/// # std::thread::spawn(move || {
+/// #     for stream in listener.incoming() {
+/// #         let mut stream = stream.unwrap();
+/// #         let mut request = [0; 4096];
+/// #         let len = stream.read(&mut request).unwrap();
+/// #         let request = String::from_utf8_lossy(&request[..len]);
+/// #         let body = if request.starts_with("GET /cgi-bin/nph-sesame/SNV?M31 ") {
+/// #             "# M31\t#Q1\n#=Simbad: 1\n%@ 1575544\n%I.0 M  31\n%C.0 G\n\
+/// #              %J 10.68470833 +41.26875000 = 00:42:44.33 +41:16:07.5\n"
+/// #         } else {
+/// #             "# Nothing\t#Q2\n#! *** Nothing found *** \n"
+/// #         };
+/// #         write!(
+/// #             stream,
+/// #             "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\
+/// #              Connection: close\r\n\r\n{}",
+/// #             body.len(),
+/// #             body
+/// #         )
+/// #         .unwrap();
+/// #     }
+/// # });
+/// let sesame = Sesame::new().with_url(mirror);
+///
+/// let m31 = sesame.resolve("M31").unwrap();
+/// assert_eq!((m31.ra(), m31.dec()), (10.68470833, 41.26875));
+///
+/// match sesame.resolve("Nothing") {
+///     Err(Error::NotFound(name)) => assert_eq!(name, "Nothing"),
+///     other => panic!("Unexpected result {:?}", other),
+/// }
+/// # }
+/// ```
+#[cfg(feature = "sesame")]
+#[derive(Debug, Clone)]
+pub struct Sesame {
+    client: VoClient,
+    url: String,
+    databases: String,
+}
+
+#[cfg(feature = "sesame")]
+impl Sesame {
+    pub const DEFAULT_URL: &'static str = "https://cds.unistra.fr/cgi-bin/nph-sesame";
+
+    pub fn new() -> Self {
+        Sesame {
+            client: VoClient::new(),
+            url: Sesame::DEFAULT_URL.to_owned(),
+            databases: "SNV".to_owned(),
+        }
+    }
+
+    /// Send the requests with `client`, e.g. to go through a proxy.
+    pub fn with_client(mut self, client: VoClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// URL of the nph-sesame script of the service, e.g. of a mirror.
+    pub fn with_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Databases queried in turn, by their initials: "S" for Simbad, "N"
+    /// for NED and "V" for VizieR. Defaults to "SNV".
+    pub fn with_databases<S: Into<String>>(mut self, databases: S) -> Self {
+        self.databases = databases.into();
+        self
+    }
+}
+
+#[cfg(feature = "sesame")]
+impl Default for Sesame {
+    fn default() -> Self {
+        Sesame::new()
+    }
+}
+
+#[cfg(feature = "sesame")]
+impl NameResolver for Sesame {
+    fn resolve(&self, name: &str) -> Result<Coord, Error> {
+        let url = format!(
+            "{}/{}?{}",
+            self.url.trim_end_matches('/'),
+            self.databases,
+            encode(name.trim())
+        );
+        debug!("Resolving {:?} with {}", name, url);
+        let res = self.client.get_blocking(&url).map_err(Error::Client)?;
+        if !res.status().is_success() {
+            return Err(Error::Resolver(format!(
+                "Unexpected HTTP status {} from {}",
+                res.status(),
+                url
+            )));
+        }
+        let mut body = String::new();
+        res.into_body()
+            .take(MAX_RESPONSE_LEN)
+            .read_to_string(&mut body)
+            .map_err(|e| Error::Client(vo_client::Error::Io(e)))?;
+
+        // The position is given in decimal degrees on the line starting
+        // with %J, e.g. "%J 10.68470833 +41.26875000 = 00:42:44.33 +41:16:07.5"
+        let position = body
+            .lines()
+            .find_map(|line| line.strip_prefix("%J "))
+            .ok_or_else(|| Error::NotFound(name.to_owned()))?;
+        let mut values = position.split_whitespace().map(str::parse::<f64>);
+        match (values.next(), values.next()) {
+            (Some(Ok(ra)), Some(Ok(dec))) => {
+                debug!("Resolved {:?} to {} {}", name, ra, dec);
+                Coord::new(ra, dec)
+            }
+            _ => Err(Error::Resolver(format!(
+                "Invalid position {:?} for {:?}",
+                position, name
+            ))),
+        }
+    }
+}
+
+/// Percent-encode `name` for the query string of a URL.
+#[cfg(feature = "sesame")]
+fn encode(name: &str) -> String {
+    use std::fmt::Write;
+
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,132 @@
+//! Transformations between frames, checked against published positions.
+
+use vo_coords::Frame;
+
+/// Seconds of arc in a degree
+const ARCSEC: f64 = 1.0 / 3600.0;
+
+/// Assert that two positions are less than `tolerance` degrees apart.
+fn assert_close(actual: (f64, f64), expected: (f64, f64), tolerance: f64) {
+    let (lon1, lat1) = (actual.0.to_radians(), actual.1.to_radians());
+    let (lon2, lat2) = (expected.0.to_radians(), expected.1.to_radians());
+    // Haversine formula, which is accurate for small separations
+    let h = ((lat1 - lat2) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon1 - lon2) / 2.0).sin().powi(2);
+    let separation = 2.0 * h.sqrt().min(1.0).asin().to_degrees();
+    assert!(
+        separation < tolerance,
+        "{:?} is {}\" away from {:?}",
+        actual,
+        separation / ARCSEC,
+        expected
+    );
+}
+
+fn hours(h: f64, m: f64, s: f64) -> f64 {
+    15.0 * (h + m / 60.0 + s / 3600.0)
+}
+
+fn degrees(d: f64, m: f64, s: f64) -> f64 {
+    d.signum() * (d.abs() + m / 60.0 + s / 3600.0)
+}
+
+#[test]
+fn galactic() {
+    // Definition of the galactic frame in the Hipparcos catalogue
+    let pole = Frame::Galactic.to_icrs(0.0, 90.0);
+    assert_close(pole, (192.85948, 27.12825), 0.05 * ARCSEC);
+    let center = Frame::Galactic.to_icrs(0.0, 0.0);
+    assert_close(center, (266.40499, -28.93617), 0.05 * ARCSEC);
+
+    let (l, b) = Frame::Galactic.from_icrs(center.0, center.1);
+    assert_close((l, b), (0.0, 0.0), 1e-9);
+    assert!(!(1e-9..=360.0 - 1e-9).contains(&l), "{}", l);
+}
+
+#[test]
+fn supergalactic() {
+    // The origin and the pole of the frame (de Vaucouleurs et al. 1991)
+    let origin = Frame::Supergalactic.transform(Frame::Galactic, 0.0, 0.0);
+    assert_close(origin, (137.37, 0.0), 1e-9);
+    let pole = Frame::Supergalactic.transform(Frame::Galactic, 0.0, 90.0);
+    assert_close(pole, (47.37, 6.32), 1e-9);
+    let (_, sgb) = Frame::Galactic.transform(Frame::Supergalactic, 47.37, 6.32);
+    assert!((sgb - 90.0).abs() < 1e-9, "{}", sgb);
+
+    // The pole in ICRS, as given by NED
+    let pole = Frame::Supergalactic.to_icrs(0.0, 90.0);
+    assert_close(
+        pole,
+        (hours(18.0, 55.0, 1.0), degrees(15.0, 42.0, 32.0)),
+        ARCSEC,
+    );
+}
+
+/// Add the E-terms of aberration to a position of the FK4 B1950 frame
+/// (Explanatory Supplement 1992, §3.531).
+fn add_e_terms(ra: f64, dec: f64) -> (f64, f64) {
+    // Components of the E-terms at B1950, in seconds of arc
+    let (c, d, c_tan_e) = (-0.065_838, 0.335_299, -0.028_553);
+    let (a, delta) = (ra.to_radians(), dec.to_radians());
+    let d_ra = (c * a.cos() + d * a.sin()) / delta.cos();
+    let d_dec = (d * a.cos() - c * a.sin()) * delta.sin() + c_tan_e * delta.cos();
+    (ra + d_ra * ARCSEC, dec + d_dec * ARCSEC)
+}
+
+#[test]
+fn fk4_with_e_terms() {
+    // The IAU 1958 definition of galactic coordinates gives positions in
+    // B1950 without the E-terms, which the FK4 frame includes.
+    let center = (hours(17.0, 42.0, 26.603), degrees(-28.0, 55.0, 0.445));
+    let fk4 = Frame::Galactic.transform(Frame::FK4_B1950, 0.0, 0.0);
+    assert_close(fk4, add_e_terms(center.0, center.1), 0.05 * ARCSEC);
+    assert!(
+        (fk4.0 - center.0).abs() > 0.3 * ARCSEC,
+        "The E-terms are missing"
+    );
+
+    let pole = Frame::Galactic.transform(Frame::FK4_B1950, 0.0, 90.0);
+    assert_close(pole, add_e_terms(192.25, 27.4), 0.05 * ARCSEC);
+
+    let (l, b) = Frame::FK4_B1950.transform(Frame::Galactic, fk4.0, fk4.1);
+    assert_close((l, b), (0.0, 0.0), 1e-9);
+}
+
+#[test]
+fn fk4_to_icrs() {
+    // 3C 273, from the B1950 catalogue of Kellermann et al., and in the
+    // ICRF
+    let fk4 = (hours(12.0, 26.0, 33.246), degrees(2.0, 19.0, 43.29));
+    let icrf = (hours(12.0, 29.0, 6.69973), degrees(2.0, 3.0, 8.5982));
+    let icrs = Frame::FK4_B1950.to_icrs(fk4.0, fk4.1);
+    assert_close(icrs, icrf, 0.3 * ARCSEC);
+    assert_close(Frame::FK4_B1950.from_icrs(icrs.0, icrs.1), fk4, 1e-9);
+}
+
+#[test]
+fn ecliptic_of_date() {
+    // Obliquity of the ecliptic at J2100 (IAU 1976)
+    let t = 1.0;
+    let obliquity = (84_381.448 - 46.815 * t - 0.000_59 * t * t + 0.001_813 * t * t * t) * ARCSEC;
+    let ecliptic = Frame::Ecliptic { equinox: 2100.0 };
+    let fk5 = Frame::FK5 { equinox: 2100.0 };
+    assert_close(
+        ecliptic.transform(fk5, 0.0, 90.0),
+        (270.0, 90.0 - obliquity),
+        1e-9,
+    );
+    assert_close(ecliptic.transform(fk5, 0.0, 0.0), (0.0, 0.0), 1e-9);
+    assert_close(ecliptic.transform(fk5, 90.0, 0.0), (90.0, obliquity), 1e-9);
+
+    // General precession in longitude over a century (IAU 1976)
+    let precession = (5_029.096_6 * t + 1.111_13 * t * t - 0.000_006 * t * t * t) * ARCSEC;
+    for &longitude in &[10.0, 100.0, 250.0] {
+        let (lon, _) = Frame::ECLIPTIC_J2000.transform(ecliptic, longitude, 0.0);
+        assert!(
+            (lon - longitude - precession).abs() < 0.05 * ARCSEC,
+            "{} precessed to {}",
+            longitude,
+            lon
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Resolution of names by a stub Sesame service.
+#![cfg(feature = "sesame")]
+
+use vo_coords::{Error, NameResolver, Sesame};
+use vo_mock::{MockService, Reply, Request};
+
+const M31: &str = "# M31\t#Q1\n#=Simbad: 1\n%@ 1575544\n%I.0 M  31\n%C.0 G\n\
+                   %J 10.68470833 +41.26875000 = 00:42:44.33 +41:16:07.5\n";
+const NOTHING: &str = "# Nothing\t#Q2\n#! *** Nothing found *** \n";
+
+/// Name looked up by a request, from its query string
+fn name(request: &Request) -> &str {
+    request.params.first().map_or("", |(name, _)| name.as_str())
+}
+
+fn sesame() -> MockService {
+    MockService::start_at("/cgi-bin/nph-sesame", |request| match name(request) {
+        "M31" => Reply::text(M31),
+        "Broken" => Reply::text("# Broken\t#Q3\n%J 10.68 north\n"),
+        "Down" => Reply::text("Service unavailable").with_status(503),
+        _ => Reply::text(NOTHING),
+    })
+}
+
+#[test]
+fn resolves_names() {
+    let service = sesame();
+    let m31 = Sesame::new()
+        .with_url(&service.url)
+        .resolve(" M31 ")
+        .unwrap();
+    assert_eq!((m31.ra(), m31.dec()), (10.68470833, 41.26875));
+
+    let requests = service.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].path, "/cgi-bin/nph-sesame/SNV");
+    assert_eq!(name(&requests[0]), "M31");
+}
+
+#[test]
+fn encodes_names() {
+    let service = sesame();
+    let sesame = Sesame::new().with_url(format!("{}/", service.url));
+    for target in &["Eta Car", "NGC 1275+4", "α Cen", "M31&M32"] {
+        match sesame.resolve(target) {
+            Err(Error::NotFound(name)) => assert_eq!(&name, target),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    let requests = service.requests();
+    let names: Vec<_> = requests.iter().map(name).collect();
+    assert_eq!(names, ["Eta Car", "NGC 1275+4", "α Cen", "M31&M32"]);
+    assert!(requests
+        .iter()
+        .all(|request| request.path == "/cgi-bin/nph-sesame/SNV" && request.params.len() == 1));
+}
+
+#[test]
+fn queries_the_given_databases() {
+    let service = sesame();
+    let sesame = Sesame::new().with_url(&service.url).with_databases("NS");
+    sesame.resolve("M31").unwrap();
+    assert_eq!(service.requests()[0].path, "/cgi-bin/nph-sesame/NS");
+}
+
+#[test]
+fn reports_failures() {
+    let service = sesame();
+    let sesame = Sesame::new().with_url(&service.url);
+    match sesame.resolve("Nothing") {
+        Err(Error::NotFound(name)) => assert_eq!(name, "Nothing"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match sesame.resolve("Broken") {
+        Err(Error::Resolver(msg)) => assert!(msg.contains("10.68 north"), "{}", msg),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match sesame.resolve("Down") {
+        Err(Error::Resolver(msg)) => assert!(msg.contains("503"), "{}", msg),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let missing = MockService::start(|_| Reply::status(404));
+    match Sesame::new().with_url(&missing.url).resolve("M31") {
+        Err(Error::Resolver(msg)) => assert!(msg.contains("404"), "{}", msg),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,305 @@
+//! Mock HTTP services answering on a local port, for the tests of the
+//! protocol crates.
+//!
+//! A service answers each request with a closure, and records the requests
+//! it receives:
+//!
+//! ```
+//! # extern crate vo_mock;
+//! # use vo_mock::{MockService, Reply};
+//! # fn main() {
+//! let service = MockService::start_at("/tap", |request| match request.param("QUERY") {
+//!     Some(_) => Reply::votable("<VOTABLE/>"),
+//!     None => Reply::status(400),
+//! });
+//! assert!(service.url.ends_with("/tap"));
+//! assert!(service.requests().is_empty());
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server};
+
+/// A request received by a mock service.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub content_type: Option<String>,
+    /// Headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// Parameters of the query string, then of the body
+    pub params: Vec<(String, String)>,
+    /// Files of multipart/form-data requests, by name
+    pub files: HashMap<String, Vec<u8>>,
+    pub received: Instant,
+}
+
+impl Request {
+    /// Value of the first header named `name`, in lowercase.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Value of the first parameter named `key`.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// The response to a request, sent after `delay`.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+    pub delay: Duration,
+}
+
+impl Reply {
+    pub fn ok<B: Into<Vec<u8>>>(body: B) -> Self {
+        Reply {
+            status: 200,
+            headers: vec![],
+            body: body.into(),
+            delay: Duration::from_secs(0),
+        }
+    }
+
+    /// An empty response with the status `status`.
+    pub fn status(status: u16) -> Self {
+        Reply {
+            status,
+            ..Reply::ok("")
+        }
+    }
+
+    pub fn votable<B: Into<Vec<u8>>>(body: B) -> Self {
+        Reply::ok(body).with_header("Content-Type", "application/x-votable+xml")
+    }
+
+    pub fn xml<B: Into<Vec<u8>>>(body: B) -> Self {
+        Reply::ok(body).with_header("Content-Type", "text/xml")
+    }
+
+    pub fn text<B: Into<Vec<u8>>>(body: B) -> Self {
+        Reply::ok(body).with_header("Content-Type", "text/plain")
+    }
+
+    pub fn see_other(location: &str) -> Self {
+        Reply::status(303).with_header("Location", location)
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_owned()));
+        self
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+#[derive(Default)]
+struct State {
+    requests: Mutex<Vec<Request>>,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+/// A service answering on a local port, which records the requests it
+/// receives.
+pub struct MockService {
+    /// URL of the service, made of the root of the server and the path
+    /// given to [`start_at`](#method.start_at)
+    pub url: String,
+    root: String,
+    state: Arc<State>,
+}
+
+impl MockService {
+    /// Start a service answering the requests with `respond`.
+    pub fn start<F>(respond: F) -> Self
+    where
+        F: Fn(&Request) -> Reply + Send + Sync + 'static,
+    {
+        MockService::start_at("", respond)
+    }
+
+    /// Start a service whose URL ends with `path`.
+    pub fn start_at<F>(path: &str, respond: F) -> Self
+    where
+        F: Fn(&Request) -> Reply + Send + Sync + 'static,
+    {
+        let state = Arc::new(State::default());
+        let respond = Arc::new(respond);
+        let server_state = state.clone();
+        let (sender, receiver) = mpsc::channel();
+        // The server has its own runtime, so that it can be started from
+        // asynchronous tests too
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let make_service = make_service_fn(move |_| {
+                    let state = server_state.clone();
+                    let respond = respond.clone();
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |req| {
+                            handle(state.clone(), respond.clone(), req)
+                        }))
+                    }
+                });
+                let server =
+                    Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+                sender.send(server.local_addr()).unwrap();
+                let _ = server.await;
+            })
+        });
+        let root = format!("http://{}", receiver.recv().unwrap());
+        MockService {
+            url: format!("{}{}", root, path),
+            root,
+            state,
+        }
+    }
+
+    /// URL of `path` on the server of the service.
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.root, path)
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Highest number of requests handled at the same time.
+    pub fn max_in_flight(&self) -> usize {
+        self.state.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+async fn handle<F>(
+    state: Arc<State>,
+    respond: Arc<F>,
+    req: hyper::Request<Body>,
+) -> Result<Response<Body>, Infallible>
+where
+    F: Fn(&Request) -> Reply,
+{
+    let in_flight = state.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+    state.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+    let reply = match read_request(req).await {
+        Some(request) => {
+            let reply = respond(&request);
+            state.requests.lock().unwrap().push(request);
+            reply
+        }
+        None => Reply::status(400),
+    };
+    tokio::time::sleep(reply.delay).await;
+    state.in_flight.fetch_sub(1, Ordering::SeqCst);
+    let mut response = Response::builder().status(reply.status);
+    for (name, value) in reply.headers {
+        response = response.header(name, value);
+    }
+    Ok(response.body(Body::from(reply.body)).unwrap())
+}
+
+async fn read_request(req: hyper::Request<Body>) -> Option<Request> {
+    let received = Instant::now();
+    let method = req.method().to_string();
+    let path = req.uri().path().to_owned();
+    let query = req.uri().query().unwrap_or("").to_owned();
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    let headers = req
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+        .collect();
+    let body = hyper::body::to_bytes(req.into_body()).await.ok()?;
+
+    let mut params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let boundary = content_type
+        .as_deref()
+        .and_then(|content_type| content_type.strip_prefix("multipart/form-data; boundary="));
+    let files = match boundary {
+        Some(boundary) => {
+            let (parts, files) = parse_multipart(&body, boundary)?;
+            params.extend(parts);
+            files
+        }
+        None => {
+            params.extend(url::form_urlencoded::parse(&body).into_owned());
+            HashMap::new()
+        }
+    };
+    Some(Request {
+        method,
+        path,
+        content_type,
+        headers,
+        params,
+        files,
+        received,
+    })
+}
+
+type Parts = (Vec<(String, String)>, HashMap<String, Vec<u8>>);
+
+/// Split a multipart/form-data body into text parameters and files.
+fn parse_multipart(body: &[u8], boundary: &str) -> Option<Parts> {
+    let body = String::from_utf8(body.to_vec()).ok()?;
+    let mut params = vec![];
+    let mut files = HashMap::new();
+    let delimiter = format!("--{}", boundary);
+    let mut parts = body.split(delimiter.as_str());
+    if !parts.next()?.is_empty() {
+        return None;
+    }
+    for part in parts {
+        if part == "--\r\n" {
+            return Some((params, files));
+        }
+        let part = part.strip_prefix("\r\n")?.strip_suffix("\r\n")?;
+        let (headers, content) = part.split_once("\r\n\r\n")?;
+        let disposition = headers.lines().next()?;
+        let name = disposition.split("name=\"").nth(1)?.split('"').next()?;
+        if disposition.contains("filename=") {
+            files.insert(name.to_owned(), content.as_bytes().to_vec());
+        } else {
+            params.push((name.to_owned(), content.to_owned()));
+        }
+    }
+    None
+}
@@ -0,0 +1,35 @@
+use std::env;
+
+use vo_scs::{Coord, ScsService, Verbosity};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let center: Coord = match args.first().map(|arg| arg.parse()) {
+        Some(Ok(center)) => center,
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+        None => Coord::new(10.684708, 41.26875).unwrap(),
+    };
+    let query = ScsService::VIZIER_2MASS_PSC
+        .create_query(center, 0.02)
+        .with_verbosity(Verbosity::V);
+
+    match query.execute_sync() {
+        Err(e) => eprintln!("Error: {}", e),
+        Ok(results) => {
+            for (i, record) in results.records().enumerate() {
+                match record.position() {
+                    Ok(Some(position)) => {
+                        println!("{}. {} {}", i, record.id().unwrap_or_default(), position)
+                    }
+                    Ok(None) => {
+                        println!("{}. {} (no position)", i, record.id().unwrap_or_default())
+                    }
+                    Err(e) => eprintln!("{}. Error: {}", i, e),
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,44 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Client(vo_client::Error),
+    InvalidUri(http::uri::InvalidUri),
+    VOTable(vo_table::Error),
+    /// Coordinates cannot be converted, e.g. because their frame is not
+    /// supported.
+    Coords(vo_coords::Error),
+    /// The search radius of a query is invalid.
+    InvalidRadius(f64),
+}
+
+impl From<vo_client::Error> for Error {
+    fn from(e: vo_client::Error) -> Self {
+        Error::Client(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+        match self {
+            Client(e) => write!(f, "{}", e),
+            InvalidUri(e) => write!(f, "Invalid URL. {}", e),
+            VOTable(e) => write!(f, "VOTable error. {}", e),
+            Coords(e) => write!(f, "{}", e),
+            InvalidRadius(radius) => {
+                write!(f, "Search radius must be in [0, 180], got {}", radius)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Client(e) => e.source(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Client for the Simple Cone Search protocol, version 1.03.
+//!
+//! A cone search returns the sources of a catalogue within a radius of a
+//! position, as a VOTable:
+//!
+//! ```no_run
+//! # extern crate vo_scs;
+//! # use vo_scs::{Coord, ScsService};
+//! # fn main() {
+//! let m31: Coord = "00h42m44.3s +41d16m09s".parse().unwrap();
+//! let results = ScsService::VIZIER_2MASS_PSC
+//!     .create_query(m31, 0.05)
+//!     .execute_sync()
+//!     .unwrap();
+//! for record in results.records() {
+//!     println!("{:?} {:?} {:?}", record.id(), record.ra(), record.dec());
+//! }
+//! # }
+//! ```
+//!
+//! Queries can be executed asynchronously on a Tokio runtime, with the
+//! `tokio` feature enabled by default, or from blocking code with
+//! [`execute_sync`](struct.ScsQuery.html#method.execute_sync). With the
+//! `blocking` feature, blocking functions send their requests without any
+//! asynchronous runtime.
+
+#[cfg(not(any(feature = "tokio", feature = "blocking")))]
+compile_error!("Either the `tokio` or the `blocking` feature of vo-scs must be enabled.");
+
+mod err;
+
+use vo_client::service::{self, Request};
+use vo_client::ResponseHook;
+#[cfg(all(feature = "tokio", not(feature = "blocking")))]
+use vo_client::Runtime;
+use vo_table::{Cell, Row, VOTable};
+
+pub use crate::err::Error;
+pub use vo_client::{Error as ClientError, RawResponse, VoClient, VoClientBuilder};
+pub use vo_coords::{Coord, Frame};
+
+/// A cone search service, identified by its base URL.
+///
+/// The base URL may already contain parameters, e.g. to select the
+/// catalogue, to which the parameters of the queries are appended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScsService<U> {
+    url: U,
+}
+
+impl ScsService<&'static str> {
+    /// 2MASS All-Sky Catalog of Point Sources, served by VizieR
+    pub const VIZIER_2MASS_PSC: ScsService<&'static str> = ScsService {
+        url: "http://vizier.cds.unistra.fr/viz-bin/conesearch/II/246/out",
+    };
+    /// Gaia DR3 main source catalogue, served by VizieR
+    pub const VIZIER_GAIA_DR3: ScsService<&'static str> = ScsService {
+        url: "http://vizier.cds.unistra.fr/viz-bin/conesearch/I/355/gaiadr3",
+    };
+}
+
+impl<U> ScsService<U>
+where
+    U: ToString,
+{
+    pub fn new(url: U) -> ScsService<U> {
+        ScsService { url }
+    }
+
+    pub fn map<F, V>(self, mut f: F) -> ScsService<V>
+    where
+        F: FnMut(U) -> V,
+        V: ToString,
+    {
+        ScsService { url: f(self.url) }
+    }
+
+    /// Search the sources within `radius` degrees of `center`.
+    pub fn create_query<'k>(&self, center: Coord, radius: f64) -> ScsQuery<'k> {
+        ScsQuery {
+            base_url: self.url.to_string(),
+            center,
+            radius,
+            verbosity: None,
+            keywords: vec![],
+            response_hook: None,
+        }
+    }
+}
+
+/// A query to a cone search service.
+///
+/// ```
+/// # extern crate vo_scs;
+/// # use vo_scs::{Coord, ScsService, Verbosity};
+/// # fn main() {
+/// let query = ScsService::new("http://example.com/scs?CAT=hip")
+///     .create_query(Coord::new(10.5, -20.25).unwrap(), 0.1)
+///     .with_verbosity(Verbosity::VVV)
+///     .with_keyword("MAXREC", "100");
+/// assert_eq!(
+///     query.query_url(),
+///     "http://example.com/scs?CAT=hip&RA=10.5&DEC=-20.25&SR=0.1&VERB=3&MAXREC=100"
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScsQuery<'k> {
+    base_url: String,
+    center: Coord,
+    radius: f64,
+    verbosity: Option<Verbosity>,
+    keywords: Vec<(&'k str, &'k str)>,
+    response_hook: Option<ResponseHook>,
+}
+
+/// Number of columns returned by the service, from the minimum required by
+/// the standard to all the columns of the catalogue.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Verbosity {
+    V = 1,
+    VV = 2,
+    VVV = 3,
+}
+
+impl<'k> ScsQuery<'k> {
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+
+    /// Add a parameter not defined by the standard, e.g. a constraint
+    /// specific to the catalogue.
+    pub fn with_keyword(mut self, key: &'k str, value: &'k str) -> Self {
+        self.keywords.push((key, value));
+        self
+    }
+
+    /// Call `hook` with the raw response of the service when the query is
+    /// executed, e.g. to log or archive it.
+    pub fn with_response_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RawResponse) + Send + Sync + 'static,
+    {
+        self.response_hook = Some(ResponseHook::new(hook));
+        self
+    }
+
+    /// Execute the query with a new client.
+    ///
+    /// Prefer [`execute_with`](#method.execute_with) to share the
+    /// connections and configuration of a client between queries.
+    #[cfg(feature = "tokio")]
+    pub async fn execute(&self) -> Result<ScsResults, Error> {
+        self.execute_with(&VoClient::new()).await
+    }
+
+    /// Execute the query with `client`.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_with(&self, client: &VoClient) -> Result<ScsResults, Error> {
+        let table = client.query(&self.check_request()?).await?;
+        Ok(ScsResults { table })
+    }
+
+    /// Blocking version of [`execute`](#method.execute).
+    pub fn execute_sync(&self) -> Result<ScsResults, Error> {
+        self.execute_sync_with(&VoClient::new())
+    }
+
+    /// Blocking version of [`execute_with`](#method.execute_with).
+    ///
+    /// Requests are sent by the blocking client with the `blocking` feature,
+    /// or else on a Tokio runtime shared by the blocking functions.
+    pub fn execute_sync_with(&self, client: &VoClient) -> Result<ScsResults, Error> {
+        #[cfg(feature = "blocking")]
+        {
+            let table = client.query_blocking(&self.check_request()?)?;
+            Ok(ScsResults { table })
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            Runtime::shared()?.block_on(self.execute_with(client))?
+        }
+    }
+
+    /// The request of the query, checked before it is sent.
+    fn check_request(&self) -> Result<Request, Error> {
+        if !(0.0..=180.0).contains(&self.radius) {
+            return Err(Error::InvalidRadius(self.radius));
+        }
+        let url = self.query_url();
+        url.parse::<http::Uri>().map_err(Error::InvalidUri)?;
+        Ok(Request::get(url).with_response_hook(self.response_hook.clone()))
+    }
+
+    pub fn query_url(&self) -> String {
+        let query_string = {
+            let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+            serializer.append_pair("RA", &self.center.ra().to_string());
+            serializer.append_pair("DEC", &self.center.dec().to_string());
+            serializer.append_pair("SR", &self.radius.to_string());
+            if let Some(verbosity) = self.verbosity {
+                serializer.append_pair("VERB", &(verbosity as usize).to_string());
+            }
+            serializer.extend_pairs(&self.keywords);
+            serializer.finish()
+        };
+        let separator = if !self.base_url.contains('?') {
+            "?"
+        } else if self.base_url.ends_with('?') || self.base_url.ends_with('&') {
+            ""
+        } else {
+            "&"
+        };
+        format!("{}{}{}", self.base_url, separator, query_string)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScsResults {
+    table: VOTable,
+}
+
+impl ScsResults {
+    /// Wrap a VOTable returned by a cone search service.
+    pub fn new(table: VOTable) -> Self {
+        ScsResults { table }
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = ScsRecord<'_>> {
+        let votable = &self.table;
+        votable
+            .tables()
+            .filter_map(|table| table.rows())
+            .flatten()
+            .map(move |row| ScsRecord { row, votable })
+    }
+
+    /// Value of the QUERY_STATUS INFO of the results, e.g. "OVERFLOW" if
+    /// they were truncated to the maximum number of records.
+    pub fn query_status(&self) -> Option<&str> {
+        service::query_status(&self.table).map(vo_table::Info::value)
+    }
+
+    pub fn table(&self) -> &VOTable {
+        &self.table
+    }
+
+    pub fn into_table(self) -> VOTable {
+        self.table
+    }
+}
+
+/// UCDs of the columns required by the standard, in UCD1 then UCD1+
+const ID_UCDS: [&str; 2] = ["ID_MAIN", "meta.id;meta.main"];
+const RA_UCDS: [&str; 2] = ["POS_EQ_RA_MAIN", "pos.eq.ra;meta.main"];
+const DEC_UCDS: [&str; 2] = ["POS_EQ_DEC_MAIN", "pos.eq.dec;meta.main"];
+
+/// A source found by a cone search.
+///
+/// The columns required by the standard are identified by their UCD, in
+/// UCD1 as specified by Cone Search 1.03, or in UCD1+ as used by recent
+/// services. Other columns are read from the [`row`](#method.row).
+#[derive(Debug, Clone, Copy)]
+pub struct ScsRecord<'a> {
+    row: Row<'a>,
+    /// VOTable of the row, with the COOSYS elements of its columns
+    votable: &'a VOTable,
+}
+
+impl<'a> ScsRecord<'a> {
+    fn get(&self, ucds: &[&str]) -> Option<Cell<'a>> {
+        ucds.iter().find_map(|ucd| self.row.get_by_ucd(ucd))
+    }
+
+    /// Identifier of the source in the catalogue, whether a string or an
+    /// integer.
+    pub fn id(&self) -> Option<String> {
+        self.get(&ID_UCDS).and_then(|cell| match cell.as_str() {
+            Some(id) => Some(id.trim().to_owned()),
+            None => cell.as_i64().map(|id| id.to_string()),
+        })
+    }
+
+    /// Right ascension of the source, in degrees.
+    pub fn ra(&self) -> Option<f64> {
+        self.get(&RA_UCDS).and_then(|cell| cell.as_f64())
+    }
+
+    /// Declination of the source, in degrees.
+    pub fn dec(&self) -> Option<f64> {
+        self.get(&DEC_UCDS).and_then(|cell| cell.as_f64())
+    }
+
+    /// Position of the source in ICRS, converted from the coordinate system
+    /// given by the COOSYS referenced by the right ascension column.
+    /// Positions without COOSYS are assumed to be in ICRS.
+    ///
+    /// Fails if the coordinate system is not supported.
+    pub fn position(&self) -> Result<Option<Coord>, Error> {
+        let (longitude, latitude) = match (self.ra(), self.dec()) {
+            (Some(longitude), Some(latitude)) => (longitude, latitude),
+            _ => return Ok(None),
+        };
+        let frame = match RA_UCDS
+            .iter()
+            .find(|ucd| self.row.get_by_ucd(ucd).is_some())
+            .and_then(|ucd| self.row.ref_by_ucd(ucd))
+            .and_then(|id| self.votable.coosys_by_id(id))
+        {
+            Some(coosys) => {
+                Frame::from_coosys(coosys.system(), coosys.equinox()).map_err(Error::Coords)?
+            }
+            None => Frame::ICRS,
+        };
+        Coord::from_frame(frame, longitude, latitude)
+            .map(Some)
+            .map_err(Error::Coords)
+    }
+
+    /// Row of the source, to read the other columns of the catalogue.
+    pub fn row(&self) -> Row<'a> {
+        self.row
+    }
+}
@@ -0,0 +1,91 @@
+//! Cone searches against a stub service.
+
+use vo_mock::{MockService, Reply};
+use vo_scs::{ClientError, Coord, Error, ScsService, Verbosity};
+
+/// Sources in the TABLEDATA serialization, as returned by VizieR
+const SOURCES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<VOTABLE version="1.4" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+  <RESOURCE type="results">
+    <INFO name="QUERY_STATUS" value="OK"/>
+    <COOSYS ID="H_2000.000" system="ICRS" epoch="2000"/>
+    <TABLE name="II/246/out">
+      <FIELD name="RAJ2000" ucd="pos.eq.ra;meta.main" ref="H_2000.000" datatype="double" width="10" precision="6" unit="deg"/>
+      <FIELD name="DEJ2000" ucd="pos.eq.dec;meta.main" ref="H_2000.000" datatype="double" width="10" precision="6" unit="deg"/>
+      <FIELD name="2MASS" ucd="meta.id;meta.main" datatype="char" arraysize="17"/>
+      <FIELD name="Jmag" ucd="phot.mag;em.IR.J" datatype="float" width="6" precision="3" unit="mag"/>
+      <FIELD name="Qflg" ucd="meta.code.qual" datatype="char" arraysize="3"/>
+      <DATA><TABLEDATA>
+        <TR><TD>010.684781</TD><TD>+41.269028</TD><TD>00424434+4116088</TD><TD>11.203</TD><TD>EEE</TD></TR>
+        <TR><TD>010.686212</TD><TD>+41.266342</TD><TD>00424469+4115588</TD><TD></TD><TD>UUU</TD></TR>
+      </TABLEDATA></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#;
+
+#[test]
+fn reads_tabledata_sources() {
+    let service = MockService::start_at("/conesearch?CAT=2mass", |_| Reply::votable(SOURCES));
+    let center = Coord::new(10.684708, 41.26875).unwrap();
+    let results = ScsService::new(service.url.as_str())
+        .create_query(center, 0.01)
+        .with_verbosity(Verbosity::VV)
+        .execute_sync()
+        .unwrap();
+
+    let request = &service.requests()[0];
+    assert_eq!(request.path, "/conesearch");
+    assert_eq!(request.param("CAT"), Some("2mass"));
+    assert_eq!(request.param("RA"), Some("10.684708"));
+    assert_eq!(request.param("DEC"), Some("41.26875"));
+    assert_eq!(request.param("SR"), Some("0.01"));
+    assert_eq!(request.param("VERB"), Some("2"));
+
+    assert_eq!(results.query_status(), Some("OK"));
+    let records: Vec<_> = results.records().collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].id().as_deref(), Some("00424434+4116088"));
+    assert_eq!(records[0].ra(), Some(10.684781));
+    assert_eq!(records[0].dec(), Some(41.269028));
+    let position = records[1].position().unwrap().unwrap();
+    assert_eq!((position.ra(), position.dec()), (10.686212, 41.266342));
+    // Empty cells are null
+    let jmag = |record: &vo_scs::ScsRecord| record.row().get_by_name("Jmag").unwrap().as_f64();
+    assert_eq!(jmag(&records[0]), Some(f64::from(11.203f32)));
+    assert_eq!(jmag(&records[1]), None);
+}
+
+#[test]
+fn reports_service_errors() {
+    let service = MockService::start(|_| {
+        Reply::votable(
+            r#"<VOTABLE version="1.3">
+  <RESOURCE type="results">
+    <INFO name="QUERY_STATUS" value="ERROR">Unknown catalogue</INFO>
+  </RESOURCE>
+</VOTABLE>"#,
+        )
+    });
+    let err = ScsService::new(service.url.as_str())
+        .create_query(Coord::new(0.0, 0.0).unwrap(), 0.1)
+        .execute_sync()
+        .unwrap_err();
+    match err {
+        Error::Client(ClientError::ServiceError { message, .. }) => {
+            assert_eq!(message, "Unknown catalogue")
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn searches_asynchronously() {
+    let service = MockService::start(|_| Reply::votable(SOURCES));
+    let results = ScsService::new(service.url.as_str())
+        .create_query(Coord::new(10.68, 41.27).unwrap(), 0.01)
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(results.records().count(), 2);
+}
@@ -1,4 +1,4 @@
-extern crate vo_sia;
+use std::env;
 
 use vo_sia::{Format, SiaService};
 
@@ -11,8 +11,25 @@ fn main() {
         Err(e) => eprintln!("Error: {:?}", e),
         Ok(results) => {
             for (i, record) in results.records().enumerate() {
-                if let Some(acref) = record.acref() {
-                    println!("{}. {}", i, acref);
+                if let Some(access_url) = record.access_url() {
+                    let obs_id = record.obs_id().unwrap_or("?");
+                    println!("{}. [{}] {}", i, obs_id, access_url);
+                }
+            }
+
+            if let Some(record) = results.records().find(|r| r.access_url().is_some()) {
+                let dest = env::temp_dir().join("vo-sia-example.fits");
+                let downloaded = record.download_with_progress(&dest, |received, total| {
+                    if let Some(total) = total {
+                        eprint!("\r{} / {} bytes", received, total);
+                    } else {
+                        eprint!("\r{} bytes", received);
+                    }
+                });
+                eprintln!();
+                match downloaded {
+                    Ok(size) => println!("Downloaded {} bytes to {}", size, dest.display()),
+                    Err(e) => eprintln!("Error: {}", e),
                 }
             }
         }
@@ -0,0 +1,21 @@
+use vo_sia::{Format, SiaService, VoClient};
+
+#[tokio::main]
+async fn main() {
+    let client = VoClient::new();
+    let query = SiaService::GAVO
+        .create_query((161.027341982576, -59.6844592879577))
+        .with_format(Format::Fits);
+
+    match query.execute_with(&client).await {
+        Err(e) => eprintln!("Error: {:?}", e),
+        Ok(results) => {
+            for (i, record) in results.records().enumerate() {
+                if let Some(access_url) = record.access_url() {
+                    let obs_id = record.obs_id().unwrap_or("?");
+                    println!("{}. [{}] {}", i, obs_id, access_url);
+                }
+            }
+        }
+    }
+}
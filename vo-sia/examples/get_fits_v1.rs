@@ -0,0 +1,19 @@
+use vo_sia::{Format, SiaService};
+
+fn main() {
+    let query = SiaService::GAVO_OLD_V1
+        .create_query((161.027341982576, -59.6844592879577))
+        .with_size(0.5, 0.5)
+        .with_format(Format::Fits);
+
+    match query.execute_sync() {
+        Err(e) => eprintln!("Error: {:?}", e),
+        Ok(results) => {
+            for (i, record) in results.records().enumerate() {
+                if let Some(access_url) = record.access_url() {
+                    println!("{}. {} {}", i, record.obs_title().unwrap_or(""), access_url);
+                }
+            }
+        }
+    };
+}
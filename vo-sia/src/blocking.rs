@@ -0,0 +1,65 @@
+//! Client of the blocking functions.
+
+#[cfg(all(feature = "tokio", not(feature = "blocking")))]
+use vo_client::Runtime;
+use vo_client::VoClient;
+
+use crate::Error;
+
+/// Client to execute many queries from blocking code.
+///
+/// Without the `blocking` feature, requests run on a Tokio runtime owned by
+/// the client, which is reused by all the queries executed with it and the
+/// downloads of their results. With the `blocking` feature, requests are
+/// sent without any asynchronous runtime.
+///
+/// Blocking functions may be called from a multi-threaded Tokio runtime,
+/// but fail with
+/// [`vo_client::Error::BlockingInAsyncContext`](../vo_client/enum.Error.html#variant.BlockingInAsyncContext)
+/// from a single-threaded one, as it cannot run other tasks while blocked.
+///
+/// ```no_run
+/// # extern crate vo_sia;
+/// # use vo_sia::{BlockingClient, SiaService};
+/// # fn main() {
+/// let client = BlockingClient::new().unwrap();
+/// for ra in 0..360 {
+///     let results = SiaService::GAVO
+///         .create_query((ra as f64, 0.0))
+///         .execute_blocking(&client)
+///         .unwrap();
+///     println!("{}: {} records", ra, results.records().count());
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlockingClient {
+    client: VoClient,
+    #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+    runtime: Runtime,
+}
+
+impl BlockingClient {
+    /// Create a client with the default configuration.
+    pub fn new() -> Result<Self, Error> {
+        BlockingClient::from_client(VoClient::new())
+    }
+
+    /// Send the requests with `client`.
+    pub fn from_client(client: VoClient) -> Result<Self, Error> {
+        Ok(BlockingClient {
+            client,
+            #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+            runtime: Runtime::new()?,
+        })
+    }
+
+    pub fn client(&self) -> &VoClient {
+        &self.client
+    }
+
+    #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+    pub(crate) fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+}
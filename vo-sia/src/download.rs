@@ -0,0 +1,195 @@
+//! Retrieval of the datasets referenced by query results.
+
+#[cfg(feature = "blocking")]
+use std::fs::{self, File};
+#[cfg(feature = "blocking")]
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[cfg(feature = "tokio")]
+use futures_util::{Stream, TryStreamExt};
+use http::header::{HeaderMap, CONTENT_LENGTH};
+#[cfg(feature = "tokio")]
+use hyper::body::{Bytes, HttpBody};
+use log::{debug, warn};
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncWriteExt;
+use vo_client::service::Request;
+use vo_client::VoClient;
+
+use crate::Error;
+
+/// Size of the buffer used to read blocking responses
+#[cfg(feature = "blocking")]
+const BUFFER_LEN: usize = 64 * 1024;
+
+#[cfg(feature = "tokio")]
+pub(crate) fn fetch(
+    client: VoClient,
+    url: String,
+) -> impl Stream<Item = Result<Bytes, Error>> + Send {
+    futures_util::stream::once(async move { client.open(&Request::get(url)).await })
+        .map_ok(|res| TryStreamExt::map_err(res.into_body(), body_error))
+        .map_err(Error::from)
+        .try_flatten()
+}
+
+/// Download `url` to `dest`, through a temporary file which is renamed once
+/// the download is complete.
+///
+/// `estsize` is the estimated size in kilobytes. `progress` is called with
+/// the number of bytes received so far and the expected total, if known.
+#[cfg(feature = "tokio")]
+pub(crate) async fn download<F>(
+    client: &VoClient,
+    url: &str,
+    estsize: Option<u64>,
+    dest: &Path,
+    mut progress: F,
+) -> Result<u64, Error>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let start = Instant::now();
+    let res = client.open(&Request::get(url)).await?;
+    let content_length = content_length(res.headers());
+    let total = content_length.or_else(|| estsize.map(|size| size * 1024));
+    let part = part_path(dest);
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&part).await.map_err(Error::Io)?;
+        let mut body = res.into_body();
+        let mut received = 0;
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(body_error)?;
+            file.write_all(&chunk).await.map_err(Error::Io)?;
+            received += chunk.len() as u64;
+            progress(received, total);
+        }
+        file.sync_all().await.map_err(Error::Io)?;
+        check_size(url, received, content_length, estsize)?;
+        tokio::fs::rename(&part, dest).await.map_err(Error::Io)?;
+        Ok(received)
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&part).await;
+    }
+    log_result(&result, url, dest, start);
+    result
+}
+
+/// Blocking version of [`download`](fn.download.html).
+#[cfg(feature = "blocking")]
+pub(crate) fn download_blocking<F>(
+    client: &VoClient,
+    url: &str,
+    estsize: Option<u64>,
+    dest: &Path,
+    mut progress: F,
+) -> Result<u64, Error>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let start = Instant::now();
+    let res = client.open_blocking(&Request::get(url))?;
+    let content_length = content_length(res.headers());
+    let total = content_length.or_else(|| estsize.map(|size| size * 1024));
+    let part = part_path(dest);
+
+    let result = (|| {
+        let mut file = File::create(&part).map_err(Error::Io)?;
+        let mut body = res.into_body();
+        let mut buf = vec![0; BUFFER_LEN];
+        let mut received = 0;
+        loop {
+            let n = body
+                .read(&mut buf)
+                .map_err(|e| Error::Client(vo_client::Error::Io(e)))?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n]).map_err(Error::Io)?;
+            received += n as u64;
+            progress(received, total);
+        }
+        file.sync_all().map_err(Error::Io)?;
+        check_size(url, received, content_length, estsize)?;
+        fs::rename(&part, dest).map_err(Error::Io)?;
+        Ok(received)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&part);
+    }
+    log_result(&result, url, dest, start);
+    result
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok())
+}
+
+/// Check the size of a dataset of `received` bytes.
+///
+/// Only the Content-Length announced by the server is enforced, since
+/// `estsize` is merely an estimate.
+fn check_size(
+    url: &str,
+    received: u64,
+    content_length: Option<u64>,
+    estsize: Option<u64>,
+) -> Result<(), Error> {
+    if let Some(expected) = content_length {
+        if received != expected {
+            return Err(Error::SizeMismatch { expected, received });
+        }
+    }
+    if let Some(estsize) = estsize {
+        if !plausible_size(received, estsize) {
+            warn!(
+                "Downloaded {} bytes from {}, which was estimated at {} kB",
+                received, url, estsize
+            );
+        }
+    }
+    Ok(())
+}
+
+fn log_result(result: &Result<u64, Error>, url: &str, dest: &Path, start: Instant) {
+    match result {
+        Ok(received) => debug!(
+            "Downloaded {} bytes from {} to {} in {:?}",
+            received,
+            url,
+            dest.display(),
+            start.elapsed()
+        ),
+        Err(e) => debug!("Download of {} failed: {}", url, e),
+    }
+}
+
+/// Path of the temporary file used while downloading to `dest`.
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Whether a dataset of `received` bytes matches an estimated size of
+/// `estsize` kilobytes.
+///
+/// The size is only an estimate, so only gross mismatches are reported, such
+/// as an error page served instead of the dataset.
+fn plausible_size(received: u64, estsize: u64) -> bool {
+    let kilobytes = received.div_ceil(1024);
+    estsize == 0 || (kilobytes * 2 >= estsize && kilobytes <= estsize * 2)
+}
+
+#[cfg(feature = "tokio")]
+fn body_error(e: hyper::Error) -> Error {
+    Error::Client(vo_client::Error::Hyper(e))
+}
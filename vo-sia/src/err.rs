@@ -4,22 +4,59 @@ use std::io;
 
 #[derive(Debug)]
 pub enum Error {
-    Hyper(hyper::Error),
-    InvalidUri(hyper::http::uri::InvalidUri),
+    Client(vo_client::Error),
+    InvalidUri(http::uri::InvalidUri),
     VOTable(vo_table::Error),
-    RuntimeError(io::Error, &'static str),
+    /// Coordinates cannot be converted, e.g. because their frame is not
+    /// supported.
+    Coords(vo_coords::Error),
+    Io(io::Error),
+    /// The region of a query is invalid.
+    InvalidPos(String),
+    /// An interval of a query is invalid.
+    InvalidInterval(String),
+    /// The record has no access URL.
+    MissingAccessUrl,
+    /// The downloaded size does not match the size announced by the
+    /// server.
+    SizeMismatch {
+        expected: u64,
+        received: u64,
+    },
+}
+
+impl From<vo_client::Error> for Error {
+    fn from(e: vo_client::Error) -> Self {
+        Error::Client(e)
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Error::*;
         match self {
-            Hyper(e) => write!(f, "HTTP error. {}", e),
+            Client(e) => write!(f, "{}", e),
             InvalidUri(e) => write!(f, "Invalid URL. {}", e),
             VOTable(e) => write!(f, "VOTable error. {}", e),
-            RuntimeError(e, msg) => write!(f, "Runtime error. {}, caused by {}", msg, e),
+            Coords(e) => write!(f, "{}", e),
+            Io(e) => write!(f, "I/O error. {}", e),
+            InvalidPos(message) => write!(f, "Invalid position. {}", message),
+            InvalidInterval(message) => write!(f, "Invalid interval. {}", message),
+            MissingAccessUrl => write!(f, "Record has no access URL"),
+            SizeMismatch { expected, received } => write!(
+                f,
+                "Expected {} bytes, but received {} bytes",
+                expected, received
+            ),
         }
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Client(e) => e.source(),
+            _ => None,
+        }
+    }
+}
@@ -1,34 +1,70 @@
-extern crate futures;
-extern crate hyper;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate tokio;
-extern crate url;
-extern crate vo_table;
+//! Client for the Simple Image Access protocol, versions 1.0 and 2.0.
+//!
+//! Queries can be executed asynchronously on a Tokio runtime, with the
+//! `tokio` feature enabled by default, or from blocking code with
+//! [`execute_sync`](struct.SiaQuery.html#method.execute_sync). With the
+//! `blocking` feature, blocking functions send their requests without any
+//! asynchronous runtime, so that blocking-only applications may disable the
+//! `tokio` feature. A [`BlockingClient`](struct.BlockingClient.html) reuses
+//! its connections and runtime between blocking queries.
 
+#[cfg(not(any(feature = "tokio", feature = "blocking")))]
+compile_error!("Either the `tokio` or the `blocking` feature of vo-sia must be enabled.");
+
+mod blocking;
+mod download;
 mod err;
+mod manager;
+mod obscore;
+mod pos;
+
+use std::path::Path;
 
-use hyper::rt::{Future, Stream};
-use hyper::Client;
+#[cfg(feature = "tokio")]
+use futures_util::{Stream, TryStreamExt};
+#[cfg(feature = "tokio")]
+use hyper::body::Bytes;
+use serde_derive::{Deserialize, Serialize};
+use vo_client::service::{self, Request};
+use vo_client::ResponseHook;
+#[cfg(all(feature = "tokio", not(feature = "blocking")))]
+use vo_client::Runtime;
 use vo_table::VOTable;
 
-pub use err::Error;
+pub use crate::blocking::BlockingClient;
+pub use crate::err::Error;
+pub use crate::manager::{DownloadItem, DownloadManager, DownloadResult, DownloadStatus};
+pub use crate::pos::{PolygonPos, Pos};
+pub use vo_client::{Error as ClientError, RawResponse, VoClient, VoClientBuilder};
+pub use vo_coords::{Coord, Frame};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SiaService<U> {
     url: U,
+    #[serde(default)]
+    version: SiaVersion,
+}
+
+/// Version of the SIA protocol spoken by a service.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SiaVersion {
+    V1,
+    #[default]
+    V2,
 }
 
 impl SiaService<&'static str> {
     pub const CADC: SiaService<&'static str> = SiaService {
         url: "http://www.cadc-ccda.hia-iha.nrc-cnrc.gc.ca/sia/v2query",
+        version: SiaVersion::V2,
     };
     pub const GAVO: SiaService<&'static str> = SiaService {
         url: "http://dc.zah.uni-heidelberg.de/__system__/siap2/sitewide/siap2.xml",
+        version: SiaVersion::V2,
     };
     pub const GAVO_OLD_V1: SiaService<&'static str> = SiaService {
         url: "http://dc.zah.uni-heidelberg.de/hppunion/q/im/siap.xml",
+        version: SiaVersion::V1,
     };
 }
 
@@ -36,8 +72,21 @@ impl<U> SiaService<U>
 where
     U: ToString,
 {
+    /// Create an SIA 2.0 service.
     pub fn new(url: U) -> SiaService<U> {
-        SiaService { url }
+        SiaService {
+            url,
+            version: SiaVersion::V2,
+        }
+    }
+
+    pub fn with_version(mut self, version: SiaVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn version(&self) -> SiaVersion {
+        self.version
     }
 
     pub fn map<F, V>(self, mut f: F) -> SiaService<V>
@@ -45,115 +94,127 @@ where
         F: FnMut(U) -> V,
         V: ToString,
     {
-        SiaService { url: f(self.url) }
+        SiaService {
+            url: f(self.url),
+            version: self.version,
+        }
     }
 
     pub fn create_query<'k, P: Into<Pos>>(&self, pos: P) -> SiaQuery<'k> {
         SiaQuery {
             base_url: self.url.to_string(),
-            pos: pos.into(),
-            // size: (1.0, 1.0),
-            format: None,
-            // intersect: Intersect::Overlaps,
-            // verbosity: Verbosity::VV,
+            version: self.version,
+            pos: vec![pos.into()],
+            size: None,
+            formats: vec![],
+            intersect: None,
+            verbosity: None,
+            bands: vec![],
+            times: vec![],
+            pols: vec![],
+            fovs: vec![],
+            spatres: vec![],
+            exptimes: vec![],
+            ids: vec![],
+            collections: vec![],
+            facilities: vec![],
+            instruments: vec![],
+            dptypes: vec![],
+            calibs: vec![],
+            targets: vec![],
+            timeres: vec![],
+            specrps: vec![],
+            maxrec: None,
+            response_format: None,
+            naxis: None,
+            cframe: None,
+            equinox: None,
+            crpix: None,
+            crval: None,
+            cdelt: None,
+            rotang: None,
+            proj: None,
             keywords: vec![],
+            response_hook: None,
         }
     }
 }
 
+/// A query to an SIA service.
+///
+/// Parameters that can be repeated are combined with a logical OR, while
+/// distinct parameters are combined with a logical AND.
+///
+/// Parameters are only sent if the version of the service supports them.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SiaQuery<'k> {
     base_url: String,
-    pos: Pos,
-    // size: (f64, f64),
-    format: Option<Format>,
-    // intersect: Intersect,
-    // verbosity: Verbosity,
+    version: SiaVersion,
+    pos: Vec<Pos>,
+    size: Option<(f64, f64)>,
+    formats: Vec<Format>,
+    intersect: Option<Intersect>,
+    verbosity: Option<Verbosity>,
+    bands: Vec<Interval>,
+    times: Vec<Interval>,
+    pols: Vec<Polarization>,
+    fovs: Vec<Interval>,
+    spatres: Vec<Interval>,
+    exptimes: Vec<Interval>,
+    ids: Vec<String>,
+    collections: Vec<String>,
+    facilities: Vec<String>,
+    instruments: Vec<String>,
+    dptypes: Vec<DataProductType>,
+    calibs: Vec<CalibLevel>,
+    targets: Vec<String>,
+    timeres: Vec<Interval>,
+    specrps: Vec<Interval>,
+    maxrec: Option<u64>,
+    response_format: Option<String>,
+    naxis: Option<(u32, u32)>,
+    cframe: Option<CoordFrame>,
+    equinox: Option<f64>,
+    crpix: Option<(f64, f64)>,
+    crval: Option<(f64, f64)>,
+    cdelt: Option<(f64, f64)>,
+    rotang: Option<f64>,
+    proj: Option<String>,
     keywords: Vec<(&'k str, &'k str)>,
+    response_hook: Option<ResponseHook>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Pos {
-    Circle {
-        longitude: f64,
-        latitude: f64,
-        radius: f64,
-    },
-    Range {
-        longitude1: f64,
-        longitude2: f64,
-        latitude1: f64,
-        latitude2: f64,
-    },
-    Polygon(PolygonPos),
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct PolygonPos(Vec<(f64, f64)>);
-
-impl Pos {
-    fn serialize(&self) -> String {
-        match self {
-            Pos::Circle {
-                longitude,
-                latitude,
-                radius,
-            } => format!("CIRCLE {} {} {}", longitude, latitude, radius),
-            Pos::Range {
-                longitude1,
-                longitude2,
-                latitude1,
-                latitude2,
-            } => format!(
-                "RANGE {} {} {} {}",
-                longitude1, longitude2, latitude1, latitude2
-            ),
-            Pos::Polygon(pos) => pos.serialize(),
-        }
-    }
-}
-
-impl From<(f64, f64)> for Pos {
-    fn from(pos: (f64, f64)) -> Pos {
-        Pos::Circle {
-            longitude: pos.0,
-            latitude: pos.1,
-            radius: 1.0,
-        }
-    }
-}
-
-impl PolygonPos {
-    fn serialize(&self) -> String {
-        use std::fmt::Write;
-
-        let mut string = String::from("POLYGON");
-        for &(long, lat) in self.0.iter() {
-            let _ = write!(&mut string, "{} {}", long, lat);
-        }
-        string
-    }
-}
-
+/// Format of the images.
+///
+/// `All`, `Graphic` and `Metadata` are only defined by SIA 1.0.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Format {
-    // All,
-    // Graphic,
-    // Metadata,
+    All,
+    Graphic,
+    Metadata,
     Fits,
-    // Jpeg,
-    // Png,
+    Jpeg,
+    Png,
 }
 
 impl From<Format> for &'static str {
     fn from(format: Format) -> &'static str {
         match format {
-            // Format::All => "ALL",
-            // Format::Graphic => "GRAPHIC",
-            // Format::Metadata => "METADATA",
+            Format::All => "ALL",
+            Format::Graphic => "GRAPHIC",
+            Format::Metadata => "METADATA",
             Format::Fits => "application/fits",
-            // Format::Jpeg => "IMAGE/JPEG",
-            // Format::Png => "IMAGE/PNG",
+            Format::Jpeg => "image/jpeg",
+            Format::Png => "image/png",
+        }
+    }
+}
+
+impl Format {
+    fn serialize(self, version: SiaVersion) -> &'static str {
+        match (self, version) {
+            (Format::Fits, SiaVersion::V1) => "image/fits",
+            _ => self.into(),
         }
     }
 }
@@ -185,81 +246,690 @@ pub enum Verbosity {
     VVV = 3,
 }
 
+/// Celestial coordinate frame of the images, for SIA 1.0 cutout services.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CoordFrame {
+    ICRS,
+    FK5,
+    FK4,
+    Ecliptic,
+    Galactic,
+    Supergalactic,
+}
+
+impl From<CoordFrame> for &'static str {
+    fn from(cframe: CoordFrame) -> &'static str {
+        match cframe {
+            CoordFrame::ICRS => "ICRS",
+            CoordFrame::FK5 => "FK5",
+            CoordFrame::FK4 => "FK4",
+            CoordFrame::Ecliptic => "ECL",
+            CoordFrame::Galactic => "GAL",
+            CoordFrame::Supergalactic => "SGAL",
+        }
+    }
+}
+
+/// Closed interval of numerical values.
+///
+/// Use infinite bounds for open-ended intervals.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Interval {
+    pub fn new(min: f64, max: f64) -> Self {
+        Interval { min, max }
+    }
+
+    /// Interval between `min` and `max`, which must not be NaN and must be
+    /// in increasing order.
+    ///
+    /// ```
+    /// # extern crate vo_sia;
+    /// # use vo_sia::Interval;
+    /// # fn main() {
+    /// assert_eq!(Interval::checked(1.0, 2.0).unwrap(), Interval::new(1.0, 2.0));
+    /// assert!(Interval::checked(2.0, 1.0).is_err());
+    /// assert!(Interval::checked(f64::NAN, 1.0).is_err());
+    /// # }
+    /// ```
+    pub fn checked(min: f64, max: f64) -> Result<Self, Error> {
+        let interval = Interval::new(min, max);
+        interval.validate()?;
+        Ok(interval)
+    }
+
+    /// Interval containing the single value `value`.
+    pub fn exact(value: f64) -> Self {
+        Interval::new(value, value)
+    }
+
+    pub fn at_least(min: f64) -> Self {
+        Interval::new(min, f64::INFINITY)
+    }
+
+    pub fn at_most(max: f64) -> Self {
+        Interval::new(f64::NEG_INFINITY, max)
+    }
+
+    /// Check that the bounds are not NaN and are in increasing order.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.min <= self.max {
+            Ok(())
+        } else {
+            Err(Error::InvalidInterval(format!(
+                "Bounds must be in increasing order, got {}",
+                self.serialize()
+            )))
+        }
+    }
+
+    /// Serialize as specified by DALI, with "-Inf" and "+Inf" for infinite
+    /// bounds.
+    fn serialize(&self) -> String {
+        fn bound(value: f64) -> String {
+            if value == f64::INFINITY {
+                "+Inf".to_owned()
+            } else if value == f64::NEG_INFINITY {
+                "-Inf".to_owned()
+            } else {
+                value.to_string()
+            }
+        }
+        format!("{} {}", bound(self.min), bound(self.max))
+    }
+}
+
+impl From<(f64, f64)> for Interval {
+    fn from((min, max): (f64, f64)) -> Self {
+        Interval::new(min, max)
+    }
+}
+
+impl From<f64> for Interval {
+    fn from(value: f64) -> Self {
+        Interval::exact(value)
+    }
+}
+
+/// Polarization states, as defined by ObsCore.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Polarization {
+    I,
+    Q,
+    U,
+    V,
+    RR,
+    LL,
+    RL,
+    LR,
+    XX,
+    YY,
+    XY,
+    YX,
+    POLI,
+    POLA,
+}
+
+impl From<Polarization> for &'static str {
+    fn from(pol: Polarization) -> &'static str {
+        match pol {
+            Polarization::I => "I",
+            Polarization::Q => "Q",
+            Polarization::U => "U",
+            Polarization::V => "V",
+            Polarization::RR => "RR",
+            Polarization::LL => "LL",
+            Polarization::RL => "RL",
+            Polarization::LR => "LR",
+            Polarization::XX => "XX",
+            Polarization::YY => "YY",
+            Polarization::XY => "XY",
+            Polarization::YX => "YX",
+            Polarization::POLI => "POLI",
+            Polarization::POLA => "POLA",
+        }
+    }
+}
+
+impl Polarization {
+    fn from_code(code: &str) -> Option<Self> {
+        match code.trim() {
+            "I" => Some(Polarization::I),
+            "Q" => Some(Polarization::Q),
+            "U" => Some(Polarization::U),
+            "V" => Some(Polarization::V),
+            "RR" => Some(Polarization::RR),
+            "LL" => Some(Polarization::LL),
+            "RL" => Some(Polarization::RL),
+            "LR" => Some(Polarization::LR),
+            "XX" => Some(Polarization::XX),
+            "YY" => Some(Polarization::YY),
+            "XY" => Some(Polarization::XY),
+            "YX" => Some(Polarization::YX),
+            "POLI" => Some(Polarization::POLI),
+            "POLA" => Some(Polarization::POLA),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DataProductType {
+    Image,
+    Cube,
+}
+
+impl From<DataProductType> for &'static str {
+    fn from(dptype: DataProductType) -> &'static str {
+        match dptype {
+            DataProductType::Image => "image",
+            DataProductType::Cube => "cube",
+        }
+    }
+}
+
+/// Calibration level, as defined by ObsCore.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CalibLevel {
+    Raw = 0,
+    Instrumental = 1,
+    Calibrated = 2,
+    Enhanced = 3,
+    Analysis = 4,
+}
+
+impl CalibLevel {
+    fn from_i64(level: i64) -> Option<Self> {
+        match level {
+            0 => Some(CalibLevel::Raw),
+            1 => Some(CalibLevel::Instrumental),
+            2 => Some(CalibLevel::Calibrated),
+            3 => Some(CalibLevel::Enhanced),
+            4 => Some(CalibLevel::Analysis),
+            _ => None,
+        }
+    }
+}
+
 impl<'k> SiaQuery<'k> {
+    /// Search another region, in addition to the region of the query.
+    ///
+    /// SIA 1.0 only supports one region, so only the first region is sent to
+    /// SIA 1.0 services.
+    pub fn with_pos<P: Into<Pos>>(mut self, pos: P) -> Self {
+        self.pos.push(pos.into());
+        self
+    }
+
     pub fn with_format(mut self, format: Format) -> Self {
-        self.format = Some(format);
-        self
-    }
-
-    pub fn execute(&self) -> impl Future<Item = SIAResults, Error = Error> {
-        let client = Client::new();
-
-        futures::future::result(self.query_url().parse::<hyper::Uri>())
-            .map_err(Error::InvalidUri)
-            .and_then(move |uri| {
-                client
-                    .get(uri)
-                    .and_then(|res| res.into_body().concat2())
-                    .map_err(Error::Hyper)
-                    .and_then(|body| {
-                        use std::io::Cursor;
-                        println!("{}", String::from_utf8_lossy(&body));
-                        let read = Cursor::new(body);
-                        vo_table::parse(read)
-                            .map(|table| SIAResults { table })
-                            .map_err(Error::VOTable)
-                    })
-            })
+        self.formats.push(format);
+        self
+    }
+
+    /// Size of the region of interest in degrees, along longitude and
+    /// latitude. Not part of SIA 2.0, though some services use it.
+    pub fn with_size(mut self, longitude: f64, latitude: f64) -> Self {
+        self.size = Some((longitude, latitude));
+        self
+    }
+
+    pub fn with_intersect(mut self, intersect: Intersect) -> Self {
+        self.intersect = Some(intersect);
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+
+    /// Energy interval, as wavelength in meters.
+    pub fn with_band<I: Into<Interval>>(mut self, band: I) -> Self {
+        self.bands.push(band.into());
+        self
+    }
+
+    /// Time interval, in Modified Julian Date.
+    pub fn with_time<I: Into<Interval>>(mut self, time: I) -> Self {
+        self.times.push(time.into());
+        self
+    }
+
+    pub fn with_pol(mut self, pol: Polarization) -> Self {
+        self.pols.push(pol);
+        self
+    }
+
+    /// Range of field of view, in degrees.
+    pub fn with_fov<I: Into<Interval>>(mut self, fov: I) -> Self {
+        self.fovs.push(fov.into());
+        self
+    }
+
+    /// Range of spatial resolution, in arcseconds.
+    pub fn with_spatres<I: Into<Interval>>(mut self, spatres: I) -> Self {
+        self.spatres.push(spatres.into());
+        self
+    }
+
+    /// Range of exposure time, in seconds.
+    pub fn with_exptime<I: Into<Interval>>(mut self, exptime: I) -> Self {
+        self.exptimes.push(exptime.into());
+        self
+    }
+
+    /// Publisher dataset identifier.
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.ids.push(id.into());
+        self
     }
 
+    pub fn with_collection<S: Into<String>>(mut self, collection: S) -> Self {
+        self.collections.push(collection.into());
+        self
+    }
+
+    pub fn with_facility<S: Into<String>>(mut self, facility: S) -> Self {
+        self.facilities.push(facility.into());
+        self
+    }
+
+    pub fn with_instrument<S: Into<String>>(mut self, instrument: S) -> Self {
+        self.instruments.push(instrument.into());
+        self
+    }
+
+    pub fn with_dptype(mut self, dptype: DataProductType) -> Self {
+        self.dptypes.push(dptype);
+        self
+    }
+
+    pub fn with_calib(mut self, calib: CalibLevel) -> Self {
+        self.calibs.push(calib);
+        self
+    }
+
+    pub fn with_target<S: Into<String>>(mut self, target: S) -> Self {
+        self.targets.push(target.into());
+        self
+    }
+
+    /// Range of temporal resolution, in seconds.
+    pub fn with_timeres<I: Into<Interval>>(mut self, timeres: I) -> Self {
+        self.timeres.push(timeres.into());
+        self
+    }
+
+    /// Range of spectral resolving power.
+    pub fn with_specrp<I: Into<Interval>>(mut self, specrp: I) -> Self {
+        self.specrps.push(specrp.into());
+        self
+    }
+
+    /// Maximum number of records to return.
+    pub fn with_maxrec(mut self, maxrec: u64) -> Self {
+        self.maxrec = Some(maxrec);
+        self
+    }
+
+    /// MIME type of the response.
+    pub fn with_response_format<S: Into<String>>(mut self, response_format: S) -> Self {
+        self.response_format = Some(response_format.into());
+        self
+    }
+
+    /// Number of pixels of the output image along each axis. SIA 1.0 only.
+    pub fn with_naxis(mut self, naxis1: u32, naxis2: u32) -> Self {
+        self.naxis = Some((naxis1, naxis2));
+        self
+    }
+
+    /// Coordinate frame of the output image. SIA 1.0 only.
+    pub fn with_cframe(mut self, cframe: CoordFrame) -> Self {
+        self.cframe = Some(cframe);
+        self
+    }
+
+    /// Equinox of the coordinate frame, in years. SIA 1.0 only.
+    pub fn with_equinox(mut self, equinox: f64) -> Self {
+        self.equinox = Some(equinox);
+        self
+    }
+
+    /// Reference pixel of the output image. SIA 1.0 only.
+    pub fn with_crpix(mut self, crpix1: f64, crpix2: f64) -> Self {
+        self.crpix = Some((crpix1, crpix2));
+        self
+    }
+
+    /// World coordinates of the reference pixel, in degrees. SIA 1.0 only.
+    pub fn with_crval(mut self, crval1: f64, crval2: f64) -> Self {
+        self.crval = Some((crval1, crval2));
+        self
+    }
+
+    /// Scale of the output image, in degrees per pixel. SIA 1.0 only.
+    pub fn with_cdelt(mut self, cdelt1: f64, cdelt2: f64) -> Self {
+        self.cdelt = Some((cdelt1, cdelt2));
+        self
+    }
+
+    /// Rotation angle of the output image, in degrees. SIA 1.0 only.
+    pub fn with_rotang(mut self, rotang: f64) -> Self {
+        self.rotang = Some(rotang);
+        self
+    }
+
+    /// Celestial projection of the output image, as a three-letter FITS
+    /// code such as "TAN". SIA 1.0 only.
+    pub fn with_proj<S: Into<String>>(mut self, proj: S) -> Self {
+        self.proj = Some(proj.into());
+        self
+    }
+
+    /// Add a parameter not defined by the standard.
+    pub fn with_keyword(mut self, key: &'k str, value: &'k str) -> Self {
+        self.keywords.push((key, value));
+        self
+    }
+
+    /// Call `hook` with the raw response of the service when the query is
+    /// executed, e.g. to log or archive it.
+    pub fn with_response_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RawResponse) + Send + Sync + 'static,
+    {
+        self.response_hook = Some(ResponseHook::new(hook));
+        self
+    }
+
+    /// Execute the query with a new client.
+    ///
+    /// Prefer [`execute_with`](#method.execute_with) to share the
+    /// connections and configuration of a client between queries.
+    #[cfg(feature = "tokio")]
+    pub async fn execute(&self) -> Result<SIAResults, Error> {
+        self.execute_with(&VoClient::new()).await
+    }
+
+    /// Execute the query with `client`. The results keep the client to
+    /// download the datasets.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_with(&self, client: &VoClient) -> Result<SIAResults, Error> {
+        let table = client.query(&self.check_request()?).await?;
+        Ok(SIAResults {
+            table,
+            version: self.version,
+            client: client.clone(),
+            #[cfg(not(feature = "blocking"))]
+            runtime: None,
+        })
+    }
+
+    /// Blocking version of [`execute`](#method.execute).
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
     pub fn execute_sync(&self) -> Result<SIAResults, Error> {
-        let mut runtime = tokio::runtime::Runtime::new()
-            .map_err(|e| Error::RuntimeError(e, "Could not initialize a Tokio runtime."))?;
-        runtime.block_on(self.execute())
+        self.execute_sync_with(&VoClient::new())
     }
 
-    fn query_url(&self) -> String {
-        let pos_val = self.pos.serialize();
-        // let size_val = format!("{},{}", self.size.0, self.size.1);
-        // let verb_val = format!("{}", self.verbosity as usize);
-        let query_string = {
-            let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    /// Blocking version of [`execute_with`](#method.execute_with).
+    ///
+    /// Requests are sent by the blocking client with the `blocking` feature,
+    /// or else on a Tokio runtime shared by the blocking functions. See
+    /// [`BlockingClient`](struct.BlockingClient.html) to call blocking
+    /// functions from an asynchronous context.
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
+    pub fn execute_sync_with(&self, client: &VoClient) -> Result<SIAResults, Error> {
+        #[cfg(feature = "blocking")]
+        {
+            let table = client.query_blocking(&self.check_request()?)?;
+            Ok(SIAResults {
+                table,
+                version: self.version,
+                client: client.clone(),
+            })
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            self.execute_on(client, Runtime::shared()?)
+        }
+    }
 
-            serializer
-                .append_pair("POS", &pos_val)
-                // .append_pair("SIZE", &size_val)
-                // .append_pair("INTERSECT", self.intersect.into())
-                // .append_pair("VERB", &verb_val)
-                .extend_pairs(&self.keywords);
+    /// Execute the query with `client`, reusing its runtime for the query
+    /// and the downloads of the results.
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
+    pub fn execute_blocking(&self, client: &BlockingClient) -> Result<SIAResults, Error> {
+        #[cfg(feature = "blocking")]
+        {
+            self.execute_sync_with(client.client())
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            self.execute_on(client.client(), client.runtime().clone())
+        }
+    }
 
-            if let Some(format) = self.format {
-                serializer.append_pair("FORMAT", format.into());
-            }
+    #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+    fn execute_on(&self, client: &VoClient, runtime: Runtime) -> Result<SIAResults, Error> {
+        let mut results = runtime.block_on(self.execute_with(client))??;
+        results.runtime = Some(runtime);
+        Ok(results)
+    }
+
+    /// The request of the query, checked before it is sent.
+    fn check_request(&self) -> Result<Request, Error> {
+        for pos in &self.pos {
+            pos.validate()?;
+        }
+        let intervals = [
+            &self.bands,
+            &self.times,
+            &self.fovs,
+            &self.spatres,
+            &self.exptimes,
+            &self.timeres,
+            &self.specrps,
+        ];
+        for interval in intervals.iter().flat_map(|intervals| intervals.iter()) {
+            interval.validate()?;
+        }
+        let url = self.query_url();
+        url.parse::<http::Uri>().map_err(Error::InvalidUri)?;
+        Ok(Request::get(url).with_response_hook(self.response_hook.clone()))
+    }
 
+    pub fn query_url(&self) -> String {
+        let query_string = {
+            let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+            match self.version {
+                SiaVersion::V1 => self.serialize_v1(&mut serializer),
+                SiaVersion::V2 => self.serialize_v2(&mut serializer),
+            }
+            serializer.extend_pairs(&self.keywords);
             serializer.finish()
         };
         format!("{}?{}", self.base_url, query_string)
     }
+
+    fn serialize_v1(&self, serializer: &mut url::form_urlencoded::Serializer<String>) {
+        fn pair(values: (f64, f64)) -> String {
+            format!("{},{}", values.0, values.1)
+        }
+
+        let (center, size) = self.pos[0].center_and_size();
+        serializer.append_pair("POS", &pair(center));
+        serializer.append_pair("SIZE", &pair(self.size.unwrap_or(size)));
+        if let Some(intersect) = self.intersect {
+            serializer.append_pair("INTERSECT", intersect.into());
+        }
+        if let Some(verbosity) = self.verbosity {
+            serializer.append_pair("VERB", &(verbosity as usize).to_string());
+        }
+        if !self.formats.is_empty() {
+            let formats: Vec<_> = self
+                .formats
+                .iter()
+                .map(|format| format.serialize(SiaVersion::V1))
+                .collect();
+            serializer.append_pair("FORMAT", &formats.join(","));
+        }
+        if let Some((naxis1, naxis2)) = self.naxis {
+            serializer.append_pair("NAXIS", &format!("{},{}", naxis1, naxis2));
+        }
+        if let Some(cframe) = self.cframe {
+            serializer.append_pair("CFRAME", cframe.into());
+        }
+        if let Some(equinox) = self.equinox {
+            serializer.append_pair("EQUINOX", &equinox.to_string());
+        }
+        if let Some(crpix) = self.crpix {
+            serializer.append_pair("CRPIX", &pair(crpix));
+        }
+        if let Some(crval) = self.crval {
+            serializer.append_pair("CRVAL", &pair(crval));
+        }
+        if let Some(cdelt) = self.cdelt {
+            serializer.append_pair("CDELT", &pair(cdelt));
+        }
+        if let Some(rotang) = self.rotang {
+            serializer.append_pair("ROTANG", &rotang.to_string());
+        }
+        if let Some(proj) = &self.proj {
+            serializer.append_pair("PROJ", proj);
+        }
+    }
+
+    fn serialize_v2(&self, serializer: &mut url::form_urlencoded::Serializer<String>) {
+        for pos in &self.pos {
+            serializer.append_pair("POS", &pos.to_string());
+        }
+        if let Some((longitude, latitude)) = self.size {
+            serializer.append_pair("SIZE", &format!("{},{}", longitude, latitude));
+        }
+        if let Some(intersect) = self.intersect {
+            serializer.append_pair("INTERSECT", intersect.into());
+        }
+        if let Some(verbosity) = self.verbosity {
+            serializer.append_pair("VERB", &(verbosity as usize).to_string());
+        }
+        for band in &self.bands {
+            serializer.append_pair("BAND", &band.serialize());
+        }
+        for time in &self.times {
+            serializer.append_pair("TIME", &time.serialize());
+        }
+        for &pol in &self.pols {
+            serializer.append_pair("POL", pol.into());
+        }
+        for fov in &self.fovs {
+            serializer.append_pair("FOV", &fov.serialize());
+        }
+        for spatres in &self.spatres {
+            serializer.append_pair("SPATRES", &spatres.serialize());
+        }
+        for exptime in &self.exptimes {
+            serializer.append_pair("EXPTIME", &exptime.serialize());
+        }
+        for id in &self.ids {
+            serializer.append_pair("ID", id);
+        }
+        for collection in &self.collections {
+            serializer.append_pair("COLLECTION", collection);
+        }
+        for facility in &self.facilities {
+            serializer.append_pair("FACILITY", facility);
+        }
+        for instrument in &self.instruments {
+            serializer.append_pair("INSTRUMENT", instrument);
+        }
+        for &dptype in &self.dptypes {
+            serializer.append_pair("DPTYPE", dptype.into());
+        }
+        for &calib in &self.calibs {
+            serializer.append_pair("CALIB", &(calib as u8).to_string());
+        }
+        for target in &self.targets {
+            serializer.append_pair("TARGET", target);
+        }
+        for timeres in &self.timeres {
+            serializer.append_pair("TIMERES", &timeres.serialize());
+        }
+        for specrp in &self.specrps {
+            serializer.append_pair("SPECRP", &specrp.serialize());
+        }
+        for &format in &self.formats {
+            serializer.append_pair("FORMAT", format.serialize(SiaVersion::V2));
+        }
+        if let Some(maxrec) = self.maxrec {
+            serializer.append_pair("MAXREC", &maxrec.to_string());
+        }
+        if let Some(response_format) = &self.response_format {
+            serializer.append_pair("RESPONSEFORMAT", response_format);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SIAResults {
     table: VOTable,
+    version: SiaVersion,
+    /// Client used to retrieve the datasets
+    client: VoClient,
+    /// Runtime of the blocking downloads, if the query was executed on one
+    #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+    runtime: Option<Runtime>,
 }
 
 impl SIAResults {
+    /// Wrap a VOTable returned by a service of the given version.
+    pub fn new(table: VOTable, version: SiaVersion) -> Self {
+        SIAResults {
+            table,
+            version,
+            client: VoClient::new(),
+            #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+            runtime: None,
+        }
+    }
+
     pub fn records(&self) -> impl Iterator<Item = SIARecord<'_>> {
-        self.table
-            .resources()
-            .iter()
-            .map(|resource| {
-                resource
-                    .tables()
-                    .iter()
-                    .filter(|table| table.rows().is_some())
-                    .map(|table| table.rows().unwrap().map(|row| SIARecord { row }))
-                    .flatten()
-            }).flatten()
+        let version = self.version;
+        let client = &self.client;
+        let votable = &self.table;
+        #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+        let runtime = self.runtime.as_ref();
+        self.table.resources().iter().flat_map(move |resource| {
+            resource
+                .tables()
+                .iter()
+                .filter(|table| table.rows().is_some())
+                .flat_map(move |table| {
+                    table.rows().unwrap().map(move |row| SIARecord {
+                        row,
+                        votable,
+                        version,
+                        client,
+                        #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+                        runtime,
+                    })
+                })
+        })
+    }
+
+    pub fn version(&self) -> SiaVersion {
+        self.version
+    }
+
+    /// Value of the QUERY_STATUS INFO of the results, e.g. "OVERFLOW" if
+    /// they were truncated to the maximum number of records.
+    pub fn query_status(&self) -> Option<&str> {
+        service::query_status(&self.table).map(vo_table::Info::value)
     }
 
     pub fn table(&self) -> &VOTable {
@@ -271,46 +941,281 @@ impl SIAResults {
     }
 }
 
+/// A record of the results of a query.
+///
+/// SIA 2.0 results follow the ObsCore data model. Each column is looked up by
+/// name, then utype, then UCD. SIA 1.0 columns are identified by their UCD,
+/// which is tried first for SIA 1.0 results and last otherwise.
 #[derive(Debug, Clone, Copy)]
 pub struct SIARecord<'a> {
     row: vo_table::Row<'a>,
+    /// VOTable of the row, with the COOSYS elements of its columns
+    votable: &'a VOTable,
+    version: SiaVersion,
+    client: &'a VoClient,
+    #[cfg(all(feature = "tokio", not(feature = "blocking")))]
+    runtime: Option<&'a Runtime>,
 }
 
 impl<'a> SIARecord<'a> {
-    pub fn access_url(&self) -> Option<&str> {
-        self.row
-            .get_by_ucd("VOX:Image_AccessReference")
-            .or_else(|| self.row.get_by_id("access_url"))
-            .or_else(|| self.row.get_by_name("access_url"))
-            .and_then(|cell| match cell {
-                vo_table::Cell::Character(link) | vo_table::Cell::UnicodeCharacter(link) => {
-                    Some(link.as_ref())
-                }
-                _ => None,
-            })
+    pub fn version(&self) -> SiaVersion {
+        self.version
     }
 
-    pub fn access_format(&self) -> Option<&str> {
-        self.row
-            .get_by_id("access_format")
-            .or_else(|| self.row.get_by_name("access_format"))
-            .and_then(|cell| match cell {
-                vo_table::Cell::Character(link) | vo_table::Cell::UnicodeCharacter(link) => {
-                    Some(link.as_ref())
-                }
-                _ => None,
-            })
+    fn get(&self, column: &obscore::Column) -> Option<vo_table::Cell<'a>> {
+        let v1 = || column.v1_ucd.and_then(|ucd| self.row.get_by_ucd(ucd));
+        let v2 = || {
+            self.row
+                .get_by_id(column.name)
+                .or_else(|| self.row.get_by_name(column.name))
+                .or_else(|| self.row.get_by_utype(column.utype))
+                .or_else(|| column.ucd.and_then(|ucd| self.row.get_by_ucd(ucd)))
+        };
+        match self.version {
+            SiaVersion::V1 => v1().or_else(v2),
+            SiaVersion::V2 => v2().or_else(v1),
+        }
+    }
+
+    /// `ref` attribute of the field of `column`, looked up like its value.
+    fn get_ref(&self, column: &obscore::Column) -> Option<&'a str> {
+        let v1 = || column.v1_ucd.and_then(|ucd| self.row.ref_by_ucd(ucd));
+        let v2 = || {
+            self.row
+                .ref_by_id(column.name)
+                .or_else(|| self.row.ref_by_name(column.name))
+                .or_else(|| self.row.ref_by_utype(column.utype))
+                .or_else(|| column.ucd.and_then(|ucd| self.row.ref_by_ucd(ucd)))
+        };
+        match self.version {
+            SiaVersion::V1 => v1().or_else(v2),
+            SiaVersion::V2 => v2().or_else(v1),
+        }
     }
 
+    fn get_str(&self, column: &obscore::Column) -> Option<&'a str> {
+        self.get(column).and_then(|cell| cell.as_str())
+    }
+
+    fn get_f64(&self, column: &obscore::Column) -> Option<f64> {
+        self.get(column).and_then(|cell| cell.as_f64())
+    }
+
+    /// Type of the data product, e.g. "image" or "cube".
+    pub fn dataproduct_type(&self) -> Option<&'a str> {
+        self.get_str(&obscore::DATAPRODUCT_TYPE)
+    }
+
+    pub fn calib_level(&self) -> Option<CalibLevel> {
+        self.get(&obscore::CALIB_LEVEL)
+            .and_then(|cell| cell.as_i64())
+            .and_then(CalibLevel::from_i64)
+    }
+
+    pub fn obs_collection(&self) -> Option<&'a str> {
+        self.get_str(&obscore::OBS_COLLECTION)
+    }
+
+    pub fn obs_id(&self) -> Option<&'a str> {
+        self.get_str(&obscore::OBS_ID)
+    }
+
+    pub fn obs_title(&self) -> Option<&'a str> {
+        self.get_str(&obscore::OBS_TITLE)
+    }
+
+    pub fn obs_publisher_did(&self) -> Option<&'a str> {
+        self.get_str(&obscore::OBS_PUBLISHER_DID)
+    }
+
+    pub fn access_url(&self) -> Option<&'a str> {
+        self.get_str(&obscore::ACCESS_URL)
+    }
+
+    pub fn access_format(&self) -> Option<&'a str> {
+        self.get_str(&obscore::ACCESS_FORMAT)
+    }
+
+    /// Estimated size of the dataset, in kilobytes.
     pub fn access_estsize(&self) -> Option<i64> {
-        self.row
-            .get_by_id("access_estsize")
-            .or_else(|| self.row.get_by_name("access_estsize"))
-            .and_then(|cell| match cell {
-                vo_table::Cell::Integer16(size) => size[0].map(|int| int as i64),
-                vo_table::Cell::Integer32(size) => size[0].map(|int| int as i64),
-                vo_table::Cell::Integer64(size) => size[0],
-                _ => None,
-            })
+        let kilobytes = || {
+            self.get(&obscore::ACCESS_ESTSIZE)
+                .and_then(|cell| cell.as_i64())
+        };
+        // SIA 1.0 gives the size in bytes
+        let bytes = || {
+            self.row
+                .get_by_ucd("VOX:Image_FileSize")
+                .and_then(|cell| cell.as_i64())
+                .map(|bytes| (bytes + 1023) / 1024)
+        };
+        match self.version {
+            SiaVersion::V1 => bytes().or_else(kilobytes),
+            SiaVersion::V2 => kilobytes().or_else(bytes),
+        }
+    }
+
+    pub fn target_name(&self) -> Option<&'a str> {
+        self.get_str(&obscore::TARGET_NAME)
+    }
+
+    /// Right ascension of the center of the observation, in degrees.
+    pub fn s_ra(&self) -> Option<f64> {
+        self.get_f64(&obscore::S_RA)
+    }
+
+    /// Declination of the center of the observation, in degrees.
+    pub fn s_dec(&self) -> Option<f64> {
+        self.get_f64(&obscore::S_DEC)
+    }
+
+    /// Center of the observation in ICRS, converted from the coordinate
+    /// system given by the COOSYS referenced by the right ascension column.
+    /// Positions without COOSYS are assumed to be in ICRS.
+    ///
+    /// Fails if the coordinate system is not supported.
+    pub fn position(&self) -> Result<Option<Coord>, Error> {
+        let (longitude, latitude) = match (self.s_ra(), self.s_dec()) {
+            (Some(longitude), Some(latitude)) => (longitude, latitude),
+            _ => return Ok(None),
+        };
+        let frame = match self
+            .get_ref(&obscore::S_RA)
+            .and_then(|id| self.votable.coosys_by_id(id))
+        {
+            Some(coosys) => {
+                Frame::from_coosys(coosys.system(), coosys.equinox()).map_err(Error::Coords)?
+            }
+            None => Frame::ICRS,
+        };
+        Coord::from_frame(frame, longitude, latitude)
+            .map(Some)
+            .map_err(Error::Coords)
+    }
+
+    /// Diameter of the covered region, in degrees.
+    pub fn s_fov(&self) -> Option<f64> {
+        self.get_f64(&obscore::S_FOV)
+    }
+
+    /// Covered region, as an STC-S or DALI string.
+    pub fn s_region(&self) -> Option<&'a str> {
+        self.get_str(&obscore::S_REGION)
+    }
+
+    /// Spatial resolution, in arcseconds.
+    pub fn s_resolution(&self) -> Option<f64> {
+        self.get_f64(&obscore::S_RESOLUTION)
+    }
+
+    /// Start time, in Modified Julian Date.
+    pub fn t_min(&self) -> Option<f64> {
+        self.get_f64(&obscore::T_MIN)
+    }
+
+    /// Stop time, in Modified Julian Date.
+    pub fn t_max(&self) -> Option<f64> {
+        self.get_f64(&obscore::T_MAX)
+    }
+
+    /// Total exposure time, in seconds.
+    pub fn t_exptime(&self) -> Option<f64> {
+        self.get_f64(&obscore::T_EXPTIME)
+    }
+
+    /// Minimum wavelength, in meters.
+    pub fn em_min(&self) -> Option<f64> {
+        self.get_f64(&obscore::EM_MIN)
+    }
+
+    /// Maximum wavelength, in meters.
+    pub fn em_max(&self) -> Option<f64> {
+        self.get_f64(&obscore::EM_MAX)
+    }
+
+    pub fn em_res_power(&self) -> Option<f64> {
+        self.get_f64(&obscore::EM_RES_POWER)
+    }
+
+    /// Polarization states. Unknown states are skipped.
+    pub fn pol_states(&self) -> Option<Vec<Polarization>> {
+        self.get_str(&obscore::POL_STATES).map(|states| {
+            states
+                .split('/')
+                .filter_map(Polarization::from_code)
+                .collect()
+        })
+    }
+
+    pub fn facility_name(&self) -> Option<&'a str> {
+        self.get_str(&obscore::FACILITY_NAME)
+    }
+
+    pub fn instrument_name(&self) -> Option<&'a str> {
+        self.get_str(&obscore::INSTRUMENT_NAME)
+    }
+
+    /// Stream the content of the dataset, following redirections.
+    #[cfg(feature = "tokio")]
+    pub fn fetch(&self) -> impl Stream<Item = Result<Bytes, Error>> + Send {
+        let url = self.access_url().map(str::to_owned);
+        let client = self.client.clone();
+        futures_util::stream::once(async move { url.ok_or(Error::MissingAccessUrl) })
+            .map_ok(move |url| download::fetch(client.clone(), url))
+            .try_flatten()
+    }
+
+    /// Download the dataset to `dest`, and return the number of bytes
+    /// written.
+    ///
+    /// The file is written to a temporary file next to `dest`, which is only
+    /// renamed to `dest` once the download is complete and its size matches
+    /// the size announced by the server. A warning is logged if the size is
+    /// far from `access_estsize`.
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
+    pub fn download<P: AsRef<Path>>(&self, dest: P) -> Result<u64, Error> {
+        self.download_with_progress(dest, |_, _| ())
+    }
+
+    /// Same as [`download`](#method.download), calling `progress` with the
+    /// number of bytes received so far and the expected total, if known.
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
+    pub fn download_with_progress<P, F>(&self, dest: P, progress: F) -> Result<u64, Error>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, Option<u64>),
+    {
+        #[cfg(feature = "blocking")]
+        {
+            let url = self.access_url().ok_or(Error::MissingAccessUrl)?;
+            download::download_blocking(self.client, url, self.estsize(), dest.as_ref(), progress)
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            let runtime = match self.runtime {
+                Some(runtime) => runtime.clone(),
+                None => Runtime::shared()?,
+            };
+            runtime.block_on(self.download_async(dest, progress))?
+        }
+    }
+
+    /// Asynchronous version of
+    /// [`download_with_progress`](#method.download_with_progress).
+    #[cfg(feature = "tokio")]
+    pub async fn download_async<P, F>(&self, dest: P, progress: F) -> Result<u64, Error>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, Option<u64>),
+    {
+        let url = self.access_url().ok_or(Error::MissingAccessUrl)?;
+        download::download(self.client, url, self.estsize(), dest.as_ref(), progress).await
+    }
+
+    /// Estimated size of the dataset in kilobytes, if valid.
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
+    fn estsize(&self) -> Option<u64> {
+        self.access_estsize()
+            .and_then(|size| if size >= 0 { Some(size as u64) } else { None })
     }
 }
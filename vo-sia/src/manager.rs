@@ -0,0 +1,398 @@
+//! Concurrent download of many datasets.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "blocking")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "blocking")]
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "tokio")]
+use futures_util::{stream, Stream, StreamExt};
+use log::{debug, warn};
+use vo_client::VoClient;
+
+use crate::download;
+use crate::{Error, SIARecord};
+
+/// Maximum length of the generated file names
+const MAX_FILE_NAME_LEN: usize = 200;
+
+/// Download many datasets to a directory, with a bounded number of
+/// concurrent downloads.
+///
+/// Files which already exist in the directory are not downloaded again.
+/// Failed downloads are retried with an exponential backoff, unless the
+/// error is permanent (e.g. a 404 status).
+///
+/// ```no_run
+/// # extern crate vo_sia;
+/// # use vo_sia::{DownloadManager, SiaService};
+/// # fn main() {
+/// let results = SiaService::GAVO
+///     .create_query((161.0, -59.7))
+///     .execute_sync()
+///     .unwrap();
+/// let manager = DownloadManager::new("images").with_concurrency(8);
+/// for result in manager.download_all(results.records()).unwrap() {
+///     println!("{}: {:?}", result.path.display(), result.status);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DownloadManager {
+    client: VoClient,
+    dir: PathBuf,
+    concurrency: usize,
+    retries: usize,
+    backoff: Duration,
+}
+
+/// A dataset to download.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadItem {
+    url: Option<String>,
+    file_name: String,
+    estsize: Option<u64>,
+}
+
+/// Outcome of the download of a [`DownloadItem`](struct.DownloadItem.html).
+#[derive(Debug)]
+pub struct DownloadResult {
+    pub item: DownloadItem,
+    /// Path of the downloaded file
+    pub path: PathBuf,
+    pub status: Result<DownloadStatus, Error>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DownloadStatus {
+    /// The file was downloaded. Contains the number of bytes written.
+    Downloaded(u64),
+    /// The file already existed.
+    Skipped,
+}
+
+impl DownloadManager {
+    /// Download to the directory `dir`, which is created if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        DownloadManager {
+            client: VoClient::new(),
+            dir: dir.into(),
+            concurrency: 4,
+            retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// Download with `client`, e.g. to share its connections.
+    pub fn with_client(mut self, client: VoClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Maximum number of concurrent downloads. Defaults to 4.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Number of retries after a failed download. Defaults to 3.
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled for each following retry.
+    /// Defaults to 1 second.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Download all the items, and return the outcome of each download in
+    /// the same order.
+    ///
+    /// The downloads run on as many threads as concurrent downloads with
+    /// the `blocking` feature, or else on the Tokio runtime shared by the
+    /// blocking functions.
+    ///
+    /// Only fails if the runtime cannot be started or blocked. Errors of each
+    /// download are reported in its
+    /// [`DownloadResult`](struct.DownloadResult.html).
+    #[cfg(any(feature = "tokio", feature = "blocking"))]
+    pub fn download_all<I>(&self, items: I) -> Result<Vec<DownloadResult>, Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<DownloadItem>,
+    {
+        let items: Vec<DownloadItem> = items.into_iter().map(Into::into).collect();
+        #[cfg(feature = "blocking")]
+        {
+            Ok(self.download_all_blocking(items))
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            let runtime = vo_client::Runtime::shared()?;
+            Ok(runtime.block_on(self.download_stream(items).collect())?)
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn download_all_blocking(&self, items: Vec<DownloadItem>) -> Vec<DownloadResult> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(items.iter().map(|_| None).collect::<Vec<_>>());
+        std::thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(items.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let item = match items.get(index) {
+                        Some(item) => item.clone(),
+                        None => break,
+                    };
+                    let result = self.download_one_blocking(item);
+                    results.lock().expect("Poisoned lock")[index] = Some(result);
+                });
+            }
+        });
+        results
+            .into_inner()
+            .expect("Poisoned lock")
+            .into_iter()
+            .map(|result| result.expect("All the items are downloaded"))
+            .collect()
+    }
+
+    /// Asynchronous version of [`download_all`](#method.download_all),
+    /// yielding the outcome of each download in the order of the items.
+    #[cfg(feature = "tokio")]
+    pub fn download_stream<I>(&self, items: I) -> impl Stream<Item = DownloadResult>
+    where
+        I: IntoIterator,
+        I::Item: Into<DownloadItem>,
+    {
+        let manager = self.clone();
+        let items: Vec<DownloadItem> = items.into_iter().map(Into::into).collect();
+        stream::iter(items)
+            .map(move |item| manager.clone().download_one(item))
+            .buffered(self.concurrency)
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn download_one(self, item: DownloadItem) -> DownloadResult {
+        let path = self.dir.join(&item.file_name);
+        let status = match self.prepare(&item, &path) {
+            Ok(Some(url)) => {
+                let mut attempt = 0;
+                loop {
+                    match download::download(&self.client, &url, item.estsize, &path, |_, _| ())
+                        .await
+                    {
+                        Ok(size) => break Ok(DownloadStatus::Downloaded(size)),
+                        Err(e) => match self.retry_delay(attempt, &url, &e) {
+                            Some(delay) => tokio::time::sleep(delay).await,
+                            None => break Err(e),
+                        },
+                    }
+                    attempt += 1;
+                }
+            }
+            Ok(None) => Ok(DownloadStatus::Skipped),
+            Err(e) => Err(e),
+        };
+        DownloadResult { item, path, status }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn download_one_blocking(&self, item: DownloadItem) -> DownloadResult {
+        let path = self.dir.join(&item.file_name);
+        let status = match self.prepare(&item, &path) {
+            Ok(Some(url)) => {
+                let mut attempt = 0;
+                loop {
+                    match download::download_blocking(
+                        &self.client,
+                        &url,
+                        item.estsize,
+                        &path,
+                        |_, _| (),
+                    ) {
+                        Ok(size) => break Ok(DownloadStatus::Downloaded(size)),
+                        Err(e) => match self.retry_delay(attempt, &url, &e) {
+                            Some(delay) => std::thread::sleep(delay),
+                            None => break Err(e),
+                        },
+                    }
+                    attempt += 1;
+                }
+            }
+            Ok(None) => Ok(DownloadStatus::Skipped),
+            Err(e) => Err(e),
+        };
+        DownloadResult { item, path, status }
+    }
+
+    /// The URL to download `item` from, or `None` if `path` already exists.
+    fn prepare(&self, item: &DownloadItem, path: &Path) -> Result<Option<String>, Error> {
+        if path.exists() {
+            debug!("Skipping {}, which already exists", path.display());
+            return Ok(None);
+        }
+        let url = item.url.clone().ok_or(Error::MissingAccessUrl)?;
+        fs::create_dir_all(&self.dir).map_err(Error::Io)?;
+        Ok(Some(url))
+    }
+
+    /// Delay before retrying a download which failed with `e`, if it should
+    /// be retried.
+    fn retry_delay(&self, attempt: usize, url: &str, e: &Error) -> Option<Duration> {
+        if attempt < self.retries && is_transient(e) {
+            let delay = self.backoff * 2u32.pow(attempt as u32);
+            warn!("Retrying {} in {:?} after error: {}", url, delay, e);
+            Some(delay)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether retrying a download which failed with `e` may succeed.
+fn is_transient(e: &Error) -> bool {
+    use vo_client::Error as ClientError;
+    match e {
+        Error::Client(ClientError::HttpStatus { code, .. })
+        | Error::Client(ClientError::ServiceError { code, .. }) => {
+            code.is_server_error() || code.as_u16() == 408 || code.as_u16() == 429
+        }
+        Error::Client(ClientError::Io(_))
+        | Error::Client(ClientError::Timeout)
+        | Error::SizeMismatch { .. } => true,
+        #[cfg(feature = "tokio")]
+        Error::Client(ClientError::Hyper(_)) => true,
+        #[cfg(feature = "blocking")]
+        Error::Client(ClientError::Transport(_)) => true,
+        _ => false,
+    }
+}
+
+impl DownloadItem {
+    /// Download `url`, to a file named after the URL.
+    pub fn from_url<S: Into<String>>(url: S) -> Self {
+        let url = url.into();
+        DownloadItem {
+            file_name: file_name(strip_scheme(&url), &url),
+            url: Some(url),
+            estsize: None,
+        }
+    }
+
+    pub fn with_file_name<S: Into<String>>(mut self, file_name: S) -> Self {
+        self.file_name = file_name.into();
+        self
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+}
+
+impl<'a> From<&'a str> for DownloadItem {
+    fn from(url: &'a str) -> Self {
+        DownloadItem::from_url(url)
+    }
+}
+
+impl From<String> for DownloadItem {
+    fn from(url: String) -> Self {
+        DownloadItem::from_url(url)
+    }
+}
+
+/// The file is named after the publisher DID of the record if any, or else
+/// after its access URL.
+impl<'a> From<SIARecord<'a>> for DownloadItem {
+    fn from(record: SIARecord<'a>) -> Self {
+        let url = record.access_url().map(str::to_owned);
+        let key = record.obs_publisher_did().or(url.as_deref()).unwrap_or("");
+        let mut file_name = file_name(strip_scheme(key), key);
+        let is_fits = match record.access_format() {
+            Some(format) => format.contains("fits"),
+            None => false,
+        };
+        if is_fits && !file_name.contains(".fits") && !file_name.ends_with(".fz") {
+            file_name.push_str(".fits");
+        }
+        DownloadItem {
+            url,
+            file_name,
+            estsize: record.access_estsize().and_then(|size| {
+                if size >= 0 {
+                    Some(size as u64)
+                } else {
+                    None
+                }
+            }),
+        }
+    }
+}
+
+fn strip_scheme(url: &str) -> &str {
+    match url.find("://") {
+        Some(index) => &url[index + 3..],
+        None => url,
+    }
+}
+
+/// Make a file name from `name`, replacing the characters which may not be
+/// portable and truncating long names. A hash of `key` is always inserted
+/// before the extension, since distinct keys may otherwise give the same
+/// name.
+fn file_name(name: &str, key: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let hash = format!("{:016x}", fnv1a(key.as_bytes()));
+    let (stem, extension) = split_extension(&name);
+    let mut stem = stem.trim_matches('.').to_owned();
+    stem.truncate(MAX_FILE_NAME_LEN - hash.len() - 1 - extension.len());
+    if stem.is_empty() {
+        format!("{}{}", hash, extension)
+    } else {
+        format!("{}-{}{}", stem, hash, extension)
+    }
+}
+
+/// Split `name` before its extension, which may be double as in `.fits.gz`.
+fn split_extension(name: &str) -> (&str, &str) {
+    let mut stem = name;
+    for _ in 0..2 {
+        match stem.rfind('.') {
+            Some(index)
+                if index > 0
+                    && (2..=5).contains(&(stem.len() - index))
+                    && stem[index + 1..].chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                stem = &stem[..index]
+            }
+            _ => break,
+        }
+    }
+    (stem, &name[stem.len()..])
+}
+
+/// 64-bit FNV-1a hash, which unlike the hashers of the standard library is
+/// stable across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
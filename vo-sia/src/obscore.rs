@@ -0,0 +1,181 @@
+//! Columns of the ObsCore data model, as returned by SIA 2.0 services.
+//!
+//! Each column may be identified by its name, its utype or its UCD. UCDs
+//! shared by several columns (e.g. `meta.id`) are not used. The equivalent
+//! SIA 1.0 column, if any, is identified by its UCD.
+
+pub(crate) struct Column {
+    pub name: &'static str,
+    pub utype: &'static str,
+    pub ucd: Option<&'static str>,
+    pub v1_ucd: Option<&'static str>,
+}
+
+pub(crate) const DATAPRODUCT_TYPE: Column = Column {
+    name: "dataproduct_type",
+    utype: "obscore:ObsDataset.dataProductType",
+    ucd: Some("meta.code.class"),
+    v1_ucd: None,
+};
+
+pub(crate) const CALIB_LEVEL: Column = Column {
+    name: "calib_level",
+    utype: "obscore:ObsDataset.calibLevel",
+    ucd: Some("meta.code;obs.calib"),
+    v1_ucd: None,
+};
+
+pub(crate) const OBS_COLLECTION: Column = Column {
+    name: "obs_collection",
+    utype: "obscore:DataID.Collection",
+    ucd: None,
+    v1_ucd: None,
+};
+
+pub(crate) const OBS_ID: Column = Column {
+    name: "obs_id",
+    utype: "obscore:DataID.observationID",
+    ucd: None,
+    v1_ucd: None,
+};
+
+pub(crate) const OBS_TITLE: Column = Column {
+    name: "obs_title",
+    utype: "obscore:DataID.Title",
+    ucd: Some("meta.title;obs"),
+    v1_ucd: Some("VOX:Image_Title"),
+};
+
+pub(crate) const OBS_PUBLISHER_DID: Column = Column {
+    name: "obs_publisher_did",
+    utype: "obscore:Curation.PublisherDID",
+    ucd: Some("meta.ref.ivoid"),
+    v1_ucd: None,
+};
+
+pub(crate) const ACCESS_URL: Column = Column {
+    name: "access_url",
+    utype: "obscore:Access.Reference",
+    ucd: Some("meta.ref.url"),
+    v1_ucd: Some("VOX:Image_AccessReference"),
+};
+
+pub(crate) const ACCESS_FORMAT: Column = Column {
+    name: "access_format",
+    utype: "obscore:Access.Format",
+    ucd: Some("meta.code.mime"),
+    v1_ucd: Some("VOX:Image_Format"),
+};
+
+/// In kilobytes. The SIA 1.0 column is in bytes.
+pub(crate) const ACCESS_ESTSIZE: Column = Column {
+    name: "access_estsize",
+    utype: "obscore:Access.Size",
+    ucd: Some("phys.size;meta.file"),
+    v1_ucd: None,
+};
+
+pub(crate) const TARGET_NAME: Column = Column {
+    name: "target_name",
+    utype: "obscore:Target.Name",
+    ucd: Some("meta.id;src"),
+    v1_ucd: None,
+};
+
+pub(crate) const S_RA: Column = Column {
+    name: "s_ra",
+    utype: "obscore:Char.SpatialAxis.Coverage.Location.Coord.Position2D.Value2.C1",
+    ucd: Some("pos.eq.ra"),
+    v1_ucd: Some("POS_EQ_RA_MAIN"),
+};
+
+pub(crate) const S_DEC: Column = Column {
+    name: "s_dec",
+    utype: "obscore:Char.SpatialAxis.Coverage.Location.Coord.Position2D.Value2.C2",
+    ucd: Some("pos.eq.dec"),
+    v1_ucd: Some("POS_EQ_DEC_MAIN"),
+};
+
+pub(crate) const S_FOV: Column = Column {
+    name: "s_fov",
+    utype: "obscore:Char.SpatialAxis.Coverage.Bounds.Extent.diameter",
+    ucd: Some("phys.angSize;instr.fov"),
+    v1_ucd: None,
+};
+
+pub(crate) const S_REGION: Column = Column {
+    name: "s_region",
+    utype: "obscore:Char.SpatialAxis.Coverage.Support.Area",
+    ucd: Some("pos.outline;obs.field"),
+    v1_ucd: None,
+};
+
+pub(crate) const S_RESOLUTION: Column = Column {
+    name: "s_resolution",
+    utype: "obscore:Char.SpatialAxis.Resolution.Refval.value",
+    ucd: Some("pos.angResolution"),
+    v1_ucd: None,
+};
+
+pub(crate) const T_MIN: Column = Column {
+    name: "t_min",
+    utype: "obscore:Char.TimeAxis.Coverage.Bounds.Limits.StartTime",
+    ucd: Some("time.start;obs.exposure"),
+    v1_ucd: None,
+};
+
+pub(crate) const T_MAX: Column = Column {
+    name: "t_max",
+    utype: "obscore:Char.TimeAxis.Coverage.Bounds.Limits.StopTime",
+    ucd: Some("time.end;obs.exposure"),
+    v1_ucd: None,
+};
+
+pub(crate) const T_EXPTIME: Column = Column {
+    name: "t_exptime",
+    utype: "obscore:Char.TimeAxis.Coverage.Support.Extent",
+    ucd: Some("time.duration;obs.exposure"),
+    v1_ucd: None,
+};
+
+pub(crate) const EM_MIN: Column = Column {
+    name: "em_min",
+    utype: "obscore:Char.SpectralAxis.Coverage.Bounds.Limits.LoLimit",
+    ucd: Some("em.wl;stat.min"),
+    v1_ucd: Some("VOX:BandPass_LoLimit"),
+};
+
+pub(crate) const EM_MAX: Column = Column {
+    name: "em_max",
+    utype: "obscore:Char.SpectralAxis.Coverage.Bounds.Limits.HiLimit",
+    ucd: Some("em.wl;stat.max"),
+    v1_ucd: Some("VOX:BandPass_HiLimit"),
+};
+
+pub(crate) const EM_RES_POWER: Column = Column {
+    name: "em_res_power",
+    utype: "obscore:Char.SpectralAxis.Resolution.ResolPower.refVal",
+    ucd: Some("spect.resolution"),
+    v1_ucd: None,
+};
+
+pub(crate) const POL_STATES: Column = Column {
+    name: "pol_states",
+    utype: "obscore:Char.PolarizationAxis.stateList",
+    ucd: Some("meta.code;phys.polarization"),
+    v1_ucd: None,
+};
+
+pub(crate) const FACILITY_NAME: Column = Column {
+    name: "facility_name",
+    utype: "obscore:Provenance.ObsConfig.Facility.name",
+    ucd: Some("meta.id;instr.tel"),
+    v1_ucd: None,
+};
+
+pub(crate) const INSTRUMENT_NAME: Column = Column {
+    name: "instrument_name",
+    utype: "obscore:Provenance.ObsConfig.Instrument.name",
+    ucd: Some("meta.id;instr"),
+    v1_ucd: Some("INST_ID"),
+};
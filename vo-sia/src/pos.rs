@@ -0,0 +1,523 @@
+//! Regions of the sky searched by a query, in ICRS coordinates.
+
+use std::fmt;
+use std::str::FromStr;
+
+use vo_coords::{Coord, Frame};
+
+use crate::{Error, Interval};
+
+/// Region of the sky, with longitudes and latitudes in degrees.
+///
+/// Serialized as specified by DALI, and parsed back from this form:
+///
+/// ```
+/// # extern crate vo_sia;
+/// # use vo_sia::{Interval, Pos};
+/// # fn main() {
+/// let circle = Pos::circle(12.0, 34.0, 0.5).unwrap();
+/// assert_eq!(circle.to_string(), "CIRCLE 12 34 0.5");
+///
+/// let range = Pos::range((0.0, 360.0), Interval::at_least(80.0)).unwrap();
+/// assert_eq!(range.to_string(), "RANGE 0 360 80 +Inf");
+///
+/// let polygon = Pos::polygon(vec![(12.0, 34.0), (14.0, 35.0), (14.0, 36.0)]).unwrap();
+/// assert_eq!(polygon.to_string(), "POLYGON 12 34 14 35 14 36");
+///
+/// for pos in &[circle, range, polygon] {
+///     assert_eq!(&pos.to_string().parse::<Pos>().unwrap(), pos);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pos {
+    Circle {
+        longitude: f64,
+        latitude: f64,
+        radius: f64,
+    },
+    Range {
+        longitude1: f64,
+        longitude2: f64,
+        latitude1: f64,
+        latitude2: f64,
+    },
+    Polygon(PolygonPos),
+}
+
+/// Polygon on the sky, with vertices joined by great circles.
+///
+/// Built with [`new`](#method.new), which validates the vertices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonPos(Vec<(f64, f64)>);
+
+impl Pos {
+    /// Circle of `radius` around (`longitude`, `latitude`).
+    pub fn circle(longitude: f64, latitude: f64, radius: f64) -> Result<Self, Error> {
+        let pos = Pos::Circle {
+            longitude,
+            latitude,
+            radius,
+        };
+        pos.validate()?;
+        Ok(pos)
+    }
+
+    /// Circle of `radius` around `coord`, e.g. a position typed by a user or
+    /// the resolved position of a target.
+    ///
+    /// ```
+    /// # extern crate vo_sia;
+    /// # use vo_sia::{Coord, Pos};
+    /// # fn main() {
+    /// let eta_car: Coord = "10h44m06.6s -59d41m04s".parse().unwrap();
+    /// let pos = Pos::cone(eta_car, 0.1).unwrap();
+    /// assert_eq!(pos, Pos::circle(161.0275, eta_car.dec(), 0.1).unwrap());
+    /// # }
+    /// ```
+    pub fn cone(coord: Coord, radius: f64) -> Result<Self, Error> {
+        Pos::circle(coord.ra(), coord.dec(), radius)
+    }
+
+    /// Region between the bounds of `longitude` and `latitude`. Bounds may
+    /// be infinite, for open-ended ranges.
+    ///
+    /// Longitudes go across 0 if the lower bound is greater than the upper
+    /// one, e.g. from 350 to 10.
+    pub fn range<I: Into<Interval>, J: Into<Interval>>(
+        longitude: I,
+        latitude: J,
+    ) -> Result<Self, Error> {
+        let (longitude, latitude) = (longitude.into(), latitude.into());
+        let pos = Pos::Range {
+            longitude1: longitude.min,
+            longitude2: longitude.max,
+            latitude1: latitude.min,
+            latitude2: latitude.max,
+        };
+        pos.validate()?;
+        Ok(pos)
+    }
+
+    /// Polygon with the given `(longitude, latitude)` vertices. See
+    /// [`PolygonPos::new`](struct.PolygonPos.html#method.new).
+    pub fn polygon<I>(vertices: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (f64, f64)>,
+    {
+        PolygonPos::new(vertices).map(Pos::Polygon)
+    }
+
+    /// Same region with coordinates converted from `frame` to ICRS, in
+    /// which services expect regions.
+    ///
+    /// Circles and polygons are converted exactly. Ranges are only
+    /// accepted in ICRS, since they are not ranges in other frames.
+    ///
+    /// ```
+    /// # extern crate vo_sia;
+    /// # use vo_sia::{Frame, Pos};
+    /// # fn main() {
+    /// let galactic_center = Pos::circle(0.0, 0.0, 0.5).unwrap();
+    /// match galactic_center.to_icrs(Frame::Galactic).unwrap() {
+    ///     Pos::Circle { longitude, latitude, radius } => {
+    ///         assert!((longitude - 266.405).abs() < 1e-3);
+    ///         assert!((latitude + 28.936).abs() < 1e-3);
+    ///         assert_eq!(radius, 0.5);
+    ///     }
+    ///     other => panic!("Unexpected region {}", other),
+    /// }
+    ///
+    /// let range = Pos::range((0.0, 10.0), (0.0, 10.0)).unwrap();
+    /// assert!(range.to_icrs(Frame::Galactic).is_err());
+    /// # }
+    /// ```
+    pub fn to_icrs(&self, frame: Frame) -> Result<Pos, Error> {
+        if frame == Frame::ICRS {
+            return Ok(self.clone());
+        }
+        match *self {
+            Pos::Circle {
+                longitude,
+                latitude,
+                radius,
+            } => {
+                let (ra, dec) = frame.to_icrs(longitude, latitude);
+                Pos::circle(ra, dec, radius)
+            }
+            Pos::Range { .. } => Err(invalid(format!(
+                "Ranges must be in ICRS, got {} in {:?}",
+                self, frame
+            ))),
+            Pos::Polygon(ref polygon) => Pos::polygon(
+                polygon
+                    .vertices()
+                    .iter()
+                    .map(|&(longitude, latitude)| frame.to_icrs(longitude, latitude)),
+            ),
+        }
+    }
+
+    /// Check the coordinates of the region.
+    ///
+    /// ```
+    /// # extern crate vo_sia;
+    /// # use vo_sia::Pos;
+    /// # fn main() {
+    /// let circle = Pos::Circle {
+    ///     longitude: 12.0,
+    ///     latitude: 95.0,
+    ///     radius: 1.0,
+    /// };
+    /// assert!(circle.validate().is_err());
+    ///
+    /// let range = Pos::Range {
+    ///     longitude1: 10.0,
+    ///     longitude2: 20.0,
+    ///     latitude1: 1.0,
+    ///     latitude2: 0.0,
+    /// };
+    /// assert!(range.validate().is_err());
+    ///
+    /// // Across longitude 0
+    /// let range = Pos::Range {
+    ///     longitude1: 350.0,
+    ///     longitude2: 10.0,
+    ///     latitude1: 0.0,
+    ///     latitude2: 1.0,
+    /// };
+    /// assert!(range.validate().is_ok());
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        match *self {
+            Pos::Circle {
+                longitude,
+                latitude,
+                radius,
+            } => {
+                check_longitude(longitude)?;
+                check_latitude(latitude)?;
+                if !(radius > 0.0 && radius <= 180.0) {
+                    return Err(invalid(format!(
+                        "Radius must be in ]0, 180], got {}",
+                        radius
+                    )));
+                }
+                Ok(())
+            }
+            Pos::Range {
+                longitude1,
+                longitude2,
+                latitude1,
+                latitude2,
+            } => {
+                if longitude1 != f64::NEG_INFINITY {
+                    check_longitude(longitude1)?;
+                }
+                if longitude2 != f64::INFINITY {
+                    check_longitude(longitude2)?;
+                }
+                if latitude1 != f64::NEG_INFINITY {
+                    check_latitude(latitude1)?;
+                }
+                if latitude2 != f64::INFINITY {
+                    check_latitude(latitude2)?;
+                }
+                if latitude1 > latitude2 {
+                    return Err(invalid(format!(
+                        "Latitude bounds must be in increasing order, got {}",
+                        self
+                    )));
+                }
+                Ok(())
+            }
+            Pos::Polygon(ref polygon) => {
+                for &(longitude, latitude) in polygon.vertices() {
+                    check_longitude(longitude)?;
+                    check_latitude(latitude)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Center and size of the region, as expected by SIA 1.0.
+    pub(crate) fn center_and_size(&self) -> ((f64, f64), (f64, f64)) {
+        match *self {
+            Pos::Circle {
+                longitude,
+                latitude,
+                radius,
+            } => ((longitude, latitude), (2.0 * radius, 2.0 * radius)),
+            Pos::Range {
+                longitude1,
+                longitude2,
+                latitude1,
+                latitude2,
+            } => {
+                let (longitude1, mut longitude2) = (longitude1.max(0.0), longitude2.min(360.0));
+                if longitude1 > longitude2 {
+                    // Across longitude 0
+                    longitude2 += 360.0;
+                }
+                let (latitude1, latitude2) = (latitude1.max(-90.0), latitude2.min(90.0));
+                (
+                    (
+                        ((longitude1 + longitude2) / 2.0).rem_euclid(360.0),
+                        (latitude1 + latitude2) / 2.0,
+                    ),
+                    (
+                        (longitude2 - longitude1).abs(),
+                        (latitude2 - latitude1).abs(),
+                    ),
+                )
+            }
+            Pos::Polygon(ref polygon) => {
+                let mut min = (f64::INFINITY, f64::INFINITY);
+                let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+                for (long, lat) in unwrap(&polygon.0) {
+                    min = (min.0.min(long), min.1.min(lat));
+                    max = (max.0.max(long), max.1.max(lat));
+                }
+                (
+                    (
+                        ((min.0 + max.0) / 2.0).rem_euclid(360.0),
+                        (min.1 + max.1) / 2.0,
+                    ),
+                    (max.0 - min.0, max.1 - min.1),
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pos::Circle {
+                longitude,
+                latitude,
+                radius,
+            } => write!(f, "CIRCLE {} {} {}", longitude, latitude, radius),
+            Pos::Range {
+                longitude1,
+                longitude2,
+                latitude1,
+                latitude2,
+            } => write!(
+                f,
+                "RANGE {} {}",
+                Interval::new(*longitude1, *longitude2).serialize(),
+                Interval::new(*latitude1, *latitude2).serialize()
+            ),
+            Pos::Polygon(polygon) => {
+                f.write_str("POLYGON")?;
+                for (long, lat) in &polygon.0 {
+                    write!(f, " {} {}", long, lat)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parse the DALI form of a region, e.g. the POS parameter of a saved
+/// query URL.
+///
+/// ```
+/// # extern crate vo_sia;
+/// # use vo_sia::Pos;
+/// # fn main() {
+/// let url = "https://example.com/sia?POS=CIRCLE+12+34+0.5&POS=RANGE+0+10+-Inf+-80";
+/// let regions: Vec<Pos> = url::Url::parse(url)
+///     .unwrap()
+///     .query_pairs()
+///     .filter(|(key, _)| key == "POS")
+///     .map(|(_, value)| value.parse().unwrap())
+///     .collect();
+/// assert_eq!(regions[0], Pos::circle(12.0, 34.0, 0.5).unwrap());
+/// # }
+/// ```
+impl FromStr for Pos {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut words = s.split_whitespace();
+        let shape = words.next().unwrap_or("").to_ascii_uppercase();
+        let values = words
+            .map(|word| {
+                word.parse::<f64>()
+                    .map_err(|_| invalid(format!("Invalid number {:?} in {:?}", word, s)))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let expected = match shape.as_str() {
+            "CIRCLE" => 3,
+            "RANGE" => 4,
+            "POLYGON" if values.len() % 2 == 0 => values.len(),
+            "POLYGON" => values.len() + 1,
+            _ => return Err(invalid(format!("Unknown shape in {:?}", s))),
+        };
+        if values.len() != expected {
+            return Err(invalid(format!(
+                "Expected {} values in {:?}, got {}",
+                expected,
+                s,
+                values.len()
+            )));
+        }
+        match shape.as_str() {
+            "CIRCLE" => Pos::circle(values[0], values[1], values[2]),
+            "RANGE" => Pos::range((values[0], values[1]), (values[2], values[3])),
+            _ => Pos::polygon(values.chunks(2).map(|vertex| (vertex[0], vertex[1]))),
+        }
+    }
+}
+
+/// Circle of radius 1 degree around `(longitude, latitude)`. Use
+/// [`Pos::circle`](enum.Pos.html#method.circle) to choose the radius.
+impl From<(f64, f64)> for Pos {
+    fn from(pos: (f64, f64)) -> Pos {
+        Pos::Circle {
+            longitude: pos.0,
+            latitude: pos.1,
+            radius: 1.0,
+        }
+    }
+}
+
+impl From<PolygonPos> for Pos {
+    fn from(polygon: PolygonPos) -> Pos {
+        Pos::Polygon(polygon)
+    }
+}
+
+impl PolygonPos {
+    /// Polygon with the given `(longitude, latitude)` vertices.
+    ///
+    /// Longitudes are wrapped to [0, 360[, and consecutive vertices are
+    /// joined across longitude 0 if they are closer that way. Vertices are
+    /// put in counter-clockwise order, with longitudes increasing to the
+    /// right, as expected by DALI.
+    ///
+    /// Fails if there are less than 3 vertices, if a latitude is not in
+    /// [-90, 90], if the polygon is degenerate or if it goes around a pole.
+    ///
+    /// ```
+    /// # extern crate vo_sia;
+    /// # use vo_sia::{PolygonPos, Pos};
+    /// # fn main() {
+    /// // Clockwise, around longitude 0
+    /// let vertices = vec![(-1.0, 0.0), (-1.0, 1.0), (1.0, 1.0), (1.0, 0.0)];
+    /// let polygon = PolygonPos::new(vertices).unwrap();
+    /// assert_eq!(
+    ///     polygon.vertices(),
+    ///     &[(1.0, 0.0), (1.0, 1.0), (359.0, 1.0), (359.0, 0.0)]
+    /// );
+    ///
+    /// assert!(PolygonPos::new(vec![(0.0, 0.0), (1.0, 1.0)]).is_err());
+    /// assert!(PolygonPos::new(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]).is_err());
+    /// assert!(PolygonPos::new(vec![(0.0, 0.0), (1.0, 91.0), (2.0, 0.0)]).is_err());
+    /// # }
+    /// ```
+    pub fn new<I>(vertices: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (f64, f64)>,
+    {
+        let mut vertices = vertices
+            .into_iter()
+            .map(|(long, lat)| {
+                check_longitude_finite(long)?;
+                check_latitude(lat)?;
+                Ok((long.rem_euclid(360.0), lat))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if vertices.len() < 3 {
+            return Err(invalid(format!(
+                "Polygon needs at least 3 vertices, got {}",
+                vertices.len()
+            )));
+        }
+
+        let unwrapped = unwrap(&vertices);
+        let (first, last) = (unwrapped[0], unwrapped[unwrapped.len() - 1]);
+        if (last.0 + wrap(first.0 - last.0) - first.0).abs() > 180.0 {
+            return Err(invalid("Polygon must not go around a pole".to_owned()));
+        }
+        let area = unwrapped
+            .iter()
+            .zip(unwrapped.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<f64>();
+        if area == 0.0 || !area.is_finite() {
+            return Err(invalid("Polygon must not be degenerate".to_owned()));
+        }
+        if area < 0.0 {
+            vertices.reverse();
+        }
+        Ok(PolygonPos(vertices))
+    }
+
+    /// Vertices in counter-clockwise order, with longitudes in [0, 360[.
+    pub fn vertices(&self) -> &[(f64, f64)] {
+        &self.0
+    }
+}
+
+/// Longitudes of `vertices` offset by multiples of 360, so that consecutive
+/// vertices are less than 180 degrees apart.
+fn unwrap(vertices: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut unwrapped: Vec<(f64, f64)> = Vec::with_capacity(vertices.len());
+    for &(long, lat) in vertices {
+        let long = match unwrapped.last() {
+            Some(&(previous, _)) => previous + wrap(long - previous),
+            None => long,
+        };
+        unwrapped.push((long, lat));
+    }
+    unwrapped
+}
+
+/// Difference of longitudes wrapped to ]-180, 180].
+fn wrap(delta: f64) -> f64 {
+    let delta = delta.rem_euclid(360.0);
+    if delta > 180.0 {
+        delta - 360.0
+    } else {
+        delta
+    }
+}
+
+fn check_longitude_finite(longitude: f64) -> Result<(), Error> {
+    if longitude.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid longitude {}", longitude)))
+    }
+}
+
+fn check_longitude(longitude: f64) -> Result<(), Error> {
+    if (0.0..=360.0).contains(&longitude) {
+        Ok(())
+    } else {
+        Err(invalid(format!(
+            "Longitude must be in [0, 360], got {}",
+            longitude
+        )))
+    }
+}
+
+fn check_latitude(latitude: f64) -> Result<(), Error> {
+    if (-90.0..=90.0).contains(&latitude) {
+        Ok(())
+    } else {
+        Err(invalid(format!(
+            "Latitude must be in [-90, 90], got {}",
+            latitude
+        )))
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidPos(message)
+}
@@ -0,0 +1,101 @@
+//! Downloads of the datasets of query results.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use vo_mock::{MockService, Reply};
+use vo_sia::{SIAResults, SiaVersion};
+use vo_table::{ArraySize, Cell, DataType, Field, Table, VOTable};
+
+/// Results with one record per `(access_url, access_estsize)`.
+fn results(records: &[(&str, i64)]) -> SIAResults {
+    let mut table = Table::new(vec![
+        Field::new("access_url", DataType::Character).with_arraysize(ArraySize::Unbounded),
+        Field::new("access_estsize", DataType::Integer64),
+    ]);
+    for (url, estsize) in records {
+        table
+            .push_row(&[Cell::Character(url), Cell::Integer64(&[Some(*estsize)])])
+            .unwrap();
+    }
+    let mut xml = Vec::new();
+    VOTable::from(table).write(&mut xml).unwrap();
+    SIAResults::new(VOTable::parse(&xml[..]).unwrap(), SiaVersion::V2)
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("vo-sia-{}-{}", name, process::id()))
+}
+
+#[test]
+fn downloads_with_progress() {
+    let server = MockService::start(|_| Reply::ok(vec![7; 3000]));
+    let results = results(&[(&server.endpoint("/image.fits"), 3)]);
+    let record = results.records().next().unwrap();
+    let dest = temp_path("progress.fits");
+
+    let mut progress = vec![];
+    let size = record
+        .download_with_progress(&dest, |received, total| progress.push((received, total)))
+        .unwrap();
+    assert_eq!(size, 3000);
+    assert_eq!(fs::read(&dest).unwrap(), vec![7; 3000]);
+    assert_eq!(progress.last(), Some(&(3000, Some(3000))));
+    assert!(progress.windows(2).all(|w| w[0].0 < w[1].0));
+    fs::remove_file(dest).unwrap();
+}
+
+#[test]
+fn keeps_datasets_larger_than_estimated() {
+    // The estimated size is off by far more than a factor of 2, but only
+    // the Content-Length is enforced
+    let server = MockService::start(|_| Reply::ok(vec![1; 100 * 1024]));
+    let results = results(&[(&server.endpoint("/big.fits"), 1)]);
+    let dest = temp_path("estsize.fits");
+
+    let size = results.records().next().unwrap().download(&dest).unwrap();
+    assert_eq!(size, 100 * 1024);
+    assert_eq!(fs::metadata(&dest).unwrap().len(), size);
+    fs::remove_file(dest).unwrap();
+}
+
+#[test]
+fn removes_failed_downloads() {
+    let server = MockService::start(|request| match request.path.as_str() {
+        "/truncated" => Reply::ok("data").with_header("Content-Length", "10"),
+        _ => Reply::status(404),
+    });
+    let results = results(&[
+        (&server.endpoint("/truncated"), 1),
+        (&server.endpoint("/missing"), 1),
+    ]);
+    for (i, record) in results.records().enumerate() {
+        let dest = temp_path(&format!("failed-{}", i));
+        assert!(record.download(&dest).is_err());
+        assert!(!dest.exists());
+        let mut part = dest.into_os_string();
+        part.push(".part");
+        assert!(!PathBuf::from(part).exists());
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn downloads_asynchronously() {
+    use futures_util::TryStreamExt;
+
+    let server = MockService::start(|_| Reply::ok("some bytes"));
+    let results = results(&[(&server.endpoint("/image.fits"), 1)]);
+    let record = results.records().next().unwrap();
+
+    let chunks: Vec<_> = record.fetch().try_collect().await.unwrap();
+    assert_eq!(chunks.concat(), b"some bytes");
+
+    let dest = temp_path("async.fits");
+    let size = record.download_async(&dest, |_, _| ()).await.unwrap();
+    assert_eq!(size, 10);
+    assert_eq!(fs::read(&dest).unwrap(), b"some bytes");
+    fs::remove_file(dest).unwrap();
+}
@@ -0,0 +1,203 @@
+//! Downloads of many datasets with the download manager.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use vo_mock::{MockService, Reply};
+use vo_sia::{ClientError, DownloadItem, DownloadManager, DownloadStatus, Error};
+
+/// An empty directory for the downloads of the test `name`.
+fn download_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("vo-sia-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn names_files_uniquely() {
+    let names: Vec<_> = [
+        "http://example.com/data?id=1",
+        "http://example.com/data?id:1",
+        "http://example.com/data/id=1",
+        "https://example.com/data?id=1",
+    ]
+    .iter()
+    .map(|url| DownloadItem::from_url(*url).file_name().to_owned())
+    .collect();
+    for (i, name) in names.iter().enumerate() {
+        assert!(!names[..i].contains(name), "{} is duplicated", name);
+    }
+
+    let name = DownloadItem::from_url("http://example.com/images/m31.fits.gz")
+        .file_name()
+        .to_owned();
+    assert!(name.starts_with("example.com_images_m31-"), "{}", name);
+    assert!(name.ends_with(".fits.gz"), "{}", name);
+    let long = format!("http://example.com/{}.fits", "a".repeat(500));
+    let name = DownloadItem::from_url(long).file_name().to_owned();
+    assert!(name.len() <= 200 && name.ends_with(".fits"), "{}", name);
+}
+
+#[test]
+fn limits_concurrent_downloads() {
+    let server = MockService::start(|_| Reply::ok("data").with_delay(Duration::from_millis(100)));
+    let dir = download_dir("concurrency");
+    let urls: Vec<_> = (0..6)
+        .map(|i| server.endpoint(&format!("/{}", i)))
+        .collect();
+    let results = DownloadManager::new(&dir)
+        .with_concurrency(2)
+        .download_all(urls)
+        .unwrap();
+
+    assert_eq!(results.len(), 6);
+    for result in &results {
+        assert_eq!(
+            result.status.as_ref().unwrap(),
+            &DownloadStatus::Downloaded(4)
+        );
+    }
+    assert_eq!(server.requests().len(), 6);
+    assert_eq!(server.max_in_flight(), 2);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn retries_with_backoff() {
+    let failures = AtomicUsize::new(0);
+    let server = MockService::start(move |request| match request.path.as_str() {
+        "/flaky" if failures.fetch_add(1, Ordering::SeqCst) < 2 => Reply::status(503),
+        "/flaky" => Reply::ok("data"),
+        _ => Reply::status(404),
+    });
+    let dir = download_dir("retries");
+    let backoff = Duration::from_millis(50);
+    let manager = DownloadManager::new(&dir)
+        .with_retries(3)
+        .with_backoff(backoff);
+    let results = manager
+        .download_all(vec![server.endpoint("/flaky"), server.endpoint("/missing")])
+        .unwrap();
+
+    assert_eq!(
+        results[0].status.as_ref().unwrap(),
+        &DownloadStatus::Downloaded(4)
+    );
+    match &results[1].status {
+        Err(Error::Client(ClientError::HttpStatus { code, .. })) => assert_eq!(code.as_u16(), 404),
+        other => panic!("Unexpected status {:?}", other),
+    }
+    let requests = server.requests();
+    let flaky: Vec<_> = requests
+        .iter()
+        .filter(|request| request.path == "/flaky")
+        .map(|request| request.received)
+        .collect();
+    assert_eq!(flaky.len(), 3);
+    assert!(flaky[1] - flaky[0] >= backoff);
+    assert!(flaky[2] - flaky[1] >= backoff * 2);
+    // Client errors are not retried
+    let missing = requests.iter().filter(|request| request.path == "/missing");
+    assert_eq!(missing.count(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn gives_up_after_retries() {
+    let server = MockService::start(|_| Reply::status(500));
+    let dir = download_dir("give-up");
+    let results = DownloadManager::new(&dir)
+        .with_retries(2)
+        .with_backoff(Duration::from_millis(1))
+        .download_all(vec![server.endpoint("/broken")])
+        .unwrap();
+
+    assert!(results[0].status.is_err());
+    assert!(!results[0].path.exists());
+    assert_eq!(server.requests().len(), 3);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reports_each_item() {
+    let server = MockService::start(|request| match request.path.as_str() {
+        "/missing" => Reply::status(404),
+        path => Reply::ok(path),
+    });
+    let dir = download_dir("results");
+    let existing = DownloadItem::from_url(server.endpoint("/existing")).with_file_name("existing");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("existing"), "old").unwrap();
+    let items = vec![
+        DownloadItem::from_url(server.endpoint("/first")).with_file_name("first"),
+        DownloadItem::from_url(server.endpoint("/missing")),
+        existing.clone(),
+        DownloadItem::from_url(server.endpoint("/last")).with_file_name("last"),
+    ];
+    let results = DownloadManager::new(&dir)
+        .with_retries(0)
+        .download_all(items.clone())
+        .unwrap();
+
+    let items_back: Vec<_> = results.iter().map(|result| result.item.clone()).collect();
+    assert_eq!(items_back, items);
+    assert_eq!(
+        results[0].status.as_ref().unwrap(),
+        &DownloadStatus::Downloaded(6)
+    );
+    assert_eq!(fs::read_to_string(dir.join("first")).unwrap(), "/first");
+    assert!(results[1].status.is_err());
+    assert!(!results[1].path.exists());
+    assert_eq!(
+        results[2].status.as_ref().unwrap(),
+        &DownloadStatus::Skipped
+    );
+    assert_eq!(fs::read_to_string(&results[2].path).unwrap(), "old");
+    assert_eq!(
+        results[3].status.as_ref().unwrap(),
+        &DownloadStatus::Downloaded(5)
+    );
+    assert_eq!(results[3].path, dir.join("last"));
+
+    let paths: Vec<_> = server
+        .requests()
+        .into_iter()
+        .map(|request| request.path)
+        .collect();
+    assert!(!paths.contains(&"/existing".to_owned()));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn streams_results_in_order() {
+    use futures_util::StreamExt;
+
+    let server = MockService::start(|request| {
+        // The first items take longer, but are still yielded first
+        let delay = if request.path == "/0" { 150 } else { 10 };
+        Reply::ok(request.path.as_str()).with_delay(Duration::from_millis(delay))
+    });
+    let dir = download_dir("stream");
+    let urls: Vec<_> = (0..4)
+        .map(|i| server.endpoint(&format!("/{}", i)))
+        .collect();
+    let results: Vec<_> = DownloadManager::new(&dir)
+        .with_concurrency(4)
+        .download_stream(urls.clone())
+        .collect()
+        .await;
+
+    let result_urls: Vec<_> = results
+        .iter()
+        .map(|result| result.item.url().unwrap().to_owned())
+        .collect();
+    assert_eq!(result_urls, urls);
+    assert!(results.iter().all(|result| result.status.is_ok()));
+    assert!(server.max_in_flight() > 1);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,243 @@
+//! Serialization of SIA queries.
+
+use vo_mock::{MockService, Reply};
+use vo_sia::{
+    CalibLevel, CoordFrame, DataProductType, Error, Format, Intersect, Interval, Polarization, Pos,
+    SiaQuery, SiaService, SiaVersion, Verbosity,
+};
+
+fn v1_param(f: impl FnOnce(SiaQuery) -> SiaQuery, key: &str) -> Vec<String> {
+    let query = f(SiaService::new("http://example.com/sia")
+        .with_version(SiaVersion::V1)
+        .create_query((12.0, 34.0)));
+    url::Url::parse(&query.query_url())
+        .unwrap()
+        .query_pairs()
+        .filter(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+        .collect()
+}
+
+#[test]
+fn serializes_v1_values() {
+    let intersects = [
+        (Intersect::Covers, "COVERS"),
+        (Intersect::Enclosed, "ENCLOSED"),
+        (Intersect::Overlaps, "OVERLAPS"),
+        (Intersect::Center, "CENTER"),
+    ];
+    for &(intersect, value) in &intersects {
+        assert_eq!(
+            v1_param(|q| q.with_intersect(intersect), "INTERSECT"),
+            vec![value]
+        );
+    }
+
+    let formats = [
+        (Format::All, "ALL"),
+        (Format::Graphic, "GRAPHIC"),
+        (Format::Metadata, "METADATA"),
+        (Format::Fits, "image/fits"),
+        (Format::Jpeg, "image/jpeg"),
+        (Format::Png, "image/png"),
+    ];
+    for &(format, value) in &formats {
+        assert_eq!(v1_param(|q| q.with_format(format), "FORMAT"), vec![value]);
+    }
+    assert_eq!(
+        v1_param(
+            |q| q.with_format(Format::Fits).with_format(Format::Graphic),
+            "FORMAT"
+        ),
+        vec!["image/fits,GRAPHIC"]
+    );
+
+    let verbosities = [
+        (Verbosity::Zero, "0"),
+        (Verbosity::V, "1"),
+        (Verbosity::VV, "2"),
+        (Verbosity::VVV, "3"),
+    ];
+    for &(verbosity, value) in &verbosities {
+        assert_eq!(
+            v1_param(|q| q.with_verbosity(verbosity), "VERB"),
+            vec![value]
+        );
+    }
+}
+
+fn query(version: SiaVersion) -> SiaQuery<'static> {
+    SiaService::new("http://example.com/sia")
+        .with_version(version)
+        .create_query(Pos::circle(12.0, 34.0, 0.5).unwrap())
+}
+
+/// Parameters of the query string, in order.
+fn pairs(query: &SiaQuery) -> Vec<(String, String)> {
+    url::Url::parse(&query.query_url())
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+fn expected(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Add the parameters of both versions of the protocol.
+fn with_all_parameters(query: SiaQuery<'static>) -> SiaQuery<'static> {
+    query
+        .with_pos(Pos::range((10.0, 11.0), Interval::at_least(80.0)).unwrap())
+        .with_size(1.0, 2.0)
+        .with_intersect(Intersect::Overlaps)
+        .with_verbosity(Verbosity::VV)
+        .with_band((1e-7, 2e-7))
+        .with_band(Interval::at_most(3e-7))
+        .with_time(Interval::new(55000.0, 55001.5))
+        .with_pol(Polarization::I)
+        .with_pol(Polarization::POLA)
+        .with_fov(0.5)
+        .with_spatres(Interval::at_most(0.1))
+        .with_exptime(Interval::at_least(30.0))
+        .with_id("ivo://example/obs?1")
+        .with_collection("survey")
+        .with_facility("telescope")
+        .with_instrument("camera")
+        .with_dptype(DataProductType::Cube)
+        .with_calib(CalibLevel::Calibrated)
+        .with_target("M 31")
+        .with_timeres(Interval::at_most(1.0))
+        .with_specrp(Interval::at_least(1000.0))
+        .with_format(Format::Fits)
+        .with_format(Format::Png)
+        .with_maxrec(100)
+        .with_response_format("votable")
+        .with_naxis(300, 200)
+        .with_cframe(CoordFrame::Galactic)
+        .with_equinox(2000.0)
+        .with_crpix(150.5, 100.5)
+        .with_crval(12.0, 34.0)
+        .with_cdelt(-0.001, 0.001)
+        .with_rotang(45.0)
+        .with_proj("TAN")
+        .with_keyword("REQUEST", "queryData")
+}
+
+#[test]
+fn serializes_v2_parameters() {
+    assert_eq!(
+        pairs(&query(SiaVersion::V2)),
+        expected(&[("POS", "CIRCLE 12 34 0.5")])
+    );
+    // Parameters only defined by SIA 1.0 are not sent
+    assert_eq!(
+        pairs(&with_all_parameters(query(SiaVersion::V2))),
+        expected(&[
+            ("POS", "CIRCLE 12 34 0.5"),
+            ("POS", "RANGE 10 11 80 +Inf"),
+            ("SIZE", "1,2"),
+            ("INTERSECT", "OVERLAPS"),
+            ("VERB", "2"),
+            ("BAND", "0.0000001 0.0000002"),
+            ("BAND", "-Inf 0.0000003"),
+            ("TIME", "55000 55001.5"),
+            ("POL", "I"),
+            ("POL", "POLA"),
+            ("FOV", "0.5 0.5"),
+            ("SPATRES", "-Inf 0.1"),
+            ("EXPTIME", "30 +Inf"),
+            ("ID", "ivo://example/obs?1"),
+            ("COLLECTION", "survey"),
+            ("FACILITY", "telescope"),
+            ("INSTRUMENT", "camera"),
+            ("DPTYPE", "cube"),
+            ("CALIB", "2"),
+            ("TARGET", "M 31"),
+            ("TIMERES", "-Inf 1"),
+            ("SPECRP", "1000 +Inf"),
+            ("FORMAT", "application/fits"),
+            ("FORMAT", "image/png"),
+            ("MAXREC", "100"),
+            ("RESPONSEFORMAT", "votable"),
+            ("REQUEST", "queryData"),
+        ])
+    );
+}
+
+#[test]
+fn serializes_v1_parameters() {
+    // The size defaults to the diameter of the circle
+    assert_eq!(
+        pairs(&query(SiaVersion::V1)),
+        expected(&[("POS", "12,34"), ("SIZE", "1,1")])
+    );
+    // Only the first region is sent, and parameters only defined by SIA 2.0
+    // are not
+    assert_eq!(
+        pairs(&with_all_parameters(query(SiaVersion::V1))),
+        expected(&[
+            ("POS", "12,34"),
+            ("SIZE", "1,2"),
+            ("INTERSECT", "OVERLAPS"),
+            ("VERB", "2"),
+            ("FORMAT", "image/fits,image/png"),
+            ("NAXIS", "300,200"),
+            ("CFRAME", "GAL"),
+            ("EQUINOX", "2000"),
+            ("CRPIX", "150.5,100.5"),
+            ("CRVAL", "12,34"),
+            ("CDELT", "-0.001,0.001"),
+            ("ROTANG", "45"),
+            ("PROJ", "TAN"),
+            ("REQUEST", "queryData"),
+        ])
+    );
+}
+
+#[test]
+fn serializes_v1_regions_as_boxes() {
+    let service = SiaService::new("http://example.com/sia").with_version(SiaVersion::V1);
+    let range = Pos::range((10.0, 30.0), (-10.0, 10.0)).unwrap();
+    assert_eq!(
+        pairs(&service.create_query(range)),
+        expected(&[("POS", "20,0"), ("SIZE", "20,20")])
+    );
+    // Around the origin of longitudes
+    let range = Pos::range((350.0, 10.0), (-5.0, 5.0)).unwrap();
+    assert_eq!(
+        pairs(&service.create_query(range)),
+        expected(&[("POS", "0,0"), ("SIZE", "20,10")])
+    );
+    let polygon = Pos::polygon(vec![(350.0, -5.0), (10.0, -5.0), (10.0, 5.0), (350.0, 5.0)]);
+    assert_eq!(
+        pairs(&service.create_query(polygon.unwrap())),
+        expected(&[("POS", "0,0"), ("SIZE", "20,10")])
+    );
+}
+
+#[test]
+fn rejects_invalid_intervals() {
+    let server = MockService::start(|_| Reply::votable("<VOTABLE/>"));
+    let service = SiaService::new(&server.url);
+    let queries = vec![
+        service.create_query((12.0, 34.0)).with_band((2e-7, 1e-7)),
+        service
+            .create_query((12.0, 34.0))
+            .with_time(Interval::at_least(f64::NAN)),
+        service
+            .create_query((12.0, 34.0))
+            .with_exptime(30.0)
+            .with_specrp(Interval::new(1000.0, f64::NAN)),
+    ];
+    for query in queries {
+        match query.execute_sync() {
+            Err(Error::InvalidInterval(_)) => (),
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+    assert!(server.requests().is_empty());
+}
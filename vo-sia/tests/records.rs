@@ -0,0 +1,198 @@
+//! ObsCore accessors of query results.
+
+use vo_sia::{CalibLevel, Polarization, SIAResults, SiaVersion};
+use vo_table::VOTable;
+
+/// Results with a single record, made of `fields` and their `cells`.
+fn results(version: SiaVersion, coosys: &str, fields: &[&str], cells: &[&str]) -> SIAResults {
+    let fields: String = fields
+        .iter()
+        .map(|field| format!("<FIELD {}/>", field))
+        .collect();
+    let cells: String = cells
+        .iter()
+        .map(|cell| format!("<TD>{}</TD>", cell))
+        .collect();
+    let xml = format!(
+        r#"<VOTABLE version="1.3">
+  {}
+  <RESOURCE type="results">
+    <TABLE>
+      {}
+      <DATA><TABLEDATA><TR>{}</TR></TABLEDATA></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#,
+        coosys, fields, cells
+    );
+    SIAResults::new(VOTable::parse(xml.as_bytes()).unwrap(), version)
+}
+
+#[test]
+fn reads_obscore_columns_by_name() {
+    let columns = [
+        ("dataproduct_type", "char", "image"),
+        ("calib_level", "int", "2"),
+        ("obs_collection", "char", "survey"),
+        ("obs_id", "char", "obs-1"),
+        ("obs_title", "char", "Survey field 1"),
+        ("obs_publisher_did", "char", "ivo://example/survey?1"),
+        ("access_url", "char", "http://example.com/1.fits"),
+        ("access_format", "char", "application/fits"),
+        ("access_estsize", "long", "2048"),
+        ("target_name", "char", "M 31"),
+        ("s_ra", "double", "10.68"),
+        ("s_dec", "double", "41.27"),
+        ("s_fov", "double", "0.5"),
+        ("s_region", "char", "CIRCLE 10.68 41.27 0.25"),
+        ("s_resolution", "double", "1.2"),
+        ("t_min", "double", "55000.5"),
+        ("t_max", "double", "55000.75"),
+        ("t_exptime", "double", "300"),
+        ("em_min", "double", "4e-7"),
+        ("em_max", "double", "7e-7"),
+        ("em_res_power", "double", "1000"),
+        ("pol_states", "char", "/I/Q/X/"),
+        ("facility_name", "char", "telescope"),
+        ("instrument_name", "char", "camera"),
+    ];
+    let fields: Vec<_> = columns
+        .iter()
+        .map(|(name, datatype, _)| {
+            let arraysize = if *datatype == "char" {
+                r#" arraysize="*""#
+            } else {
+                ""
+            };
+            format!(r#"name="{}" datatype="{}"{}"#, name, datatype, arraysize)
+        })
+        .collect();
+    let fields: Vec<_> = fields.iter().map(String::as_str).collect();
+    let cells: Vec<_> = columns.iter().map(|(_, _, value)| *value).collect();
+    let results = results(SiaVersion::V2, "", &fields, &cells);
+    let record = results.records().next().unwrap();
+
+    assert_eq!(record.dataproduct_type(), Some("image"));
+    assert_eq!(record.calib_level(), Some(CalibLevel::Calibrated));
+    assert_eq!(record.obs_collection(), Some("survey"));
+    assert_eq!(record.obs_id(), Some("obs-1"));
+    assert_eq!(record.obs_title(), Some("Survey field 1"));
+    assert_eq!(record.obs_publisher_did(), Some("ivo://example/survey?1"));
+    assert_eq!(record.access_url(), Some("http://example.com/1.fits"));
+    assert_eq!(record.access_format(), Some("application/fits"));
+    assert_eq!(record.access_estsize(), Some(2048));
+    assert_eq!(record.target_name(), Some("M 31"));
+    assert_eq!(record.s_ra(), Some(10.68));
+    assert_eq!(record.s_dec(), Some(41.27));
+    assert_eq!(record.s_fov(), Some(0.5));
+    assert_eq!(record.s_region(), Some("CIRCLE 10.68 41.27 0.25"));
+    assert_eq!(record.s_resolution(), Some(1.2));
+    assert_eq!(record.t_min(), Some(55000.5));
+    assert_eq!(record.t_max(), Some(55000.75));
+    assert_eq!(record.t_exptime(), Some(300.0));
+    assert_eq!(record.em_min(), Some(4e-7));
+    assert_eq!(record.em_max(), Some(7e-7));
+    assert_eq!(record.em_res_power(), Some(1000.0));
+    // Unknown states are skipped
+    assert_eq!(
+        record.pol_states(),
+        Some(vec![Polarization::I, Polarization::Q])
+    );
+    assert_eq!(record.facility_name(), Some("telescope"));
+    assert_eq!(record.instrument_name(), Some("camera"));
+}
+
+#[test]
+fn falls_back_to_utype_and_ucd() {
+    let results = results(
+        SiaVersion::V2,
+        "",
+        &[
+            r#"name="url" datatype="char" arraysize="*" utype="OBSCORE:access.reference""#,
+            r#"name="ra" datatype="double" ucd="pos.eq.ra""#,
+            r#"name="dec" datatype="double" ucd="pos.eq.dec""#,
+            r#"name="other" datatype="char" arraysize="*" ucd="meta.id""#,
+        ],
+        &["http://example.com/1.fits", "10.5", "-20.25", "obs-1"],
+    );
+    let record = results.records().next().unwrap();
+    // Utypes are compared case-insensitively
+    assert_eq!(record.access_url(), Some("http://example.com/1.fits"));
+    assert_eq!(record.s_ra(), Some(10.5));
+    assert_eq!(record.s_dec(), Some(-20.25));
+    // Shared UCDs do not identify columns
+    assert_eq!(record.obs_id(), None);
+    assert_eq!(record.target_name(), None);
+}
+
+#[test]
+fn reads_sia_v1_columns() {
+    let fields = [
+        r#"name="url" datatype="char" arraysize="*" ucd="VOX:Image_AccessReference""#,
+        r#"name="format" datatype="char" arraysize="*" ucd="VOX:Image_Format""#,
+        r#"name="title" datatype="char" arraysize="*" ucd="VOX:Image_Title""#,
+        r#"name="size" datatype="long" ucd="VOX:Image_FileSize""#,
+        r#"name="ra" datatype="double" ucd="POS_EQ_RA_MAIN""#,
+        r#"name="dec" datatype="double" ucd="POS_EQ_DEC_MAIN""#,
+        // Also matches the SIA 2.0 name of the column
+        r#"name="access_url" datatype="char" arraysize="*""#,
+    ];
+    let cells = [
+        "http://example.com/v1.fits",
+        "image/fits",
+        "Field 1",
+        "1025",
+        "150",
+        "2.5",
+        "http://example.com/v2.fits",
+    ];
+    let results = results(SiaVersion::V1, "", &fields, &cells);
+    let record = results.records().next().unwrap();
+    assert_eq!(record.access_url(), Some("http://example.com/v1.fits"));
+    assert_eq!(record.access_format(), Some("image/fits"));
+    assert_eq!(record.obs_title(), Some("Field 1"));
+    // The size in bytes is rounded up to kilobytes
+    assert_eq!(record.access_estsize(), Some(2));
+    assert_eq!(record.s_ra(), Some(150.0));
+    assert_eq!(record.s_dec(), Some(2.5));
+
+    // SIA 2.0 columns have precedence for SIA 2.0 results
+    let results = self::results(SiaVersion::V2, "", &fields, &cells);
+    let record = results.records().next().unwrap();
+    assert_eq!(record.access_url(), Some("http://example.com/v2.fits"));
+    assert_eq!(record.access_format(), Some("image/fits"));
+}
+
+#[test]
+fn converts_positions_to_icrs() {
+    let fields = [
+        r#"name="s_ra" datatype="double" ref="gal""#,
+        r#"name="s_dec" datatype="double" ref="gal""#,
+    ];
+    let coosys = r#"<COOSYS ID="gal" system="galactic"/>"#;
+    let results = results(SiaVersion::V2, coosys, &fields, &["0", "0"]);
+    let position = results.records().next().unwrap().position().unwrap();
+    // The galactic center
+    let position = position.unwrap();
+    assert!((position.ra() - 266.405).abs() < 1e-3, "{:?}", position);
+    assert!((position.dec() + 28.936).abs() < 1e-3, "{:?}", position);
+
+    // Without COOSYS, positions are in ICRS
+    let results = self::results(SiaVersion::V2, "", &fields, &["10", "20"]);
+    let position = results.records().next().unwrap().position().unwrap();
+    let position = position.unwrap();
+    assert_eq!((position.ra(), position.dec()), (10.0, 20.0));
+
+    let results = self::results(SiaVersion::V2, "", &fields[..1], &["10"]);
+    assert!(results
+        .records()
+        .next()
+        .unwrap()
+        .position()
+        .unwrap()
+        .is_none());
+
+    let coosys = r#"<COOSYS ID="gal" system="geo_app"/>"#;
+    let results = self::results(SiaVersion::V2, coosys, &fields, &["0", "0"]);
+    assert!(results.records().next().unwrap().position().is_err());
+}
@@ -0,0 +1,117 @@
+#[macro_use]
+extern crate criterion;
+extern crate base64;
+extern crate byteorder;
+extern crate vo_table;
+
+use std::io::Cursor;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use criterion::Criterion;
+use vo_table::{MemoryResolver, ParseOptions};
+
+const ROWS: usize = 1_000_000;
+
+/// Generate the content of a BINARY stream of `rows` rows, mixing scalar,
+/// fixed-size and variable-size columns.
+fn generate_stream(rows: usize) -> Vec<u8> {
+    let mut stream = vec![];
+    for i in 0..rows {
+        let url = format!("http://example.com/data/image_{:08}.fits", i);
+        stream.write_i32::<BigEndian>(url.len() as i32).unwrap();
+        stream.extend_from_slice(url.as_bytes());
+        let mut format = *b"fits\0\0\0\0";
+        format[0] = b'a' + (i % 26) as u8;
+        stream.extend_from_slice(&format);
+        let size = if i % 10 == 0 { -1 } else { i as i32 };
+        stream.write_i32::<BigEndian>(size).unwrap();
+        stream.write_i64::<BigEndian>(i as i64 * 1000).unwrap();
+        stream.write_f64::<BigEndian>(i as f64 * 0.001).unwrap();
+        stream.write_f64::<BigEndian>(-(i as f64) * 0.001).unwrap();
+        stream.write_f32::<BigEndian>(2000.0).unwrap();
+        for axis in 0..3 {
+            stream.write_i16::<BigEndian>(axis as i16).unwrap();
+        }
+        stream.write_i32::<BigEndian>(2).unwrap();
+        stream.write_f64::<BigEndian>(0.5).unwrap();
+        stream.write_f64::<BigEndian>(0.25).unwrap();
+    }
+    stream
+}
+
+fn header(stream: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<VOTABLE version="1.3" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+<RESOURCE type="results"><TABLE>
+<FIELD name="access_url" datatype="char" arraysize="*"/>
+<FIELD name="format" datatype="char" arraysize="8"/>
+<FIELD name="size" datatype="int"><VALUES null="-1"/></FIELD>
+<FIELD name="id" datatype="long"/>
+<FIELD name="ra" datatype="double" unit="deg"/>
+<FIELD name="dec" datatype="double" unit="deg"/>
+<FIELD name="equinox" datatype="float"/>
+<FIELD name="axes" datatype="short" arraysize="3"/>
+<FIELD name="scale" datatype="double" arraysize="*"/>
+<DATA><BINARY>{}</BINARY></DATA></TABLE></RESOURCE></VOTABLE>
+"#,
+        stream
+    )
+}
+
+/// VOTable with an inline base64-encoded stream.
+fn generate_fixture(rows: usize) -> Vec<u8> {
+    let mut encoded = String::new();
+    for line in base64::encode(&generate_stream(rows)).as_bytes().chunks(76) {
+        encoded.push_str(std::str::from_utf8(line).unwrap());
+        encoded.push('\n');
+    }
+    let stream = format!(r#"<STREAM encoding="base64">{}</STREAM>"#, encoded);
+    header(&stream).into_bytes()
+}
+
+fn parse_binary(c: &mut Criterion) {
+    let fixture = generate_fixture(ROWS);
+    c.bench_function("parse BINARY 1M rows", move |b| {
+        b.iter(|| vo_table::parse(Cursor::new(&fixture)).unwrap())
+    });
+}
+
+/// Keep the data out of the XML document, so as to only measure the decoding
+/// of the BINARY serialization.
+fn decode_binary(c: &mut Criterion) {
+    let fixture = header(r#"<STREAM href="data.bin"/>"#).into_bytes();
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("data.bin", generate_stream(ROWS));
+    c.bench_function("decode BINARY 1M rows", move |b| {
+        b.iter(|| {
+            vo_table::parse_with_resolver(Cursor::new(&fixture), ParseOptions::default(), &resolver)
+                .unwrap()
+        })
+    });
+}
+
+fn read_binary(c: &mut Criterion) {
+    let fixture = generate_fixture(ROWS);
+    let votable = vo_table::parse(Cursor::new(&fixture)).unwrap();
+    c.bench_function("read BINARY 1M rows", move |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for table in votable.tables() {
+                for row in table.rows().unwrap() {
+                    if row.get_by_name("ra").is_some() {
+                        count += 1;
+                    }
+                }
+            }
+            count
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = parse_binary, decode_binary, read_binary
+}
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+extern crate vo_table;
+
+use std::io::{self, Cursor};
+
+use vo_table::VOTable;
+
+/// Keep the images of the SIA result centered north of -59.7°, and write
+/// them as a new VOTable.
+fn main() {
+    let string = include_bytes!("sia-result.xml");
+    let votable = vo_table::parse(Cursor::new(string.as_ref())).unwrap();
+    let table = votable.tables().next().unwrap();
+    let north = table.filter(|row| {
+        row.get_by_ucd("POS_EQ_DEC_MAIN")
+            .and_then(|cell| cell.as_f64())
+            .is_some_and(|dec| dec > -59.7)
+    });
+    eprintln!("{} of {} images", north.len(), table.len());
+    VOTable::from(north).write(io::stdout()).unwrap();
+}
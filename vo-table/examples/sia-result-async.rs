@@ -0,0 +1,27 @@
+extern crate futures_util;
+extern crate tokio;
+extern crate vo_table;
+
+use futures_util::{stream, StreamExt};
+use tokio::runtime::Runtime;
+use vo_table::ParseOptions;
+
+fn main() {
+    let string = include_bytes!("sia-result.xml");
+    // Simulate a download by chunks
+    let chunks = || stream::iter(string.chunks(1024).map(Ok::<_, std::io::Error>));
+    let runtime = Runtime::new().unwrap();
+
+    let votable = runtime
+        .block_on(vo_table::parse_async(chunks(), ParseOptions::default()))
+        .unwrap();
+    println!("Parsed {} rows", votable.len());
+
+    let tables = vo_table::parse_tables_async(chunks(), ParseOptions::default());
+    for table in runtime.block_on(tables.collect::<Vec<_>>()) {
+        let table = table.unwrap();
+        for (i, row) in table.rows().unwrap().enumerate() {
+            println!("{}. {:?}", i, row.get_by_ucd("VOX:Image_AccessReference"));
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Parsing of VOTables from asynchronous byte streams.
+//!
+//! The XML parser is synchronous, so it runs on the blocking thread pool of
+//! Tokio. The bytes received from the stream are handed over to it as they
+//! come, so that parsing goes along with the download without ever blocking
+//! the executor.
+
+use std::error;
+use std::future::Future;
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::task::{ready, Context as TaskContext, Poll};
+
+use futures_core::Stream;
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinHandle};
+use tokio_util::io::ReaderStream;
+use tokio_util::sync::PollSender;
+
+use {Context, Error, ParseOptions, Table, VOTable};
+
+/// Number of chunks buffered between the byte stream and the parser
+const CHUNK_BUFFER: usize = 16;
+/// Number of parsed tables buffered before the parser waits for them to be
+/// consumed
+const TABLE_BUFFER: usize = 1;
+
+type Chunk = io::Result<Vec<u8>>;
+type NextChunk = Box<dyn FnMut(&mut TaskContext) -> Poll<Option<Chunk>> + Send>;
+type Parse<T> = Box<dyn FnOnce() -> Result<T, Error> + Send>;
+
+/// Parse a VOTable from a stream of bytes, such as the body of an HTTP
+/// response.
+///
+/// The parser runs on the blocking thread pool of the Tokio runtime polling
+/// the returned future.
+pub fn parse_async<S, B, E>(stream: S, options: ParseOptions) -> ParseFuture
+where
+    S: Stream<Item = Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    let (feed, reader) = feed(stream);
+    ParseFuture {
+        feed: Some(feed),
+        parser: Parser::new(Box::new(move || {
+            VOTable::parse_with_options(reader, options)
+        })),
+    }
+}
+
+/// Parse a VOTable from an asynchronous reader.
+pub fn parse_async_read<R>(r: R, options: ParseOptions) -> ParseFuture
+where
+    R: AsyncRead + Send + 'static,
+{
+    parse_async(ReaderStream::new(r), options)
+}
+
+/// Parse a VOTable from a stream of bytes, and yield each table as soon as
+/// it is complete.
+///
+/// Tables are yielded in document order, including tables in nested
+/// resources. Rows are only available a table at a time: the rows of a
+/// table are yielded once all of them are parsed, so a large table is held
+/// in memory until its end.
+pub fn parse_tables_async<S, B, E>(stream: S, options: ParseOptions) -> TableStream
+where
+    S: Stream<Item = Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    let (feed, reader) = feed(stream);
+    let (sender, tables) = mpsc::channel(TABLE_BUFFER);
+    let parse = move || {
+        let mut sink = |table| {
+            // Keep parsing even if nobody listens anymore. The error is
+            // reported at the end anyway.
+            let _ = sender.blocking_send(table);
+        };
+        let mut ctx = Context::new(options, None);
+        ctx.table_sink = Some(&mut sink);
+        VOTable::parse_with_context(reader, ctx).map(|_| ())
+    };
+    TableStream {
+        feed: Some(feed),
+        tables,
+        parser: Some(Parser::new(Box::new(parse))),
+        error: None,
+    }
+}
+
+/// Future resolving to a parsed VOTable.
+///
+/// Created by [`parse_async`](fn.parse_async.html).
+#[must_use = "futures do nothing unless polled"]
+pub struct ParseFuture {
+    feed: Option<Feed>,
+    parser: Parser<VOTable>,
+}
+
+impl Future for ParseFuture {
+    type Output = Result<VOTable, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        poll_feed(&mut this.feed, cx);
+        this.parser.poll(cx)
+    }
+}
+
+/// Stream of the tables in a VOTable.
+///
+/// Created by [`parse_tables_async`](fn.parse_tables_async.html).
+#[must_use = "streams do nothing unless polled"]
+pub struct TableStream {
+    feed: Option<Feed>,
+    tables: mpsc::Receiver<Table>,
+    /// `None` once the parser is done
+    parser: Option<Parser<()>>,
+    /// Error of the parser, yielded after the last table
+    error: Option<Error>,
+}
+
+impl Stream for TableStream {
+    type Item = Result<Table, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        poll_feed(&mut this.feed, cx);
+        let done = match this.parser {
+            Some(ref mut parser) => match parser.poll(cx) {
+                Poll::Ready(result) => {
+                    this.error = result.err();
+                    true
+                }
+                Poll::Pending => false,
+            },
+            None => false,
+        };
+        if done {
+            this.parser = None;
+        }
+        match this.tables.poll_recv(cx) {
+            Poll::Ready(Some(table)) => Poll::Ready(Some(Ok(table))),
+            // The parser may have stopped without its result being ready yet
+            Poll::Ready(None) if this.parser.is_some() => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(this.error.take().map(Err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+fn parser_panicked() -> Error {
+    Error::Io(io::Error::other("VOTable parser panicked"))
+}
+
+/// Parser running on the blocking thread pool, started when first polled so
+/// that it may be created outside of a runtime.
+enum Parser<T> {
+    Pending(Option<Parse<T>>),
+    Running(JoinHandle<Result<T, Error>>),
+}
+
+impl<T: Send + 'static> Parser<T> {
+    fn new(parse: Parse<T>) -> Self {
+        Parser::Pending(Some(parse))
+    }
+
+    fn poll(&mut self, cx: &mut TaskContext) -> Poll<Result<T, Error>> {
+        if let Parser::Pending(ref mut parse) = *self {
+            let parse = parse.take().expect("parser started twice");
+            *self = Parser::Running(task::spawn_blocking(parse));
+        }
+        match *self {
+            Parser::Running(ref mut handle) => match ready!(Pin::new(handle).poll(cx)) {
+                Ok(result) => Poll::Ready(result),
+                Err(_) => Poll::Ready(Err(parser_panicked())),
+            },
+            Parser::Pending(_) => unreachable!(),
+        }
+    }
+}
+
+/// Transfer of bytes from the input stream to the parser.
+struct Feed {
+    next: NextChunk,
+    sender: PollSender<Chunk>,
+}
+
+impl Feed {
+    fn poll(&mut self, cx: &mut TaskContext) -> Poll<()> {
+        loop {
+            // Only take a chunk from the stream once the parser can accept
+            // it. Errors mean that the parser is done.
+            if ready!(self.sender.poll_reserve(cx)).is_err() {
+                return Poll::Ready(());
+            }
+            match ready!((self.next)(cx)) {
+                Some(chunk) => {
+                    if self.sender.send_item(chunk).is_err() {
+                        return Poll::Ready(());
+                    }
+                }
+                // End of stream
+                None => return Poll::Ready(()),
+            }
+        }
+    }
+}
+
+/// Drive the transfer of bytes from the input stream to the parser, until
+/// it completes.
+fn poll_feed(feed: &mut Option<Feed>, cx: &mut TaskContext) {
+    let done = match *feed {
+        Some(ref mut feed) => feed.poll(cx).is_ready(),
+        None => false,
+    };
+    if done {
+        // Dropping the sender signals the end of the stream to the parser
+        *feed = None;
+    }
+}
+
+/// Create the transfer of the content of `stream` to the returned reader.
+fn feed<S, B, E>(stream: S) -> (Feed, ChunkReader)
+where
+    S: Stream<Item = Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    let mut stream = Box::pin(stream);
+    let next = move |cx: &mut TaskContext| {
+        stream.as_mut().poll_next(cx).map(|chunk| {
+            chunk.map(|chunk| {
+                chunk
+                    .map(|chunk| chunk.as_ref().to_vec())
+                    .map_err(io::Error::other)
+            })
+        })
+    };
+    let (sender, receiver) = mpsc::channel(CHUNK_BUFFER);
+    let feed = Feed {
+        next: Box::new(next),
+        sender: PollSender::new(sender),
+    };
+    let reader = ChunkReader {
+        chunks: receiver,
+        current: vec![],
+        pos: 0,
+    };
+    (feed, reader)
+}
+
+/// Blocking reader over the chunks received from the input stream.
+struct ChunkReader {
+    chunks: mpsc::Receiver<Chunk>,
+    current: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.current.len() {
+            match self.chunks.blocking_recv() {
+                Some(Ok(chunk)) => {
+                    self.current = chunk;
+                    self.pos = 0;
+                }
+                Some(Err(e)) => return Err(e),
+                // End of stream
+                None => return Ok(0),
+            }
+        }
+        let len = (&self.current[self.pos..]).read(buf)?;
+        self.pos += len;
+        Ok(len)
+    }
+}
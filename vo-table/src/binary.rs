@@ -0,0 +1,388 @@
+//! Decoding of the BINARY serialization.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use column::{Column, ColumnValues};
+use {ArraySize, Cell, Context, Data, DataType, Error, Field, NullableDataValue, Warning};
+
+/// Minimum size of a stream to decode in parallel
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_BYTES: usize = 1 << 20;
+
+/// Decode all the rows in `bytes`, the content of a BINARY stream.
+pub(crate) fn decode(ctx: &mut Context, fields: &[Field], bytes: &[u8]) -> Result<Data, Error> {
+    #[cfg(feature = "parallel")]
+    {
+        if let Some(size) = row_size(fields) {
+            if size > 0 && bytes.len() >= PARALLEL_MIN_BYTES {
+                return decode_parallel(ctx, fields, bytes, size);
+            }
+        }
+    }
+    decode_rows(ctx, fields, bytes)
+}
+
+/// Decode rows of fixed size concurrently, by chunks of rows whose
+/// boundaries are known in advance.
+#[cfg(feature = "parallel")]
+fn decode_parallel(
+    ctx: &mut Context,
+    fields: &[Field],
+    bytes: &[u8],
+    row_size: usize,
+) -> Result<Data, Error> {
+    use rayon::prelude::*;
+
+    let rows = bytes.len() / row_size;
+    let chunk_rows = rows.div_ceil(rayon::current_num_threads()).max(1);
+    let options = ctx.options;
+    let chunks = bytes
+        .par_chunks(chunk_rows * row_size)
+        .map(|chunk| {
+            let mut chunk_ctx = Context::new(options, None);
+            decode_rows(&mut chunk_ctx, fields, chunk).map(|data| (data, chunk_ctx.warnings))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut chunks = chunks.into_iter();
+    let mut data = match chunks.next() {
+        Some((data, warnings)) => {
+            ctx.warnings.extend(warnings);
+            data
+        }
+        None => return decode_rows(ctx, fields, bytes),
+    };
+    for (chunk, warnings) in chunks {
+        ctx.warnings.extend(warnings);
+        for (column, chunk_column) in data.columns.iter_mut().zip(chunk.columns) {
+            column.append(chunk_column);
+        }
+        data.len += chunk.len;
+    }
+    Ok(data)
+}
+
+fn decode_rows(ctx: &mut Context, fields: &[Field], bytes: &[u8]) -> Result<Data, Error> {
+    let mut columns = Vec::with_capacity(fields.len());
+    for field in fields {
+        let datatype = field.datatype.ok_or_else(|| Error::CannotParse {
+            got: format!("Cannot parse field {:?}. Missing datatype", field.name),
+            target: "BINARY > STREAM",
+        })?;
+        columns.push(Column::new(field, datatype));
+    }
+    if let Some(rows) = row_size(fields).and_then(|size| bytes.len().checked_div(size)) {
+        for column in &mut columns {
+            column.reserve(rows);
+        }
+    }
+
+    let mut pos = 0;
+    let mut len = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        for (field, column) in fields.iter().zip(&mut columns) {
+            decode_cell(ctx, field, column, bytes, &mut pos)?;
+        }
+        if pos == start {
+            return Err(Error::CannotParse {
+                got: "rows of size zero".to_owned(),
+                target: "BINARY > STREAM",
+            });
+        }
+        len += 1;
+    }
+    Ok(Data { columns, len })
+}
+
+/// Size in bytes of each row, if all the fields have a fixed size.
+pub(crate) fn row_size(fields: &[Field]) -> Option<usize> {
+    let mut size = 0;
+    for field in fields {
+        let len = field.len()?;
+        size += match field.datatype? {
+            DataType::BitArray => len.div_ceil(8),
+            datatype => len * value_size(datatype),
+        };
+    }
+    Some(size)
+}
+
+/// Size in bytes of a single value.
+fn value_size(datatype: DataType) -> usize {
+    match datatype {
+        DataType::Logical | DataType::Byte | DataType::Character => 1,
+        DataType::UnicodeCharacter | DataType::Integer16 => 2,
+        DataType::Integer32 | DataType::Float32 => 4,
+        DataType::Integer64 | DataType::Float64 | DataType::Complex32 => 8,
+        DataType::Complex64 => 16,
+        // Bits are packed. See row_size.
+        DataType::BitArray => 1,
+    }
+}
+
+fn take<'b>(bytes: &'b [u8], pos: &mut usize, len: usize) -> Result<&'b [u8], Error> {
+    let end = *pos + len;
+    if end > bytes.len() {
+        return Err(Error::CannotParse {
+            got: format!(
+                "end of stream while reading {} bytes at offset {}",
+                len, *pos
+            ),
+            target: "BINARY > STREAM",
+        });
+    }
+    let slice = &bytes[*pos..end];
+    *pos = end;
+    Ok(slice)
+}
+
+fn decode_cell(
+    ctx: &mut Context,
+    field: &Field,
+    column: &mut Column,
+    bytes: &[u8],
+    pos: &mut usize,
+) -> Result<(), Error> {
+    let len = match field.len() {
+        Some(len) => len,
+        None => {
+            let len = BigEndian::read_i32(take(bytes, pos, 4)?);
+            if len < 0 {
+                return Err(Error::CannotParse {
+                    got: format!("negative array length {}", len),
+                    target: "BINARY > STREAM",
+                });
+            }
+            len as usize
+        }
+    };
+    if let Some(ArraySize::Variable { max }) = field.arraysize {
+        if len > max {
+            // Accepted in strict mode too, as the array can still be read
+            ctx.warn(Warning::ArrayTooLong {
+                field: field.name.clone(),
+                max,
+                len,
+            });
+        }
+    }
+
+    match column.values_mut() {
+        ColumnValues::Logical(values) => {
+            let input = take(bytes, pos, len)?;
+            values.extend(input.iter().map(|b| match b {
+                b'T' | b't' | b'1' => Some(true),
+                b'F' | b'f' | b'0' => Some(false),
+                _ => None,
+            }));
+        }
+        ColumnValues::Bit(values) => {
+            let input = take(bytes, pos, len.div_ceil(8))?;
+            values.extend((0..len).map(|i| input[i / 8] & (0x80 >> (i % 8)) != 0));
+        }
+        ColumnValues::Byte(values) => values.extend_from_slice(take(bytes, pos, len)?),
+        ColumnValues::Character(values) => {
+            let mut input = take(bytes, pos, len)?;
+            if let Some(last) = input.iter().position(|b| *b == 0) {
+                input = &input[..last];
+            }
+            match ::std::str::from_utf8(input) {
+                Ok(string) => values.push_str(string),
+                Err(_) => values.push_str(&String::from_utf8_lossy(input)),
+            }
+        }
+        ColumnValues::UnicodeCharacter(values) => {
+            let input = take(bytes, pos, len * 2)?;
+            let units = input
+                .chunks(2)
+                .map(BigEndian::read_u16)
+                .take_while(|unit| *unit != 0);
+            values.extend(
+                ::std::char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(::std::char::REPLACEMENT_CHARACTER)),
+            );
+        }
+        ColumnValues::Integer16(values) => {
+            let input = take(bytes, pos, len * 2)?;
+            read_into(values, input, 2, |b| {
+                let int = BigEndian::read_i16(b);
+                if field.is_null(int) {
+                    None
+                } else {
+                    Some(int)
+                }
+            });
+        }
+        ColumnValues::Integer32(values) => {
+            let input = take(bytes, pos, len * 4)?;
+            read_into(values, input, 4, |b| {
+                let int = BigEndian::read_i32(b);
+                if field.is_null(int) {
+                    None
+                } else {
+                    Some(int)
+                }
+            });
+        }
+        ColumnValues::Integer64(values) => {
+            let input = take(bytes, pos, len * 8)?;
+            read_into(values, input, 8, |b| {
+                let int = BigEndian::read_i64(b);
+                if field.is_null(int) {
+                    None
+                } else {
+                    Some(int)
+                }
+            });
+        }
+        ColumnValues::Float32(values) => {
+            let input = take(bytes, pos, len * 4)?;
+            read_into(values, input, 4, BigEndian::read_f32);
+        }
+        ColumnValues::Float64(values) => {
+            let input = take(bytes, pos, len * 8)?;
+            read_into(values, input, 8, BigEndian::read_f64);
+        }
+        ColumnValues::Complex32(values) => {
+            let input = take(bytes, pos, len * 8)?;
+            read_into(values, input, 8, |b| {
+                (BigEndian::read_f32(&b[..4]), BigEndian::read_f32(&b[4..]))
+            });
+        }
+        ColumnValues::Complex64(values) => {
+            let input = take(bytes, pos, len * 16)?;
+            read_into(values, input, 16, |b| {
+                (BigEndian::read_f64(&b[..8]), BigEndian::read_f64(&b[8..]))
+            });
+        }
+    }
+    column.end_row();
+    Ok(())
+}
+
+/// Push the values of `size` bytes read from `input` into `values`.
+#[inline]
+fn read_into<T, F>(values: &mut Vec<T>, input: &[u8], size: usize, read: F)
+where
+    F: Fn(&[u8]) -> T,
+{
+    // Fast path for scalars
+    if input.len() == size {
+        values.push(read(input));
+    } else {
+        values.extend(input.chunks(size).map(read));
+    }
+}
+
+/// Encode all the rows of `data` as the content of a BINARY stream.
+///
+/// Null integers are written as the value in `nulls`, given for each field.
+pub(crate) fn encode(
+    fields: &[Field],
+    nulls: &[Option<NullableDataValue>],
+    data: &Data,
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(row_size(fields).unwrap_or(0) * data.len);
+    for index in 0..data.len {
+        for ((field, null), column) in fields.iter().zip(nulls).zip(&data.columns) {
+            encode_cell(field, null.as_ref(), column.cell(index), &mut bytes);
+        }
+    }
+    bytes
+}
+
+fn encode_cell(field: &Field, null: Option<&NullableDataValue>, cell: Cell, bytes: &mut Vec<u8>) {
+    let len = field.len();
+    if len.is_none() {
+        bytes.extend_from_slice(&(cell.len() as i32).to_be_bytes());
+    }
+    match cell {
+        Cell::Logical(values) => bytes.extend(values.iter().map(|value| match value {
+            Some(true) => b'T',
+            Some(false) => b'F',
+            None => b'?',
+        })),
+        Cell::Bit(values) => {
+            let mut packed = vec![0u8; values.len().div_ceil(8)];
+            for (i, _) in values.iter().enumerate().filter(|(_, bit)| **bit) {
+                packed[i / 8] |= 0x80 >> (i % 8);
+            }
+            bytes.extend(packed);
+        }
+        Cell::Byte(values) => bytes.extend_from_slice(values),
+        Cell::Character(string) => {
+            let string = string.as_bytes();
+            match len {
+                Some(len) => {
+                    let string = &string[..string.len().min(len)];
+                    bytes.extend_from_slice(string);
+                    bytes.resize(bytes.len() + len - string.len(), 0);
+                }
+                None => bytes.extend_from_slice(string),
+            }
+        }
+        Cell::UnicodeCharacter(string) => {
+            let units: Vec<u16> = string.encode_utf16().collect();
+            let units = match len {
+                Some(len) => &units[..units.len().min(len)],
+                None => &units[..],
+            };
+            for unit in units {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+            if let Some(len) = len {
+                bytes.resize(bytes.len() + 2 * (len - units.len()), 0);
+            }
+        }
+        Cell::Integer16(values) => {
+            let null = match null {
+                Some(NullableDataValue::Integer16(null)) => *null,
+                _ => 0,
+            };
+            for value in values {
+                bytes.extend_from_slice(&value.unwrap_or(null).to_be_bytes());
+            }
+        }
+        Cell::Integer32(values) => {
+            let null = match null {
+                Some(NullableDataValue::Integer32(null)) => *null,
+                _ => 0,
+            };
+            for value in values {
+                bytes.extend_from_slice(&value.unwrap_or(null).to_be_bytes());
+            }
+        }
+        Cell::Integer64(values) => {
+            let null = match null {
+                Some(NullableDataValue::Integer64(null)) => *null,
+                _ => 0,
+            };
+            for value in values {
+                bytes.extend_from_slice(&value.unwrap_or(null).to_be_bytes());
+            }
+        }
+        Cell::Float32(values) => {
+            for value in values {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        Cell::Float64(values) => {
+            for value in values {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        Cell::Complex32(values) => {
+            for (re, im) in values {
+                bytes.extend_from_slice(&re.to_be_bytes());
+                bytes.extend_from_slice(&im.to_be_bytes());
+            }
+        }
+        Cell::Complex64(values) => {
+            for (re, im) in values {
+                bytes.extend_from_slice(&re.to_be_bytes());
+                bytes.extend_from_slice(&im.to_be_bytes());
+            }
+        }
+    }
+}
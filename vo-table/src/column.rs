@@ -0,0 +1,249 @@
+//! Columnar storage of table data.
+//!
+//! The values of each column are stored contiguously for all rows, so that
+//! decoding a table does not allocate for each cell.
+
+use std::ops::Range;
+
+use {ArraySize, Cell, DataType, Field};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Column {
+    values: ColumnValues,
+    /// Number of values in each row, for fixed-size columns
+    width: usize,
+    /// End offset in `values` of each row, for columns whose rows may have
+    /// different sizes
+    ends: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ColumnValues {
+    Logical(Vec<Option<bool>>),
+    Bit(Vec<bool>),
+    Byte(Vec<u8>),
+    Character(String),
+    UnicodeCharacter(String),
+    Integer16(Vec<Option<i16>>),
+    Integer32(Vec<Option<i32>>),
+    Integer64(Vec<Option<i64>>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Complex32(Vec<(f32, f32)>),
+    Complex64(Vec<(f64, f64)>),
+}
+
+impl Column {
+    pub fn new(field: &Field, datatype: DataType) -> Self {
+        let values = match datatype {
+            DataType::Logical => ColumnValues::Logical(vec![]),
+            DataType::BitArray => ColumnValues::Bit(vec![]),
+            DataType::Byte => ColumnValues::Byte(vec![]),
+            DataType::Character => ColumnValues::Character(String::new()),
+            DataType::UnicodeCharacter => ColumnValues::UnicodeCharacter(String::new()),
+            DataType::Integer16 => ColumnValues::Integer16(vec![]),
+            DataType::Integer32 => ColumnValues::Integer32(vec![]),
+            DataType::Integer64 => ColumnValues::Integer64(vec![]),
+            DataType::Float32 => ColumnValues::Float32(vec![]),
+            DataType::Float64 => ColumnValues::Float64(vec![]),
+            DataType::Complex32 => ColumnValues::Complex32(vec![]),
+            DataType::Complex64 => ColumnValues::Complex64(vec![]),
+        };
+        // Strings are truncated at the first NUL character, so their length
+        // is not known in advance even for fixed-size arrays.
+        let is_string = matches!(datatype, DataType::Character | DataType::UnicodeCharacter);
+        match field.arraysize {
+            Some(ArraySize::Fixed(width)) if !is_string => Column {
+                values,
+                width,
+                ends: None,
+            },
+            None if !is_string => Column {
+                values,
+                width: 1,
+                ends: None,
+            },
+            _ => Column {
+                values,
+                width: 0,
+                ends: Some(vec![]),
+            },
+        }
+    }
+
+    pub fn values(&self) -> &ColumnValues {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut ColumnValues {
+        &mut self.values
+    }
+
+    /// Check that `cell` can be pushed to the column: it must be of the
+    /// type of the column, and fill fixed-size columns exactly.
+    pub fn check(&self, cell: &Cell) -> Result<(), String> {
+        if cell.datatype() != self.values.datatype() {
+            return Err(format!(
+                "expected {} values, got {}",
+                self.values.datatype(),
+                cell.datatype()
+            ));
+        }
+        match self.ends {
+            None if cell.len() != self.width => Err(format!(
+                "expected {} values, got {}",
+                self.width,
+                cell.len()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Append `cell` as the value of a new row. See [`check`](#method.check).
+    pub fn push(&mut self, cell: Cell) {
+        use self::ColumnValues::*;
+        match (&mut self.values, cell) {
+            (Logical(values), Cell::Logical(cell)) => values.extend_from_slice(cell),
+            (Bit(values), Cell::Bit(cell)) => values.extend_from_slice(cell),
+            (Byte(values), Cell::Byte(cell)) => values.extend_from_slice(cell),
+            (Character(values), Cell::Character(cell)) => values.push_str(cell),
+            (UnicodeCharacter(values), Cell::UnicodeCharacter(cell)) => values.push_str(cell),
+            (Integer16(values), Cell::Integer16(cell)) => values.extend_from_slice(cell),
+            (Integer32(values), Cell::Integer32(cell)) => values.extend_from_slice(cell),
+            (Integer64(values), Cell::Integer64(cell)) => values.extend_from_slice(cell),
+            (Float32(values), Cell::Float32(cell)) => values.extend_from_slice(cell),
+            (Float64(values), Cell::Float64(cell)) => values.extend_from_slice(cell),
+            (Complex32(values), Cell::Complex32(cell)) => values.extend_from_slice(cell),
+            (Complex64(values), Cell::Complex64(cell)) => values.extend_from_slice(cell),
+            (values, cell) => panic!("Cannot push {:?} to {:?}", cell, values),
+        }
+        self.end_row();
+    }
+
+    /// Must be called after the values of each row are pushed.
+    pub fn end_row(&mut self) {
+        let len = self.values.len();
+        if let Some(ends) = &mut self.ends {
+            ends.push(len);
+        }
+    }
+
+    pub fn reserve(&mut self, rows: usize) {
+        match &mut self.ends {
+            Some(ends) => ends.reserve(rows),
+            None => self.values.reserve(rows * self.width),
+        }
+    }
+
+    /// Append the rows of `other`, a column of the same field.
+    #[cfg(feature = "parallel")]
+    pub fn append(&mut self, other: Column) {
+        let offset = self.values.len();
+        if let (Some(ends), Some(other_ends)) = (&mut self.ends, other.ends) {
+            ends.extend(other_ends.into_iter().map(|end| end + offset));
+        }
+        self.values.append(other.values);
+    }
+
+    fn range(&self, index: usize) -> Range<usize> {
+        match &self.ends {
+            Some(ends) => {
+                let start = if index == 0 { 0 } else { ends[index - 1] };
+                start..ends[index]
+            }
+            None => index * self.width..(index + 1) * self.width,
+        }
+    }
+
+    pub fn cell(&self, index: usize) -> Cell<'_> {
+        let range = self.range(index);
+        match &self.values {
+            ColumnValues::Logical(values) => Cell::Logical(&values[range]),
+            ColumnValues::Bit(values) => Cell::Bit(&values[range]),
+            ColumnValues::Byte(values) => Cell::Byte(&values[range]),
+            ColumnValues::Character(values) => Cell::Character(&values[range]),
+            ColumnValues::UnicodeCharacter(values) => Cell::UnicodeCharacter(&values[range]),
+            ColumnValues::Integer16(values) => Cell::Integer16(&values[range]),
+            ColumnValues::Integer32(values) => Cell::Integer32(&values[range]),
+            ColumnValues::Integer64(values) => Cell::Integer64(&values[range]),
+            ColumnValues::Float32(values) => Cell::Float32(&values[range]),
+            ColumnValues::Float64(values) => Cell::Float64(&values[range]),
+            ColumnValues::Complex32(values) => Cell::Complex32(&values[range]),
+            ColumnValues::Complex64(values) => Cell::Complex64(&values[range]),
+        }
+    }
+}
+
+impl ColumnValues {
+    fn datatype(&self) -> DataType {
+        match self {
+            ColumnValues::Logical(_) => DataType::Logical,
+            ColumnValues::Bit(_) => DataType::BitArray,
+            ColumnValues::Byte(_) => DataType::Byte,
+            ColumnValues::Character(_) => DataType::Character,
+            ColumnValues::UnicodeCharacter(_) => DataType::UnicodeCharacter,
+            ColumnValues::Integer16(_) => DataType::Integer16,
+            ColumnValues::Integer32(_) => DataType::Integer32,
+            ColumnValues::Integer64(_) => DataType::Integer64,
+            ColumnValues::Float32(_) => DataType::Float32,
+            ColumnValues::Float64(_) => DataType::Float64,
+            ColumnValues::Complex32(_) => DataType::Complex32,
+            ColumnValues::Complex64(_) => DataType::Complex64,
+        }
+    }
+
+    /// Number of values, or number of bytes for strings.
+    fn len(&self) -> usize {
+        match self {
+            ColumnValues::Logical(values) => values.len(),
+            ColumnValues::Bit(values) => values.len(),
+            ColumnValues::Byte(values) => values.len(),
+            ColumnValues::Character(values) => values.len(),
+            ColumnValues::UnicodeCharacter(values) => values.len(),
+            ColumnValues::Integer16(values) => values.len(),
+            ColumnValues::Integer32(values) => values.len(),
+            ColumnValues::Integer64(values) => values.len(),
+            ColumnValues::Float32(values) => values.len(),
+            ColumnValues::Float64(values) => values.len(),
+            ColumnValues::Complex32(values) => values.len(),
+            ColumnValues::Complex64(values) => values.len(),
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn append(&mut self, other: ColumnValues) {
+        use self::ColumnValues::*;
+        match (self, other) {
+            (Logical(values), Logical(other)) => values.extend(other),
+            (Bit(values), Bit(other)) => values.extend(other),
+            (Byte(values), Byte(other)) => values.extend(other),
+            (Character(values), Character(other)) => values.push_str(&other),
+            (UnicodeCharacter(values), UnicodeCharacter(other)) => values.push_str(&other),
+            (Integer16(values), Integer16(other)) => values.extend(other),
+            (Integer32(values), Integer32(other)) => values.extend(other),
+            (Integer64(values), Integer64(other)) => values.extend(other),
+            (Float32(values), Float32(other)) => values.extend(other),
+            (Float64(values), Float64(other)) => values.extend(other),
+            (Complex32(values), Complex32(other)) => values.extend(other),
+            (Complex64(values), Complex64(other)) => values.extend(other),
+            (values, other) => panic!("Cannot append {:?} to {:?}", other, values),
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        match self {
+            ColumnValues::Logical(values) => values.reserve(additional),
+            ColumnValues::Bit(values) => values.reserve(additional),
+            ColumnValues::Byte(values) => values.reserve(additional),
+            ColumnValues::Character(values) => values.reserve(additional),
+            ColumnValues::UnicodeCharacter(values) => values.reserve(additional),
+            ColumnValues::Integer16(values) => values.reserve(additional),
+            ColumnValues::Integer32(values) => values.reserve(additional),
+            ColumnValues::Integer64(values) => values.reserve(additional),
+            ColumnValues::Float32(values) => values.reserve(additional),
+            ColumnValues::Float64(values) => values.reserve(additional),
+            ColumnValues::Complex32(values) => values.reserve(additional),
+            ColumnValues::Complex64(values) => values.reserve(additional),
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use std::error;
 use std::fmt;
+use std::io;
 use std::num;
 
-use xml::reader;
+use xml::{reader, writer};
 
 #[derive(Debug)]
 pub enum Error {
@@ -18,6 +19,21 @@ pub enum Error {
         got: String,
         target: &'static str,
     },
+    StreamUnavailable {
+        href: String,
+        e: io::Error,
+    },
+    Io(io::Error),
+    XmlWriterError(writer::Error),
+    /// A row does not fit the fields of the table it is pushed to.
+    InvalidRow {
+        reason: String,
+    },
+    /// An integer column holding nulls has no value left to stand for null
+    /// in the BINARY serialization.
+    NoNullValue {
+        field: Option<String>,
+    },
 }
 
 impl From<reader::Error> for Error {
@@ -26,6 +42,12 @@ impl From<reader::Error> for Error {
     }
 }
 
+impl From<writer::Error> for Error {
+    fn from(e: writer::Error) -> Self {
+        Error::XmlWriterError(e)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Error::*;
@@ -46,8 +68,82 @@ impl fmt::Display for Error {
                 "Invalid VO Table file. Could not parse {}, instead got {}, which is unexpected.",
                 target, got
             ),
+            StreamUnavailable { href, e } => {
+                write!(f, "Could not read external stream '{}'. {}", href, e)
+            }
+            Io(e) => write!(f, "I/O error. {}", e),
+            XmlWriterError(e) => write!(f, "Error writing VO Table XML file: {}", e),
+            InvalidRow { reason } => write!(f, "Invalid row. {}", reason),
+            NoNullValue { field } => write!(
+                f,
+                "Cannot write nulls in field {:?}, which uses every value of its type.",
+                field
+            ),
         }
     }
 }
 
 impl error::Error for Error {}
+
+/// Non-conformances tolerated when parsing in non-strict mode.
+///
+/// See [`ParseOptions`](struct.ParseOptions.html).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// The datatype of a field is unknown or missing. The field is read as
+    /// `char` instead.
+    DataTypeFallback {
+        field: Option<String>,
+        got: Option<String>,
+    },
+    /// An attribute could not be parsed and was ignored.
+    IgnoredAttribute {
+        field: Option<String>,
+        attribute: &'static str,
+        got: String,
+    },
+    /// A variable-length array is longer than the maximum size declared in
+    /// its field.
+    ArrayTooLong {
+        field: Option<String>,
+        max: usize,
+        len: usize,
+    },
+    /// The data of a table was skipped, as the arraysize of one of its
+    /// fields could not be parsed.
+    SkippedData { field: Option<String> },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Warning::*;
+        match self {
+            DataTypeFallback { field, got } => {
+                match got {
+                    Some(got) => write!(f, "Unknown datatype '{}'", got)?,
+                    None => write!(f, "Missing datatype")?,
+                }
+                write!(f, " on field {:?}. Read as char.", field)
+            }
+            IgnoredAttribute {
+                field,
+                attribute,
+                got,
+            } => write!(
+                f,
+                "Could not parse attribute '{}' on field {:?}, got '{}'. Ignored.",
+                attribute, field, got
+            ),
+            ArrayTooLong { field, max, len } => write!(
+                f,
+                "Array of length {} on field {:?} exceeds declared maximum {}.",
+                len, field, max
+            ),
+            SkippedData { field } => write!(
+                f,
+                "Unknown arraysize on field {:?}. Data of the table skipped.",
+                field
+            ),
+        }
+    }
+}
@@ -1,14 +1,29 @@
 extern crate base64;
 extern crate byteorder;
+extern crate flate2;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "async")]
+extern crate tokio;
+#[cfg(feature = "async")]
+extern crate tokio_util;
 extern crate xml;
 
+#[cfg(feature = "async")]
+mod async_parse;
+mod binary;
+mod column;
 mod err;
+mod stream;
+mod tabledata;
+mod write;
 
 use std::fmt;
-use std::io::{Cursor, Read};
+use std::io::Read;
 use std::str::FromStr;
 
-use byteorder::{BigEndian, ReadBytesExt};
 use xml::{
     attribute::OwnedAttribute,
     name::OwnedName,
@@ -16,28 +31,143 @@ use xml::{
     ParserConfig,
 };
 
-pub use err::Error;
+#[cfg(feature = "async")]
+pub use async_parse::{
+    parse_async, parse_async_read, parse_tables_async, ParseFuture, TableStream,
+};
+use column::Column;
+pub use err::{Error, Warning};
+pub use stream::{FileSystemResolver, MemoryResolver, StreamResolver};
 
 pub fn parse<R: Read>(r: R) -> Result<VOTable, Error> {
     VOTable::parse(r)
 }
 
+pub fn parse_with_options<R: Read>(r: R, options: ParseOptions) -> Result<VOTable, Error> {
+    VOTable::parse_with_options(r, options)
+}
+
+pub fn parse_with_resolver<R: Read>(
+    r: R,
+    options: ParseOptions,
+    resolver: &dyn StreamResolver,
+) -> Result<VOTable, Error> {
+    VOTable::parse_with_resolver(r, options, resolver)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// If false, recoverable non-conformances to the VOTable standard do not
+    /// fail parsing. The parser falls back to a sensible interpretation and
+    /// records a [`Warning`](enum.Warning.html) on the returned VOTable.
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions { strict: true }
+    }
+}
+
+impl ParseOptions {
+    pub fn lenient() -> Self {
+        ParseOptions { strict: false }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct VOTable {
     description: Option<Description>,
+    infos: Vec<Info>,
+    coosys: Vec<CooSys>,
     resources: Vec<Resource>,
+    warnings: Vec<Warning>,
+}
+
+/// State shared by all the parsing functions.
+struct Context<'r> {
+    options: ParseOptions,
+    warnings: Vec<Warning>,
+    resolver: Option<&'r dyn StreamResolver>,
+    /// If defined, tables are handed to this function as soon as they are
+    /// parsed, instead of being stored in their resource.
+    table_sink: Option<&'r mut dyn FnMut(Table)>,
+}
+
+impl<'r> Context<'r> {
+    fn new(options: ParseOptions, resolver: Option<&'r dyn StreamResolver>) -> Self {
+        Context {
+            options,
+            warnings: vec![],
+            resolver,
+            table_sink: None,
+        }
+    }
+
+    /// Fail with `error` in strict mode. Record `warning` otherwise.
+    fn recover(&mut self, error: Error, warning: Warning) -> Result<(), Error> {
+        if self.options.strict {
+            Err(error)
+        } else {
+            self.warnings.push(warning);
+            Ok(())
+        }
+    }
+
+    /// Record `warning` in non-strict mode only, for non-conformances which
+    /// are accepted in strict mode too.
+    fn warn(&mut self, warning: Warning) {
+        if !self.options.strict {
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// Skip the content of the element whose start was just read.
+fn skip_element<R: Read>(events: &mut Events<R>) -> Result<(), Error> {
+    let mut depth = 0;
+    for event in events {
+        match event? {
+            StartElement { .. } => depth += 1,
+            EndElement { .. } => {
+                depth -= 1;
+                if depth == -1 {
+                    break;
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Resource {
     description: Option<Description>,
     infos: Vec<Info>,
+    coosys: Vec<CooSys>,
     tables: Vec<Table>,
     child_resources: Vec<Resource>,
 }
 
+/// An INFO element, used e.g. by services to report the status of a query.
+#[derive(Debug, Clone, Default)]
+pub struct Info {
+    id: Option<String>,
+    name: String,
+    value: String,
+    content: Option<String>,
+}
+
+/// A COOSYS element, defining the coordinate system of the columns that
+/// reference it.
 #[derive(Debug, Clone, Default)]
-struct Info {}
+pub struct CooSys {
+    id: Option<String>,
+    system: String,
+    equinox: Option<String>,
+    epoch: Option<String>,
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct Table {
@@ -51,8 +181,12 @@ struct Description {
     content: String,
 }
 
+/// A FIELD element, describing a column of a table.
+///
+/// Fields of new tables are built with [`Field::new`](#method.new) and the
+/// `with_*` methods.
 #[derive(Debug, Clone, Default)]
-struct Field {
+pub struct Field {
     id: Option<String>,
     name: Option<String>,
     datatype: Option<DataType>,
@@ -62,12 +196,19 @@ struct Field {
     xtype: Option<XType>,
     unit: Option<String>,
     ucd: Option<String>,
+    utype: Option<String>,
+    /// ID of another element, e.g. of the COOSYS of the field
+    reference: Option<String>,
     description: Option<Description>,
     values: Option<Values>,
+    /// Whether the arraysize attribute could not be parsed, in which case
+    /// the layout of the data is unknown
+    invalid_arraysize: bool,
 }
 
+/// Type of the values of a field.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum DataType {
+pub enum DataType {
     Logical,
     BitArray,
     Byte,
@@ -82,15 +223,16 @@ enum DataType {
     Complex64,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum ArraySize {
+/// Number of values in each cell of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArraySize {
     Unbounded,
     Variable { max: usize },
     Fixed(usize),
 }
 
-#[derive(Debug, Clone)]
-enum Precision {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
     ///  Number of significant digits after decimal point
     AfterDecimalPoint(usize),
     ///  Number of significant figures
@@ -111,28 +253,29 @@ struct Values {
 
 #[derive(Debug, Clone, Default)]
 struct Data {
-    rows: Vec<OwnedRow>,
-}
-
-#[derive(Debug, Clone, Default)]
-struct OwnedRow {
-    cells: Vec<Cell>,
+    /// One column for each field
+    columns: Vec<Column>,
+    /// Number of rows
+    len: usize,
 }
 
-#[derive(Debug, Clone)]
-pub enum Cell {
-    Logical(Vec<Option<bool>>),
-    Bit(Vec<bool>),
-    Byte(Vec<u8>),
-    Character(String),
-    UnicodeCharacter(String),
-    Integer16(Vec<Option<i16>>),
-    Integer32(Vec<Option<i32>>),
-    Integer64(Vec<Option<i64>>),
-    Float32(Vec<f32>),
-    Float64(Vec<f64>),
-    Complex32(Vec<(f32, f32)>),
-    Complex64(Vec<(f64, f64)>),
+/// Value of a cell, borrowed from its table.
+///
+/// Scalars are represented as slices of length one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell<'a> {
+    Logical(&'a [Option<bool>]),
+    Bit(&'a [bool]),
+    Byte(&'a [u8]),
+    Character(&'a str),
+    UnicodeCharacter(&'a str),
+    Integer16(&'a [Option<i16>]),
+    Integer32(&'a [Option<i32>]),
+    Integer64(&'a [Option<i64>]),
+    Float32(&'a [f32]),
+    Float64(&'a [f64]),
+    Complex32(&'a [(f32, f32)]),
+    Complex64(&'a [(f64, f64)]),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -166,6 +309,24 @@ impl Nullable for i64 {
 
 impl VOTable {
     pub fn parse<R: Read>(r: R) -> Result<Self, Error> {
+        VOTable::parse_with_options(r, ParseOptions::default())
+    }
+
+    pub fn parse_with_options<R: Read>(r: R, options: ParseOptions) -> Result<Self, Error> {
+        VOTable::parse_with_context(r, Context::new(options, None))
+    }
+
+    /// Parse a VOTable whose binary streams may refer to external data
+    /// through the `href` attribute.
+    pub fn parse_with_resolver<R: Read>(
+        r: R,
+        options: ParseOptions,
+        resolver: &dyn StreamResolver,
+    ) -> Result<Self, Error> {
+        VOTable::parse_with_context(r, Context::new(options, Some(resolver)))
+    }
+
+    fn parse_with_context<R: Read>(r: R, mut ctx: Context) -> Result<Self, Error> {
         let parser = ParserConfig::new()
             // Cannot trim whitespaces as white spaces are significant for some string types
             // .trim_whitespace(true)
@@ -178,54 +339,109 @@ impl VOTable {
             let event = event?;
             if let StartElement {
                 name: OwnedName { local_name, .. },
+                attributes,
                 ..
             } = event
             {
                 match local_name.as_str() {
-                    "DESCRIPTION" => if table.description.is_none() {
+                    "DESCRIPTION" if table.description.is_none() => {
                         table.description = Some(Description::parse(&mut events)?);
-                    },
-                    "RESOURCE" => table.resources.push(Resource::parse(&mut events)?),
+                    }
+                    "INFO" => table.infos.push(Info::parse(attributes, &mut events)?),
+                    "COOSYS" => table.coosys.push(CooSys::parse(attributes, &mut events)?),
+                    "RESOURCE" => table
+                        .resources
+                        .push(Resource::parse(&mut ctx, &mut events)?),
                     _ => (),
                 }
             }
         }
+        table.warnings = ctx.warnings;
         Ok(table)
     }
 
+    /// Add a resource, e.g. to write a VOTable built locally.
+    pub fn with_resource(mut self, resource: Resource) -> Self {
+        self.resources.push(resource);
+        self
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|desc| desc.content.as_str())
+    }
+
     pub fn resources(&self) -> &[Resource] {
         &self.resources
     }
 
-    pub fn len(&self) -> usize {
-        let mut len = 0;
-        for table in self.tables() {
-            if let Some(rows) = table.rows() {
-                for _ in rows {
-                    len += 1;
-                }
-            }
+    /// INFO elements directly under the VOTABLE element.
+    pub fn infos(&self) -> &[Info] {
+        &self.infos
+    }
+
+    /// COOSYS elements outside of resources.
+    pub fn coosys(&self) -> &[CooSys] {
+        &self.coosys
+    }
+
+    /// COOSYS element with the given ID, anywhere in the VOTable.
+    pub fn coosys_by_id(&self, id: &str) -> Option<&CooSys> {
+        fn find<'a>(resources: &'a [Resource], id: &str) -> Option<&'a CooSys> {
+            resources.iter().find_map(|resource| {
+                resource
+                    .coosys
+                    .iter()
+                    .find(|coosys| coosys.id() == Some(id))
+                    .or_else(|| find(&resource.child_resources, id))
+            })
         }
-        len
+        self.coosys
+            .iter()
+            .find(|coosys| coosys.id() == Some(id))
+            .or_else(|| find(&self.resources, id))
+    }
+
+    /// Non-conformances encountered while parsing in non-strict mode.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total number of rows in all the tables.
+    pub fn len(&self) -> usize {
+        self.tables().map(Table::len).sum()
     }
 
     /// Iterate over all the tables in the VOTable, included nested ones.
     pub fn tables(&self) -> impl Iterator<Item = &Table> {
-        self.resources
-            .iter()
-            .map(|resource| {
-                resource.tables().iter().chain(
-                    resource
-                        .children()
-                        .iter()
-                        .map(|child_resource| child_resource.tables())
-                        .flatten(),
-                )
-            }).flatten()
+        self.resources.iter().flat_map(|resource| {
+            resource.tables().iter().chain(
+                resource
+                    .children()
+                    .iter()
+                    .flat_map(|child_resource| child_resource.tables()),
+            )
+        })
+    }
+}
+
+/// A VOTable with a single resource containing `table`.
+impl From<Table> for VOTable {
+    fn from(table: Table) -> Self {
+        VOTable::default().with_resource(Resource::default().with_table(table))
     }
 }
 
 impl Description {
+    fn new(content: &str) -> Self {
+        Description {
+            content: content.to_owned(),
+        }
+    }
+
     fn parse<R: Read>(events: &mut Events<R>) -> Result<Self, Error> {
         let mut description = None;
         for event in events {
@@ -240,18 +456,29 @@ impl Description {
 }
 
 impl Resource {
-    fn parse<R: Read>(events: &mut Events<R>) -> Result<Self, Error> {
+    fn parse<R: Read>(ctx: &mut Context, events: &mut Events<R>) -> Result<Self, Error> {
         let mut resource = Resource::default();
         let mut depth = 0;
         while let Some(event) = events.next() {
             match event? {
                 StartElement {
                     name: OwnedName { local_name, .. },
+                    attributes,
                     ..
                 } => match local_name.as_str() {
-                    "INFO" => resource.infos.push(Info::parse(events)?),
-                    "TABLE" => resource.tables.push(Table::parse(events)?),
-                    "RESOURCE" => resource.child_resources.push(Resource::parse(events)?),
+                    "INFO" => resource.infos.push(Info::parse(attributes, events)?),
+                    "COOSYS" => resource.coosys.push(CooSys::parse(attributes, events)?),
+                    "DESCRIPTION" if resource.description.is_none() => {
+                        resource.description = Some(Description::parse(events)?);
+                    }
+                    "TABLE" => {
+                        let table = Table::parse(ctx, events)?;
+                        match &mut ctx.table_sink {
+                            Some(sink) => sink(table),
+                            None => resource.tables.push(table),
+                        }
+                    }
+                    "RESOURCE" => resource.child_resources.push(Resource::parse(ctx, events)?),
                     _ => depth += 1,
                 },
                 EndElement { .. } => {
@@ -266,6 +493,23 @@ impl Resource {
         Ok(resource)
     }
 
+    pub fn with_table(mut self, table: Table) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|desc| desc.content.as_str())
+    }
+
+    pub fn infos(&self) -> &[Info] {
+        &self.infos
+    }
+
+    pub fn coosys(&self) -> &[CooSys] {
+        &self.coosys
+    }
+
     pub fn tables(&self) -> &[Table] {
         &self.tables
     }
@@ -276,12 +520,32 @@ impl Resource {
 }
 
 impl Info {
-    fn parse<R: Read>(events: &mut Events<R>) -> Result<Self, Error> {
-        // TODO
-        let info = Info::default();
+    fn parse<R: Read>(
+        attributes: Vec<OwnedAttribute>,
+        events: &mut Events<R>,
+    ) -> Result<Self, Error> {
+        let mut info = Info::default();
+        for OwnedAttribute {
+            name: OwnedName { local_name, .. },
+            value,
+        } in attributes
+        {
+            match local_name.as_str() {
+                "ID" => info.id = Some(value),
+                "name" => info.name = value,
+                "value" => info.value = value,
+                _ => (),
+            }
+        }
         let mut depth = 0;
         for event in events {
             match event? {
+                Characters(content) if depth == 0 => {
+                    info.content = Some(match info.content.take() {
+                        Some(previous) => previous + &content,
+                        None => content,
+                    })
+                }
                 StartElement { .. } => depth += 1,
                 EndElement { .. } => {
                     depth -= 1;
@@ -294,10 +558,107 @@ impl Info {
         }
         Ok(info)
     }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Text content of the element, e.g. the message of an error.
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+}
+
+impl CooSys {
+    fn parse<R: Read>(
+        attributes: Vec<OwnedAttribute>,
+        events: &mut Events<R>,
+    ) -> Result<Self, Error> {
+        let mut coosys = CooSys::default();
+        for OwnedAttribute {
+            name: OwnedName { local_name, .. },
+            value,
+        } in attributes
+        {
+            match local_name.as_str() {
+                "ID" => coosys.id = Some(value),
+                "system" => coosys.system = value,
+                "equinox" => coosys.equinox = Some(value),
+                "epoch" => coosys.epoch = Some(value),
+                _ => (),
+            }
+        }
+        // Skip the content, e.g. FIELDref elements
+        let mut depth = 0;
+        for event in events {
+            match event? {
+                StartElement { .. } => depth += 1,
+                EndElement { .. } => {
+                    depth -= 1;
+                    if depth == -1 {
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(coosys)
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Coordinate system, e.g. "ICRS", "eq_FK5" or "galactic".
+    pub fn system(&self) -> &str {
+        &self.system
+    }
+
+    /// Equinox, e.g. "J2000" or "B1950".
+    pub fn equinox(&self) -> Option<&str> {
+        self.equinox.as_deref()
+    }
+
+    /// Epoch of the positions, e.g. "J2015.5".
+    pub fn epoch(&self) -> Option<&str> {
+        self.epoch.as_deref()
+    }
 }
 
 impl Table {
-    fn parse<R: Read>(events: &mut Events<R>) -> Result<Self, Error> {
+    /// Create a table without rows, whose columns are described by `fields`.
+    ///
+    /// Fields without datatype hold characters.
+    pub fn new(fields: Vec<Field>) -> Self {
+        let fields: Vec<_> = fields
+            .into_iter()
+            .map(|mut field| {
+                field.datatype = field.datatype.or(Some(DataType::Character));
+                field
+            })
+            .collect();
+        let data = Data::empty(&fields);
+        Table {
+            description: None,
+            fields,
+            data: Some(data),
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(Description::new(description));
+        self
+    }
+
+    fn parse<R: Read>(ctx: &mut Context, events: &mut Events<R>) -> Result<Self, Error> {
         let mut table = Table::default();
         let mut depth = 0;
         while let Some(event) = events.next() {
@@ -307,14 +668,27 @@ impl Table {
                     attributes,
                     ..
                 } => match local_name.as_str() {
+                    "DESCRIPTION" if table.description.is_none() => {
+                        table.description = Some(Description::parse(events)?);
+                    }
                     "FIELD" => {
-                        let field = Field::parse(attributes, events)?;
+                        let field = Field::parse(ctx, attributes, events)?;
                         table.fields.push(field);
                     }
-                    "DATA" => {
-                        let data = Data::parse(&table.fields, events)?;
-                        table.data = Some(data);
-                    }
+                    "DATA" => match table.fields.iter().find(|field| field.invalid_arraysize) {
+                        // Cells cannot be delimited without the size of
+                        // their arrays
+                        Some(field) => {
+                            ctx.warnings.push(Warning::SkippedData {
+                                field: field.name.clone(),
+                            });
+                            skip_element(events)?;
+                        }
+                        None => {
+                            let data = Data::parse(ctx, &table.fields, events)?;
+                            table.data = Some(data);
+                        }
+                    },
                     _ => depth += 1,
                 },
                 EndElement { .. } => {
@@ -329,61 +703,289 @@ impl Table {
         Ok(table)
     }
 
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|desc| desc.content.as_str())
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
     pub fn rows(&self) -> Option<impl Iterator<Item = Row<'_>>> {
         let fields = &self.fields;
-        self.data
-            .as_ref()
-            .map(|data| data.rows.iter().map(move |row| Row { fields, row }))
+        self.data.as_ref().map(|data| {
+            let columns = &data.columns;
+            (0..data.len).map(move |index| Row {
+                fields,
+                columns,
+                index,
+            })
+        })
+    }
+
+    /// Number of rows in the table.
+    pub fn len(&self) -> usize {
+        self.data.as_ref().map(|data| data.len).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append a row with one cell for each field.
+    ///
+    /// Fails with [`Error::InvalidRow`](enum.Error.html) and leaves the
+    /// table unchanged if a cell does not fit its field.
+    pub fn push_row(&mut self, cells: &[Cell]) -> Result<(), Error> {
+        if cells.len() != self.fields.len() {
+            return Err(Error::InvalidRow {
+                reason: format!("expected {} cells, got {}", self.fields.len(), cells.len()),
+            });
+        }
+        let fields = &self.fields;
+        let data = self.data.get_or_insert_with(|| Data::empty(fields));
+        for ((field, column), cell) in fields.iter().zip(&data.columns).zip(cells) {
+            field
+                .check(cell)
+                .and_then(|_| column.check(cell))
+                .map_err(|reason| Error::InvalidRow {
+                    reason: format!("field {:?}: {}", field.name, reason),
+                })?;
+        }
+        for (column, cell) in data.columns.iter_mut().zip(cells) {
+            column.push(*cell);
+        }
+        data.len += 1;
+        Ok(())
+    }
+
+    /// A table with the same fields, and only the rows for which `f`
+    /// returns true.
+    pub fn filter<F: FnMut(&Row) -> bool>(&self, mut f: F) -> Table {
+        let mut data = Data::empty(&self.fields);
+        if let Some(rows) = self.rows() {
+            for row in rows.filter(|row| f(row)) {
+                for (column, cell) in data.columns.iter_mut().zip(row.cells()) {
+                    column.push(cell);
+                }
+                data.len += 1;
+            }
+        }
+        Table {
+            description: self.description.clone(),
+            fields: self.fields.clone(),
+            data: Some(data),
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Row<'a> {
     fields: &'a [Field],
-    row: &'a OwnedRow,
+    columns: &'a [Column],
+    index: usize,
 }
 
 impl<'a> Row<'a> {
-    pub fn get_by_ucd(&self, ucd: &str) -> Option<&Cell> {
-        for (cell, field) in self.row.cells.iter().zip(self.fields) {
-            if let Some(check_ucd) = &field.ucd {
-                if check_ucd == ucd {
-                    return Some(cell);
-                }
-            }
-        }
-        None
+    pub fn get_by_ucd(&self, ucd: &str) -> Option<Cell<'a>> {
+        self.get_by(|field| field.ucd.as_deref() == Some(ucd))
     }
 
-    pub fn get_by_id(&self, id: &str) -> Option<&Cell> {
-        for (cell, field) in self.row.cells.iter().zip(self.fields) {
-            if let Some(check_id) = &field.id {
-                if check_id == id {
-                    return Some(cell);
-                }
-            }
-        }
-        None
+    /// Utypes are compared case-insensitively.
+    pub fn get_by_utype(&self, utype: &str) -> Option<Cell<'a>> {
+        self.get_by(|field| {
+            field
+                .utype
+                .as_ref()
+                .map(|field_utype| field_utype.eq_ignore_ascii_case(utype))
+                .unwrap_or(false)
+        })
     }
 
-    pub fn get_by_name(&self, name: &str) -> Option<&Cell> {
-        for (cell, field) in self.row.cells.iter().zip(self.fields) {
-            if let Some(check_name) = &field.name {
-                if check_name == name {
-                    return Some(cell);
-                }
+    pub fn get_by_id(&self, id: &str) -> Option<Cell<'a>> {
+        self.get_by(|field| field.id.as_deref() == Some(id))
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<Cell<'a>> {
+        self.get_by(|field| field.name.as_deref() == Some(name))
+    }
+
+    /// Cells of the row, in the order of the fields.
+    pub fn cells(&self) -> impl Iterator<Item = Cell<'a>> + 'a {
+        let index = self.index;
+        self.columns.iter().map(move |column| column.cell(index))
+    }
+
+    /// Value of the `ref` attribute of the field with the given UCD, e.g.
+    /// the ID of its COOSYS.
+    pub fn ref_by_ucd(&self, ucd: &str) -> Option<&'a str> {
+        self.field_by(|field| field.ucd.as_deref() == Some(ucd))
+            .and_then(|field| field.reference.as_deref())
+    }
+
+    /// Utypes are compared case-insensitively.
+    pub fn ref_by_utype(&self, utype: &str) -> Option<&'a str> {
+        self.field_by(|field| {
+            field
+                .utype
+                .as_ref()
+                .map(|field_utype| field_utype.eq_ignore_ascii_case(utype))
+                .unwrap_or(false)
+        })
+        .and_then(|field| field.reference.as_deref())
+    }
+
+    pub fn ref_by_id(&self, id: &str) -> Option<&'a str> {
+        self.field_by(|field| field.id.as_deref() == Some(id))
+            .and_then(|field| field.reference.as_deref())
+    }
+
+    pub fn ref_by_name(&self, name: &str) -> Option<&'a str> {
+        self.field_by(|field| field.name.as_deref() == Some(name))
+            .and_then(|field| field.reference.as_deref())
+    }
+
+    fn get_by<F: Fn(&Field) -> bool>(&self, f: F) -> Option<Cell<'a>> {
+        for (column, field) in self.columns.iter().zip(self.fields) {
+            if f(field) {
+                return Some(column.cell(self.index));
             }
         }
         None
     }
+
+    /// First field matching `f` that has a column.
+    fn field_by<F: Fn(&Field) -> bool>(&self, f: F) -> Option<&'a Field> {
+        let fields: &'a [Field] = self.fields;
+        fields
+            .iter()
+            .take(self.columns.len())
+            .find(|field| f(field))
+    }
 }
 
 impl Field {
+    pub fn new(name: &str, datatype: DataType) -> Self {
+        Field {
+            name: Some(name.to_owned()),
+            datatype: Some(datatype),
+            ..Field::default()
+        }
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_owned());
+        self
+    }
+
+    pub fn with_arraysize(mut self, arraysize: ArraySize) -> Self {
+        self.arraysize = Some(arraysize);
+        self
+    }
+
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn with_xtype(mut self, xtype: &str) -> Self {
+        self.xtype = Some(XType {
+            value: xtype.to_owned(),
+        });
+        self
+    }
+
+    pub fn with_unit(mut self, unit: &str) -> Self {
+        self.unit = Some(unit.to_owned());
+        self
+    }
+
+    pub fn with_ucd(mut self, ucd: &str) -> Self {
+        self.ucd = Some(ucd.to_owned());
+        self
+    }
+
+    pub fn with_utype(mut self, utype: &str) -> Self {
+        self.utype = Some(utype.to_owned());
+        self
+    }
+
+    /// Reference another element, e.g. the COOSYS of the field.
+    pub fn with_ref(mut self, reference: &str) -> Self {
+        self.reference = Some(reference.to_owned());
+        self
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(Description::new(description));
+        self
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn datatype(&self) -> Option<DataType> {
+        self.datatype
+    }
+
+    pub fn arraysize(&self) -> Option<ArraySize> {
+        self.arraysize
+    }
+
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    pub fn precision(&self) -> Option<Precision> {
+        self.precision
+    }
+
+    pub fn xtype(&self) -> Option<&str> {
+        self.xtype.as_ref().map(|xtype| xtype.value.as_str())
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref()
+    }
+
+    pub fn ucd(&self) -> Option<&str> {
+        self.ucd.as_deref()
+    }
+
+    pub fn utype(&self) -> Option<&str> {
+        self.utype.as_deref()
+    }
+
+    /// Value of the `ref` attribute, e.g. the ID of the COOSYS of the field.
+    pub fn reference(&self) -> Option<&str> {
+        self.reference.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|desc| desc.content.as_str())
+    }
+
     fn parse<R: Read>(
+        ctx: &mut Context,
         attributes: Vec<OwnedAttribute>,
         events: &mut Events<R>,
     ) -> Result<Self, Error> {
         let mut field = Field::default();
+        // Used to report warnings, whatever the order of the attributes
+        let name = attributes
+            .iter()
+            .find(|attr| attr.name.local_name == "name")
+            .map(|attr| attr.value.clone());
 
         for OwnedAttribute {
             name: OwnedName { local_name, .. },
@@ -393,27 +995,77 @@ impl Field {
             match local_name.as_str() {
                 "ID" => field.id = Some(value),
                 "name" => field.name = Some(value),
-                "datatype" => field.datatype = Some(DataType::from_str(&value)?),
-                "arraysize" => field.arraysize = Some(ArraySize::from_str(&value)?),
-                "width" => {
-                    field.width = Some(match FromStr::from_str(&value) {
-                        Ok(width) => width,
-                        Err(e) => {
-                            return Err(Error::CannotParseIntAttribute {
-                                e,
-                                attribute: "width",
-                            })
-                        }
-                    })
-                }
-                "precision" => field.precision = Some(Precision::from_str(&value)?),
+                "datatype" => match DataType::from_str(&value) {
+                    Ok(datatype) => field.datatype = Some(datatype),
+                    Err(e) => {
+                        ctx.recover(
+                            e,
+                            Warning::DataTypeFallback {
+                                field: name.clone(),
+                                got: Some(value),
+                            },
+                        )?;
+                        field.datatype = Some(DataType::Character);
+                    }
+                },
+                "arraysize" => match ArraySize::from_str(&value) {
+                    Ok(arraysize) => field.arraysize = Some(arraysize),
+                    Err(e) => {
+                        ctx.recover(
+                            e,
+                            Warning::IgnoredAttribute {
+                                field: name.clone(),
+                                attribute: "arraysize",
+                                got: value,
+                            },
+                        )?;
+                        field.invalid_arraysize = true;
+                    }
+                },
+                "width" => match FromStr::from_str(&value) {
+                    Ok(width) => field.width = Some(width),
+                    Err(e) => ctx.recover(
+                        Error::CannotParseIntAttribute {
+                            e,
+                            attribute: "width",
+                        },
+                        Warning::IgnoredAttribute {
+                            field: name.clone(),
+                            attribute: "width",
+                            got: value,
+                        },
+                    )?,
+                },
+                "precision" => match Precision::from_str(&value) {
+                    Ok(precision) => field.precision = Some(precision),
+                    Err(e) => ctx.recover(
+                        e,
+                        Warning::IgnoredAttribute {
+                            field: name.clone(),
+                            attribute: "precision",
+                            got: value,
+                        },
+                    )?,
+                },
                 "xtype" => field.xtype = Some(XType::from_str(&value)?),
                 "unit" => field.unit = Some(value),
                 "ucd" => field.ucd = Some(value),
+                "utype" => field.utype = Some(value),
+                "ref" => field.reference = Some(value),
                 _ => (),
             }
         }
 
+        // The datatype attribute is mandatory. Only fail when reading data
+        // in strict mode to keep accepting tables that have no data.
+        if field.datatype.is_none() && !ctx.options.strict {
+            ctx.warnings.push(Warning::DataTypeFallback {
+                field: name.clone(),
+                got: None,
+            });
+            field.datatype = Some(DataType::Character);
+        }
+
         let mut depth = 0;
         while let Some(event) = events.next() {
             match event? {
@@ -423,8 +1075,12 @@ impl Field {
                     ..
                 } => match local_name.as_str() {
                     "DESCRIPTION" => field.description = Some(Description::parse(events)?),
-                    "VALUES" => if let Some(datatype) = field.datatype {
-                        field.values = Some(Values::parse(datatype, attributes, events)?)
+                    "VALUES" => match field.datatype {
+                        Some(datatype) => {
+                            field.values =
+                                Some(Values::parse(ctx, &name, datatype, attributes, events)?)
+                        }
+                        None => depth += 1,
                     },
                     _ => depth += 1,
                 },
@@ -456,10 +1112,27 @@ impl Field {
             false
         }
     }
+
+    /// Check that strings fit the declared size of the field, which is
+    /// not enforced by their column.
+    fn check(&self, cell: &Cell) -> Result<(), String> {
+        let max = match self.arraysize {
+            Some(ArraySize::Fixed(max)) | Some(ArraySize::Variable { max }) => max,
+            Some(ArraySize::Unbounded) => return Ok(()),
+            None => 1,
+        };
+        if cell.len() > max {
+            Err(format!("{} values exceed arraysize {}", cell.len(), max))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl Values {
     fn parse<R: Read>(
+        ctx: &mut Context,
+        field: &Option<String>,
         datatype: DataType,
         attributes: Vec<OwnedAttribute>,
         events: &mut Events<R>,
@@ -472,7 +1145,17 @@ impl Values {
         } in attributes
         {
             if local_name == "null" {
-                values.null = Some(NullableDataValue::parse(datatype, &value)?);
+                match NullableDataValue::parse(datatype, &value) {
+                    Ok(null) => values.null = Some(null),
+                    Err(e) => ctx.recover(
+                        e,
+                        Warning::IgnoredAttribute {
+                            field: field.clone(),
+                            attribute: "null",
+                            got: value,
+                        },
+                    )?,
+                }
             }
         }
 
@@ -502,7 +1185,20 @@ impl Values {
 }
 
 impl Data {
-    fn parse<R: Read>(fields: &[Field], events: &mut Events<R>) -> Result<Self, Error> {
+    /// Data without rows, with a column for each field.
+    fn empty(fields: &[Field]) -> Self {
+        let columns = fields
+            .iter()
+            .map(|field| Column::new(field, field.datatype.unwrap_or(DataType::Character)))
+            .collect();
+        Data { columns, len: 0 }
+    }
+
+    fn parse<R: Read>(
+        ctx: &mut Context,
+        fields: &[Field],
+        events: &mut Events<R>,
+    ) -> Result<Self, Error> {
         let mut data = Data::default();
 
         let mut depth = 0;
@@ -512,10 +1208,14 @@ impl Data {
                     name: OwnedName { local_name, .. },
                     ..
                 } => match local_name.as_str() {
-                    "TABLEDATA" => unimplemented!("TABLEDATA"),
-                    "FITS" => unimplemented!("FITS"),
-                    "BINARY" => data = Data::parse_binary(fields, events)?,
-                    "BINARY2" => unimplemented!("BINARY2"),
+                    "TABLEDATA" => data = tabledata::decode(ctx, fields, events)?,
+                    "BINARY" => data = Data::parse_binary(ctx, fields, events)?,
+                    serialization @ ("FITS" | "BINARY2") => {
+                        return Err(Error::CannotParse {
+                            got: format!("unsupported {} serialization", serialization),
+                            target: "DATA",
+                        })
+                    }
                     _ => depth += 1,
                 },
                 EndElement { .. } => {
@@ -531,7 +1231,11 @@ impl Data {
         Ok(data)
     }
 
-    fn parse_binary<R: Read>(fields: &[Field], events: &mut Events<R>) -> Result<Self, Error> {
+    fn parse_binary<R: Read>(
+        ctx: &mut Context,
+        fields: &[Field],
+        events: &mut Events<R>,
+    ) -> Result<Self, Error> {
         let mut data = Data::default();
 
         let mut depth = 0;
@@ -542,7 +1246,7 @@ impl Data {
                     attributes,
                     ..
                 } => match local_name.as_str() {
-                    "STREAM" => data = Data::parse_binary_stream(fields, &attributes, events)?,
+                    "STREAM" => data = Data::parse_binary_stream(ctx, fields, &attributes, events)?,
                     _ => depth += 1,
                 },
                 EndElement { .. } => {
@@ -559,17 +1263,19 @@ impl Data {
     }
 
     fn parse_binary_stream<R: Read>(
+        ctx: &mut Context,
         fields: &[Field],
         attributes: &[OwnedAttribute],
         events: &mut Events<R>,
     ) -> Result<Self, Error> {
-        let encoding = attributes
-            .iter()
-            .find(|attr| attr.name.local_name == "encoding")
-            .ok_or_else(|| Error::CannotParse {
-                got: "encoding is missing".to_owned(),
-                target: "BINARY > STREAM",
-            })?;
+        let find_attribute = |name| {
+            attributes
+                .iter()
+                .find(|attr| attr.name.local_name == name)
+                .map(|attr| attr.value.as_str())
+        };
+        let encoding = find_attribute("encoding");
+        let href = find_attribute("href");
 
         let mut depth = 0;
         let mut some_input = None;
@@ -587,124 +1293,46 @@ impl Data {
             }
         }
 
-        let bytes = match encoding.value.as_str() {
-            "base64" => if let Some(input) = some_input {
-                // We need to strip spaces and newlines from input before decoding it
-                let mut stripped_input = String::with_capacity(input.len());
-                for chunk in input.split_whitespace() {
-                    stripped_input.push_str(chunk);
-                }
-                match base64::decode(&stripped_input) {
-                    Ok(bytes) => bytes,
-                    Err(e) => {
+        let bytes = if let Some(href) = href {
+            let resolver = ctx.resolver.ok_or_else(|| Error::CannotParse {
+                got: format!("external stream {} with no StreamResolver", href),
+                target: "BINARY > STREAM",
+            })?;
+            let stream = resolver
+                .resolve(href)
+                .map_err(|e| Error::StreamUnavailable {
+                    href: href.to_owned(),
+                    e,
+                })?;
+            stream::decode(href, stream, encoding)?
+        } else {
+            match encoding {
+                Some("base64") => {
+                    if let Some(input) = some_input {
+                        stream::decode_base64(&input)?
+                    } else {
                         return Err(Error::CannotParse {
-                            got: format!("{}", e),
+                            got: "No input defined in STREAM!".to_owned(),
                             target: "BINARY > STREAM",
-                        })
+                        });
                     }
                 }
-            } else {
-                return Err(Error::CannotParse {
-                    got: "No input defined in STREAM!".to_owned(),
-                    target: "BINARY > STREAM",
-                });
-            },
-            encoding => {
-                return Err(Error::CannotParse {
-                    got: format!("Cannot parse encoding {}", encoding),
-                    target: "BINARY > STREAM",
-                })
+                Some(encoding) => {
+                    return Err(Error::CannotParse {
+                        got: format!("Cannot parse encoding {}", encoding),
+                        target: "BINARY > STREAM",
+                    })
+                }
+                None => {
+                    return Err(Error::CannotParse {
+                        got: "encoding is missing".to_owned(),
+                        target: "BINARY > STREAM",
+                    })
+                }
             }
         };
 
-        let mut bytes = Cursor::new(bytes);
-        let mut data = Data::default();
-        'end: loop {
-            let mut row = OwnedRow::default();
-            for field in fields {
-                let cell;
-                let len = if let Some(len) = field.len() {
-                    len
-                } else {
-                    match bytes.read_i32::<BigEndian>() {
-                        Ok(len) => {
-                            assert!(len >= 0, "Length must bust be positive");
-                            len as usize
-                        }
-                        Err(_) => break 'end,
-                    }
-                };
-                match field.datatype.ok_or_else(|| Error::CannotParse {
-                    got: format!("Cannot parse field {:?}. Missing datatype", field.name),
-                    target: "BINARY > STREAM",
-                })? {
-                    DataType::Byte => {
-                        let mut buf = vec![0; len];
-                        bytes.read_exact(&mut buf).expect("No read error");
-                        cell = Cell::Byte(buf)
-                    }
-                    DataType::Character => {
-                        let mut buf = vec![0; len];
-                        bytes.read_exact(&mut buf).expect("No read error");
-                        if let Some(last) = buf.iter().position(|b| *b == 0) {
-                            buf.truncate(last);
-                        }
-                        cell = Cell::Character(String::from_utf8_lossy(&buf).to_string())
-                    }
-                    DataType::Integer16 => {
-                        let mut buf = vec![0; len];
-                        bytes
-                            .read_i16_into::<BigEndian>(&mut buf)
-                            .expect("No read error");
-                        cell = Cell::Integer16(
-                            buf.into_iter()
-                                .map(|int| if field.is_null(int) { None } else { Some(int) })
-                                .collect(),
-                        )
-                    }
-                    DataType::Integer32 => {
-                        let mut buf = vec![0; len];
-                        bytes
-                            .read_i32_into::<BigEndian>(&mut buf)
-                            .expect("No read error");
-                        cell = Cell::Integer32(
-                            buf.into_iter()
-                                .map(|int| if field.is_null(int) { None } else { Some(int) })
-                                .collect(),
-                        )
-                    }
-                    DataType::Integer64 => {
-                        let mut buf = vec![0; len];
-                        bytes
-                            .read_i64_into::<BigEndian>(&mut buf)
-                            .expect("No read error");
-                        cell = Cell::Integer64(
-                            buf.into_iter()
-                                .map(|int| if field.is_null(int) { None } else { Some(int) })
-                                .collect(),
-                        )
-                    }
-                    DataType::Float32 => {
-                        let mut buf = vec![0.0; len];
-                        bytes
-                            .read_f32_into::<BigEndian>(&mut buf)
-                            .expect("No read error");
-                        cell = Cell::Float32(buf)
-                    }
-                    DataType::Float64 => {
-                        let mut buf = vec![0.0; len];
-                        bytes
-                            .read_f64_into::<BigEndian>(&mut buf)
-                            .expect("No read error");
-                        cell = Cell::Float64(buf)
-                    }
-                    e => unimplemented!("{:?}", e),
-                }
-                row.cells.push(cell);
-            }
-            data.rows.push(row);
-        }
-        Ok(data)
+        binary::decode(ctx, fields, &bytes)
     }
 }
 
@@ -801,6 +1429,45 @@ impl FromStr for Precision {
     }
 }
 
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            DataType::Logical => "boolean",
+            DataType::BitArray => "bit",
+            DataType::Byte => "unsignedByte",
+            DataType::Integer16 => "short",
+            DataType::Integer32 => "int",
+            DataType::Integer64 => "long",
+            DataType::Character => "char",
+            DataType::UnicodeCharacter => "unicodeChar",
+            DataType::Float32 => "float",
+            DataType::Float64 => "double",
+            DataType::Complex32 => "floatComplex",
+            DataType::Complex64 => "doubleComplex",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl fmt::Display for ArraySize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArraySize::Unbounded => write!(f, "*"),
+            ArraySize::Variable { max } => write!(f, "{}*", max),
+            ArraySize::Fixed(len) => write!(f, "{}", len),
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Precision::AfterDecimalPoint(digits) => write!(f, "{}", digits),
+            Precision::SignificantFigures(figures) => write!(f, "E{}", figures),
+        }
+    }
+}
+
 impl FromStr for XType {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Error> {
@@ -810,6 +1477,12 @@ impl FromStr for XType {
     }
 }
 
+impl fmt::Display for XType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
 impl NullableDataValue {
     fn parse(datatype: DataType, s: &str) -> Result<Self, Error> {
         match datatype {
@@ -842,7 +1515,74 @@ impl NullableDataValue {
     }
 }
 
-impl fmt::Display for Cell {
+impl<'a> Cell<'a> {
+    /// Number of values, counting strings in bytes for `char` and in UTF-16
+    /// code units for `unicodeChar`.
+    fn len(&self) -> usize {
+        match self {
+            Cell::Logical(values) => values.len(),
+            Cell::Bit(values) => values.len(),
+            Cell::Byte(values) => values.len(),
+            Cell::Character(string) => string.len(),
+            Cell::UnicodeCharacter(string) => string.encode_utf16().count(),
+            Cell::Integer16(values) => values.len(),
+            Cell::Integer32(values) => values.len(),
+            Cell::Integer64(values) => values.len(),
+            Cell::Float32(values) => values.len(),
+            Cell::Float64(values) => values.len(),
+            Cell::Complex32(values) => values.len(),
+            Cell::Complex64(values) => values.len(),
+        }
+    }
+
+    fn datatype(&self) -> DataType {
+        match self {
+            Cell::Logical(_) => DataType::Logical,
+            Cell::Bit(_) => DataType::BitArray,
+            Cell::Byte(_) => DataType::Byte,
+            Cell::Character(_) => DataType::Character,
+            Cell::UnicodeCharacter(_) => DataType::UnicodeCharacter,
+            Cell::Integer16(_) => DataType::Integer16,
+            Cell::Integer32(_) => DataType::Integer32,
+            Cell::Integer64(_) => DataType::Integer64,
+            Cell::Float32(_) => DataType::Float32,
+            Cell::Float64(_) => DataType::Float64,
+            Cell::Complex32(_) => DataType::Complex32,
+            Cell::Complex64(_) => DataType::Complex64,
+        }
+    }
+
+    /// Get string value, if the cell contains characters.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Cell::Character(string) | Cell::UnicodeCharacter(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Get integer value, if the cell contains a single non-null integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Cell::Integer16([int]) => int.map(i64::from),
+            Cell::Integer32([int]) => int.map(i64::from),
+            Cell::Integer64([int]) => *int,
+            Cell::Byte([byte]) => Some(i64::from(*byte)),
+            _ => None,
+        }
+    }
+
+    /// Get floating-point value, if the cell contains a single non-null
+    /// number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Cell::Float32([float]) if !float.is_nan() => Some(f64::from(*float)),
+            Cell::Float64([float]) if !float.is_nan() => Some(*float),
+            _ => self.as_i64().map(|int| int as f64),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Cell<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Cell::*;
         fn format_slice<T, F>(slice: &[T], fmt: &mut fmt::Formatter, mut f: F) -> fmt::Result
@@ -868,7 +1608,7 @@ impl fmt::Display for Cell {
                 None => write!(f, "?"),
             }),
             Bit(bools) => {
-                for b in bools {
+                for b in bools.iter() {
                     if *b {
                         write!(f, "1")?;
                     } else {
@@ -0,0 +1,182 @@
+//! Access to binary data stored outside of the VOTable document.
+//!
+//! A `STREAM` element may point to its content with an `href` attribute
+//! instead of embedding it. A [`StreamResolver`](trait.StreamResolver.html)
+//! tells the parser how to get the data behind such a reference.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use base64;
+use flate2::read::GzDecoder;
+
+use err::Error;
+
+pub trait StreamResolver {
+    /// Open the stream referred to by `href`.
+    fn resolve<'a>(&'a self, href: &str) -> io::Result<Box<dyn Read + 'a>>;
+}
+
+/// Resolve `file://` URLs and relative paths against a base directory,
+/// usually the directory containing the VOTable document.
+///
+/// Only files under the base directory can be read, since the `href` comes
+/// from the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSystemResolver {
+    base: PathBuf,
+}
+
+impl FileSystemResolver {
+    pub fn new<P: Into<PathBuf>>(base: P) -> Self {
+        FileSystemResolver { base: base.into() }
+    }
+
+    /// Resolve paths relative to the parent directory of `document`.
+    pub fn for_document<P: AsRef<Path>>(document: P) -> Self {
+        let base = document
+            .as_ref()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        FileSystemResolver { base }
+    }
+}
+
+impl StreamResolver for FileSystemResolver {
+    fn resolve<'a>(&'a self, href: &str) -> io::Result<Box<dyn Read + 'a>> {
+        let path = if let Some(path) = href.strip_prefix("file://") {
+            path
+        } else if href.contains("://") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a local file: {}", href),
+            ));
+        } else {
+            href
+        };
+        // The document must not read files outside of the base directory
+        let base = self.base.canonicalize()?;
+        let path = base.join(path).canonicalize()?;
+        if !path.starts_with(&base) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is outside of {}", href, base.display()),
+            ));
+        }
+        let file = File::open(path)?;
+        Ok(Box::new(file))
+    }
+}
+
+/// Serve streams registered in memory, by their exact `href`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryResolver {
+    streams: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        MemoryResolver::default()
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, href: S, bytes: Vec<u8>) -> &mut Self {
+        self.streams.insert(href.into(), bytes);
+        self
+    }
+}
+
+impl StreamResolver for MemoryResolver {
+    fn resolve<'a>(&'a self, href: &str) -> io::Result<Box<dyn Read + 'a>> {
+        match self.streams.get(href) {
+            Some(bytes) => Ok(Box::new(Cursor::new(bytes.as_slice()))),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No stream registered for {}", href),
+            )),
+        }
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Read and decode the whole content of an external stream.
+pub(crate) fn decode<R: Read>(
+    href: &str,
+    mut stream: R,
+    encoding: Option<&str>,
+) -> Result<Vec<u8>, Error> {
+    let io_error = |e| Error::StreamUnavailable {
+        href: href.to_owned(),
+        e,
+    };
+    let mut bytes = vec![];
+    match encoding {
+        None | Some("") => {
+            stream.read_to_end(&mut bytes).map_err(io_error)?;
+        }
+        Some("gzip") => {
+            GzDecoder::new(stream)
+                .read_to_end(&mut bytes)
+                .map_err(io_error)?;
+        }
+        Some("base64") => {
+            let mut input = String::new();
+            stream.read_to_string(&mut input).map_err(io_error)?;
+            bytes = decode_base64(&input)?;
+        }
+        // The encoding is left to the transport. Recognize gzip from its
+        // magic number, and take anything else as is.
+        Some("dynamic") => {
+            stream.read_to_end(&mut bytes).map_err(io_error)?;
+            if bytes.starts_with(&GZIP_MAGIC) {
+                let mut decoded = vec![];
+                GzDecoder::new(bytes.as_slice())
+                    .read_to_end(&mut decoded)
+                    .map_err(io_error)?;
+                bytes = decoded;
+            }
+        }
+        Some(encoding) => {
+            return Err(Error::CannotParse {
+                got: format!("Cannot parse encoding {}", encoding),
+                target: "BINARY > STREAM",
+            })
+        }
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn decode_base64(input: &str) -> Result<Vec<u8>, Error> {
+    // We need to strip spaces and newlines from input before decoding it
+    let mut stripped_input = String::with_capacity(input.len());
+    for chunk in input.split_whitespace() {
+        stripped_input.push_str(chunk);
+    }
+    let map_err = |e: base64::DecodeError| Error::CannotParse {
+        got: format!("{}", e),
+        target: "BINARY > STREAM",
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        // Each chunk is made of whole groups of 4 characters, so it can be
+        // decoded independently.
+        const CHUNK_SIZE: usize = 1 << 20;
+        if stripped_input.len() > CHUNK_SIZE {
+            let chunks = stripped_input
+                .as_bytes()
+                .par_chunks(CHUNK_SIZE)
+                .map(base64::decode)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(map_err)?;
+            return Ok(chunks.concat());
+        }
+    }
+
+    base64::decode(&stripped_input).map_err(map_err)
+}
@@ -0,0 +1,270 @@
+//! Decoding of the TABLEDATA serialization, where each cell is the text of
+//! a TD element.
+
+use std::convert::TryFrom;
+use std::io::Read;
+use std::iter;
+use std::str::FromStr;
+
+use xml::{
+    name::OwnedName,
+    reader::{Events, XmlEvent::*},
+};
+
+use column::{Column, ColumnValues};
+use {ArraySize, Context, Data, Error, Field, Warning};
+
+/// Decode the rows of a TABLEDATA element, whose start was just read.
+pub(crate) fn decode<R: Read>(
+    ctx: &mut Context,
+    fields: &[Field],
+    events: &mut Events<R>,
+) -> Result<Data, Error> {
+    let mut columns = Vec::with_capacity(fields.len());
+    for field in fields {
+        let datatype = field.datatype.ok_or_else(|| Error::CannotParse {
+            got: format!("Cannot parse field {:?}. Missing datatype", field.name),
+            target: "TABLEDATA",
+        })?;
+        columns.push(Column::new(field, datatype));
+    }
+
+    let mut len = 0;
+    let mut cells = Vec::with_capacity(fields.len());
+    // Text of the TD element being read
+    let mut cell: Option<String> = None;
+    let mut depth = 0;
+    for event in events {
+        match event? {
+            StartElement {
+                name: OwnedName { local_name, .. },
+                ..
+            } => {
+                match local_name.as_str() {
+                    "TR" => cells.clear(),
+                    "TD" => cell = Some(String::new()),
+                    _ => (),
+                }
+                depth += 1;
+            }
+            Characters(text) | Whitespace(text) => {
+                if let Some(cell) = &mut cell {
+                    cell.push_str(&text);
+                }
+            }
+            EndElement {
+                name: OwnedName { local_name, .. },
+            } => {
+                depth -= 1;
+                if depth == -1 {
+                    break;
+                }
+                match local_name.as_str() {
+                    "TD" => cells.push(cell.take().unwrap_or_default()),
+                    "TR" => {
+                        decode_row(ctx, fields, &mut columns, &cells)?;
+                        len += 1;
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(Data { columns, len })
+}
+
+fn decode_row(
+    ctx: &mut Context,
+    fields: &[Field],
+    columns: &mut [Column],
+    cells: &[String],
+) -> Result<(), Error> {
+    if cells.len() != fields.len() {
+        return Err(Error::CannotParse {
+            got: format!("{} cells for {} fields", cells.len(), fields.len()),
+            target: "TABLEDATA > TR",
+        });
+    }
+    for ((field, column), text) in fields.iter().zip(columns).zip(cells) {
+        decode_cell(ctx, field, column, text)?;
+    }
+    Ok(())
+}
+
+/// Decode the text of a TD element. Empty cells are null.
+fn decode_cell(
+    ctx: &mut Context,
+    field: &Field,
+    column: &mut Column,
+    text: &str,
+) -> Result<(), Error> {
+    match column.values_mut() {
+        ColumnValues::Character(values) => {
+            check_len(ctx, field, text.len());
+            values.push_str(text);
+        }
+        ColumnValues::UnicodeCharacter(values) => {
+            check_len(ctx, field, text.encode_utf16().count());
+            values.push_str(text);
+        }
+        ColumnValues::Logical(values) => {
+            let parsed = parse_values(text, |value| match value.to_ascii_lowercase().as_str() {
+                "t" | "true" | "1" => Some(Some(true)),
+                "f" | "false" | "0" => Some(Some(false)),
+                "?" => Some(None),
+                _ => None,
+            })?;
+            push_values(ctx, field, values, parsed, None)?;
+        }
+        ColumnValues::Bit(values) => {
+            // Bits may be separated by spaces, or not
+            let parsed = text
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| match c {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(invalid_value(text)),
+                })
+                .collect::<Result<_, _>>()?;
+            push_values(ctx, field, values, parsed, false)?;
+        }
+        ColumnValues::Byte(values) => {
+            let parsed = parse_values(text, |value| {
+                parse_integer(value, |hex| u8::try_from(hex).ok())
+            })?;
+            push_values(ctx, field, values, parsed, 0)?;
+        }
+        ColumnValues::Integer16(values) => {
+            let parsed = parse_values(text, |value| {
+                parse_integer(value, |hex| u16::try_from(hex).ok().map(|int| int as i16))
+                    .map(|int| if field.is_null(int) { None } else { Some(int) })
+            })?;
+            push_values(ctx, field, values, parsed, None)?;
+        }
+        ColumnValues::Integer32(values) => {
+            let parsed = parse_values(text, |value| {
+                parse_integer(value, |hex| u32::try_from(hex).ok().map(|int| int as i32))
+                    .map(|int| if field.is_null(int) { None } else { Some(int) })
+            })?;
+            push_values(ctx, field, values, parsed, None)?;
+        }
+        ColumnValues::Integer64(values) => {
+            let parsed = parse_values(text, |value| {
+                parse_integer(value, |hex| Some(hex as i64)).map(|int| {
+                    if field.is_null(int) {
+                        None
+                    } else {
+                        Some(int)
+                    }
+                })
+            })?;
+            push_values(ctx, field, values, parsed, None)?;
+        }
+        ColumnValues::Float32(values) => {
+            let parsed = parse_values(text, parse_float)?;
+            push_values(ctx, field, values, parsed, f32::NAN)?;
+        }
+        ColumnValues::Float64(values) => {
+            let parsed = parse_values(text, parse_float)?;
+            push_values(ctx, field, values, parsed, f64::NAN)?;
+        }
+        ColumnValues::Complex32(values) => {
+            let parsed = parse_complex(text)?;
+            push_values(ctx, field, values, parsed, (f32::NAN, f32::NAN))?;
+        }
+        ColumnValues::Complex64(values) => {
+            let parsed = parse_complex(text)?;
+            push_values(ctx, field, values, parsed, (f64::NAN, f64::NAN))?;
+        }
+    }
+    column.end_row();
+    Ok(())
+}
+
+/// Push the values of a cell. Empty cells of fixed size are filled with
+/// `null`.
+fn push_values<T: Clone>(
+    ctx: &mut Context,
+    field: &Field,
+    values: &mut Vec<T>,
+    parsed: Vec<T>,
+    null: T,
+) -> Result<(), Error> {
+    match field.len() {
+        Some(len) if parsed.is_empty() => values.extend(iter::repeat_n(null, len)),
+        Some(len) if parsed.len() != len => {
+            return Err(Error::CannotParse {
+                got: format!("{} values for arraysize {}", parsed.len(), len),
+                target: "TABLEDATA > TD",
+            })
+        }
+        _ => {
+            check_len(ctx, field, parsed.len());
+            values.extend(parsed);
+        }
+    }
+    Ok(())
+}
+
+/// Check the length of a variable-length array against its maximum size.
+fn check_len(ctx: &mut Context, field: &Field, len: usize) {
+    if let Some(ArraySize::Variable { max }) = field.arraysize {
+        if len > max {
+            // Accepted in strict mode too, as the array can still be read
+            ctx.warn(Warning::ArrayTooLong {
+                field: field.name.clone(),
+                max,
+                len,
+            });
+        }
+    }
+}
+
+fn invalid_value(value: &str) -> Error {
+    Error::CannotParse {
+        got: value.to_owned(),
+        target: "TABLEDATA > TD",
+    }
+}
+
+/// Parse the values of a cell, separated by whitespace.
+fn parse_values<T, F>(text: &str, parse: F) -> Result<Vec<T>, Error>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    text.split_whitespace()
+        .map(|value| parse(value).ok_or_else(|| invalid_value(value)))
+        .collect()
+}
+
+/// Parse a decimal or `0x` prefixed hexadecimal integer. Hexadecimal
+/// values are the bits of the integer, converted by `from_bits`.
+fn parse_integer<T, F>(value: &str, from_bits: F) -> Option<T>
+where
+    T: FromStr,
+    F: Fn(u64) -> Option<T>,
+{
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok().and_then(from_bits),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parse a floating-point number, including `NaN`, `+Inf` and `-Inf`.
+fn parse_float<T: FromStr>(value: &str) -> Option<T> {
+    value.parse().ok()
+}
+
+fn parse_complex<T: FromStr + Copy>(text: &str) -> Result<Vec<(T, T)>, Error> {
+    let parts: Vec<T> = parse_values(text, parse_float)?;
+    let pairs = parts.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(invalid_value(text));
+    }
+    Ok(pairs.map(|pair| (pair[0], pair[1])).collect())
+}
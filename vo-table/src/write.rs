@@ -0,0 +1,281 @@
+//! Serialization of VOTables, with data in the BINARY format.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use binary;
+use column::{Column, ColumnValues};
+use {
+    CooSys, Data, DataType, Description, Error, Field, Info, NullableDataValue, Resource, Table,
+    VOTable,
+};
+
+const VOTABLE_VERSION: &str = "1.3";
+const VOTABLE_NAMESPACE: &str = "http://www.ivoa.net/xml/VOTable/v1.3";
+
+impl VOTable {
+    /// Write the VOTable as XML, with the data of its tables in base64
+    /// encoded BINARY streams.
+    ///
+    /// ```
+    /// # extern crate vo_table;
+    /// # fn main() {
+    /// use vo_table::{Cell, DataType, Field, Table, VOTable};
+    ///
+    /// let mut table = Table::new(vec![
+    ///     Field::new("ra", DataType::Float64).with_unit("deg"),
+    ///     Field::new("dec", DataType::Float64).with_unit("deg"),
+    /// ]);
+    /// table
+    ///     .push_row(&[Cell::Float64(&[10.68]), Cell::Float64(&[41.27])])
+    ///     .unwrap();
+    ///
+    /// let mut xml = Vec::new();
+    /// VOTable::from(table).write(&mut xml).unwrap();
+    /// let votable = VOTable::parse(&xml[..]).unwrap();
+    /// assert_eq!(votable.len(), 1);
+    /// # }
+    /// ```
+    pub fn write<W: Write>(&self, w: W) -> Result<(), Error> {
+        let mut writer = EmitterConfig::new().perform_indent(true).create_writer(w);
+        writer.write(
+            XmlEvent::start_element("VOTABLE")
+                .attr("version", VOTABLE_VERSION)
+                .default_ns(VOTABLE_NAMESPACE),
+        )?;
+        write_description(&mut writer, &self.description)?;
+        for info in &self.infos {
+            info.write(&mut writer)?;
+        }
+        for coosys in &self.coosys {
+            coosys.write(&mut writer)?;
+        }
+        for resource in &self.resources {
+            resource.write(&mut writer)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+fn write_description<W: Write>(
+    writer: &mut EventWriter<W>,
+    description: &Option<Description>,
+) -> Result<(), Error> {
+    if let Some(description) = description {
+        write_text(writer, "DESCRIPTION", &description.content)?;
+    }
+    Ok(())
+}
+
+fn write_text<W: Write>(writer: &mut EventWriter<W>, tag: &str, text: &str) -> Result<(), Error> {
+    writer.write(XmlEvent::start_element(tag))?;
+    writer.write(XmlEvent::characters(text))?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+impl Resource {
+    fn write<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("RESOURCE"))?;
+        write_description(writer, &self.description)?;
+        for info in &self.infos {
+            info.write(writer)?;
+        }
+        for coosys in &self.coosys {
+            coosys.write(writer)?;
+        }
+        for table in &self.tables {
+            table.write(writer)?;
+        }
+        for child in &self.child_resources {
+            child.write(writer)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Info {
+    fn write<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        let mut start = XmlEvent::start_element("INFO");
+        if let Some(id) = &self.id {
+            start = start.attr("ID", id);
+        }
+        writer.write(start.attr("name", &self.name).attr("value", &self.value))?;
+        if let Some(content) = &self.content {
+            writer.write(XmlEvent::characters(content))?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl CooSys {
+    fn write<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        let mut start = XmlEvent::start_element("COOSYS");
+        if let Some(id) = &self.id {
+            start = start.attr("ID", id);
+        }
+        start = start.attr("system", &self.system);
+        if let Some(equinox) = &self.equinox {
+            start = start.attr("equinox", equinox);
+        }
+        if let Some(epoch) = &self.epoch {
+            start = start.attr("epoch", epoch);
+        }
+        writer.write(start)?;
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Table {
+    fn write<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        let columns = self.data.as_ref().map(|data| &data.columns[..]);
+        let nulls: Vec<_> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| null_value(field, columns.and_then(|columns| columns.get(i))))
+            .collect::<Result<_, _>>()?;
+        writer.write(XmlEvent::start_element("TABLE"))?;
+        write_description(writer, &self.description)?;
+        for (field, null) in self.fields.iter().zip(&nulls) {
+            field.write(writer, null.as_ref())?;
+        }
+        if let Some(data) = &self.data {
+            write_data(writer, &self.fields, &nulls, data)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Field {
+    fn write<W: Write>(
+        &self,
+        writer: &mut EventWriter<W>,
+        null: Option<&NullableDataValue>,
+    ) -> Result<(), Error> {
+        let datatype = self.datatype.unwrap_or(DataType::Character).to_string();
+        let arraysize = self.arraysize.map(|arraysize| arraysize.to_string());
+        let width = self.width.map(|width| width.to_string());
+        let precision = self.precision.map(|precision| precision.to_string());
+
+        let mut start = XmlEvent::start_element("FIELD");
+        if let Some(id) = &self.id {
+            start = start.attr("ID", id);
+        }
+        start = start.attr("name", self.name.as_deref().unwrap_or(""));
+        start = start.attr("datatype", &datatype);
+        if let Some(arraysize) = &arraysize {
+            start = start.attr("arraysize", arraysize);
+        }
+        if let Some(width) = &width {
+            start = start.attr("width", width);
+        }
+        if let Some(precision) = &precision {
+            start = start.attr("precision", precision);
+        }
+        if let Some(xtype) = &self.xtype {
+            start = start.attr("xtype", &xtype.value);
+        }
+        if let Some(unit) = &self.unit {
+            start = start.attr("unit", unit);
+        }
+        if let Some(ucd) = &self.ucd {
+            start = start.attr("ucd", ucd);
+        }
+        if let Some(utype) = &self.utype {
+            start = start.attr("utype", utype);
+        }
+        if let Some(reference) = &self.reference {
+            start = start.attr("ref", reference);
+        }
+        writer.write(start)?;
+        write_description(writer, &self.description)?;
+        if let Some(null) = null {
+            let null = match null {
+                NullableDataValue::Integer16(null) => null.to_string(),
+                NullableDataValue::Integer32(null) => null.to_string(),
+                NullableDataValue::Integer64(null) => null.to_string(),
+            };
+            writer.write(XmlEvent::start_element("VALUES").attr("null", &null))?;
+            writer.write(XmlEvent::end_element())?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+fn write_data<W: Write>(
+    writer: &mut EventWriter<W>,
+    fields: &[Field],
+    nulls: &[Option<NullableDataValue>],
+    data: &Data,
+) -> Result<(), Error> {
+    let bytes = binary::encode(fields, nulls, data);
+    writer.write(XmlEvent::start_element("DATA"))?;
+    writer.write(XmlEvent::start_element("BINARY"))?;
+    writer.write(XmlEvent::start_element("STREAM").attr("encoding", "base64"))?;
+    writer.write(XmlEvent::characters(&base64::encode(&bytes)))?;
+    writer.write(XmlEvent::end_element())?;
+    writer.write(XmlEvent::end_element())?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+/// The value standing for null integers in the column of `field`.
+///
+/// This is the value declared by the field if any. Otherwise, if the
+/// column has null values, the minimum value of the type is used, or the
+/// maximum, or the smallest value which is not a valid value of the column.
+fn null_value(field: &Field, column: Option<&Column>) -> Result<Option<NullableDataValue>, Error> {
+    if let Some(null) = field.values.as_ref().and_then(|values| values.null.clone()) {
+        return Ok(Some(null));
+    }
+    let null = match column.map(Column::values) {
+        Some(ColumnValues::Integer16(values)) if values.contains(&None) => unused_value(
+            values.iter().flatten().map(|&value| value.into()),
+            i16::MIN.into(),
+            i16::MAX.into(),
+        )
+        .map(|null| NullableDataValue::Integer16(null as i16)),
+        Some(ColumnValues::Integer32(values)) if values.contains(&None) => unused_value(
+            values.iter().flatten().map(|&value| value.into()),
+            i32::MIN.into(),
+            i32::MAX.into(),
+        )
+        .map(|null| NullableDataValue::Integer32(null as i32)),
+        Some(ColumnValues::Integer64(values)) if values.contains(&None) => {
+            unused_value(values.iter().flatten().copied(), i64::MIN, i64::MAX)
+                .map(NullableDataValue::Integer64)
+        }
+        _ => return Ok(None),
+    };
+    match null {
+        Some(null) => Ok(Some(null)),
+        None => Err(Error::NoNullValue {
+            field: field.name.clone(),
+        }),
+    }
+}
+
+/// A value of [`min`, `max`] which is not one of `values`.
+fn unused_value<I: Iterator<Item = i64>>(values: I, min: i64, max: i64) -> Option<i64> {
+    let values: BTreeSet<i64> = values.collect();
+    if !values.contains(&min) {
+        return Some(min);
+    }
+    if !values.contains(&max) {
+        return Some(max);
+    }
+    // The first gap between the sorted values
+    (min..=max)
+        .zip(&values)
+        .find(|(candidate, value)| candidate != *value)
+        .map(|(candidate, _)| candidate)
+}
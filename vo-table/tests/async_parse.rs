@@ -0,0 +1,130 @@
+//! Parsing from asynchronous byte streams.
+
+#![cfg(feature = "async")]
+
+extern crate futures_util;
+extern crate tokio;
+extern crate vo_table;
+
+use std::io::{self, Cursor};
+use std::iter;
+
+use futures_util::{stream, StreamExt};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use vo_table::{ParseOptions, Table};
+
+const DOCUMENT: &str = r#"<?xml version="1.0"?>
+<VOTABLE version="1.4">
+  <RESOURCE>
+    <TABLE>
+      <DESCRIPTION>first</DESCRIPTION>
+      <FIELD name="x" datatype="int"/>
+      <DATA><TABLEDATA><TR><TD>1</TD></TR><TR><TD>2</TD></TR></TABLEDATA></DATA>
+    </TABLE>
+    <RESOURCE>
+      <TABLE>
+        <DESCRIPTION>nested</DESCRIPTION>
+        <FIELD name="x" datatype="int"/>
+        <DATA><TABLEDATA><TR><TD>3</TD></TR></TABLEDATA></DATA>
+      </TABLE>
+    </RESOURCE>
+    <TABLE>
+      <DESCRIPTION>last</DESCRIPTION>
+      <FIELD name="x" datatype="int"/>
+      <DATA><TABLEDATA></TABLEDATA></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#;
+
+fn chunks(
+    document: &'static str,
+    size: usize,
+) -> stream::Iter<impl Iterator<Item = io::Result<&'static [u8]>>> {
+    stream::iter(document.as_bytes().chunks(size).map(Ok))
+}
+
+fn descriptions<'a, I: IntoIterator<Item = &'a Table>>(tables: I) -> Vec<&'a str> {
+    tables
+        .into_iter()
+        .map(|table| table.description().unwrap())
+        .collect()
+}
+
+#[test]
+fn parses_chunked_stream() {
+    // The future may be created outside of the runtime
+    let future = vo_table::parse_async(chunks(DOCUMENT, 7), ParseOptions::default());
+    let votable = Runtime::new().unwrap().block_on(future).unwrap();
+    assert_eq!(votable.len(), 3);
+    assert_eq!(votable.resources()[0].children()[0].tables()[0].len(), 1);
+}
+
+#[test]
+fn parses_async_reader() {
+    let reader = Cursor::new(DOCUMENT.as_bytes().to_vec());
+    let future = vo_table::parse_async_read(reader, ParseOptions::default());
+    let votable = Runtime::new().unwrap().block_on(future).unwrap();
+    assert_eq!(votable.len(), 3);
+}
+
+#[test]
+fn reports_stream_errors() {
+    let (head, _) = DOCUMENT.split_at(200);
+    let reset = io::Error::new(io::ErrorKind::ConnectionReset, "reset");
+    let stream = stream::iter(
+        head.as_bytes()
+            .chunks(64)
+            .map(Ok)
+            .chain(iter::once(Err(reset))),
+    );
+    let future = vo_table::parse_async(stream, ParseOptions::default());
+    let err = Runtime::new().unwrap().block_on(future).unwrap_err();
+    assert!(format!("{}", err).contains("reset"), "{}", err);
+}
+
+#[test]
+fn yields_tables_in_document_order() {
+    let tables = vo_table::parse_tables_async(chunks(DOCUMENT, 16), ParseOptions::default());
+    let tables: Vec<_> = Runtime::new()
+        .unwrap()
+        .block_on(tables.collect::<Vec<_>>())
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(descriptions(&tables), vec!["first", "nested", "last"]);
+    assert_eq!(tables[0].len(), 2);
+}
+
+#[test]
+fn yields_tables_before_end_of_stream() {
+    let runtime = Runtime::new().unwrap();
+    let (sender, mut receiver) = mpsc::channel::<io::Result<Vec<u8>>>(4);
+    let stream = stream::poll_fn(move |cx| receiver.poll_recv(cx));
+    let mut tables = vo_table::parse_tables_async(stream, ParseOptions::default());
+
+    let split = DOCUMENT.find("<RESOURCE>\n      <TABLE>").unwrap();
+    sender
+        .blocking_send(Ok(DOCUMENT.as_bytes()[..split].to_vec()))
+        .unwrap();
+    let first = runtime.block_on(tables.next()).unwrap().unwrap();
+    assert_eq!(first.description(), Some("first"));
+
+    sender
+        .blocking_send(Ok(DOCUMENT.as_bytes()[split..].to_vec()))
+        .unwrap();
+    drop(sender);
+    let rest = runtime.block_on(tables.collect::<Vec<_>>());
+    assert_eq!(rest.len(), 2);
+}
+
+#[test]
+fn yields_tables_then_error() {
+    let split = DOCUMENT.find("<RESOURCE>\n      <TABLE>").unwrap();
+    let tables =
+        vo_table::parse_tables_async(chunks(&DOCUMENT[..split], 16), ParseOptions::default());
+    let results = Runtime::new().unwrap().block_on(tables.collect::<Vec<_>>());
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().description(), Some("first"));
+    assert!(results[1].is_err());
+}
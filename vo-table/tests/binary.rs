@@ -0,0 +1,220 @@
+//! Encoding and decoding of the BINARY serialization.
+
+extern crate vo_table;
+
+use vo_table::{ArraySize, Cell, DataType, Field, MemoryResolver, ParseOptions, Table, VOTable};
+
+const DATATYPES: [DataType; 12] = [
+    DataType::Logical,
+    DataType::BitArray,
+    DataType::Byte,
+    DataType::Character,
+    DataType::UnicodeCharacter,
+    DataType::Integer16,
+    DataType::Integer32,
+    DataType::Integer64,
+    DataType::Float32,
+    DataType::Float64,
+    DataType::Complex32,
+    DataType::Complex64,
+];
+
+/// Owned values of a cell of `len` values, for each datatype.
+struct Values {
+    logical: Vec<Option<bool>>,
+    bits: Vec<bool>,
+    bytes: Vec<u8>,
+    string: String,
+    unicode: String,
+    i16s: Vec<Option<i16>>,
+    i32s: Vec<Option<i32>>,
+    i64s: Vec<Option<i64>>,
+    f32s: Vec<f32>,
+    f64s: Vec<f64>,
+    c32s: Vec<(f32, f32)>,
+    c64s: Vec<(f64, f64)>,
+}
+
+impl Values {
+    /// Values including the extremes of each type, and a null at index 1.
+    fn new(len: usize) -> Self {
+        let nullable = |i: usize| if i == 1 { None } else { Some(i) };
+        Values {
+            logical: (0..len).map(|i| nullable(i).map(|i| i % 3 == 0)).collect(),
+            bits: (0..len).map(|i| i % 3 != 1).collect(),
+            bytes: (0..len).map(|i| [0, 255, 37][i % 3]).collect(),
+            string: "abcdefghij".chars().take(len).collect(),
+            unicode: "αβγδεζηθικ".chars().take(len).collect(),
+            i16s: (0..len)
+                .map(|i| nullable(i).map(|i| [i16::MIN, 0, i16::MAX][i % 3]))
+                .collect(),
+            i32s: (0..len)
+                .map(|i| nullable(i).map(|i| [i32::MIN, 0, i32::MAX][i % 3]))
+                .collect(),
+            i64s: (0..len)
+                .map(|i| nullable(i).map(|i| [i64::MIN, 0, i64::MAX][i % 3]))
+                .collect(),
+            f32s: (0..len)
+                .map(|i| [f32::MIN, -0.5, f32::INFINITY][i % 3])
+                .collect(),
+            f64s: (0..len).map(|i| [f64::MAX, 1e-300, -2.5][i % 3]).collect(),
+            c32s: (0..len).map(|i| (i as f32, -(i as f32) / 3.0)).collect(),
+            c64s: (0..len).map(|i| (-(i as f64), i as f64 / 7.0)).collect(),
+        }
+    }
+
+    fn cell(&self, datatype: DataType) -> Cell<'_> {
+        match datatype {
+            DataType::Logical => Cell::Logical(&self.logical),
+            DataType::BitArray => Cell::Bit(&self.bits),
+            DataType::Byte => Cell::Byte(&self.bytes),
+            DataType::Character => Cell::Character(&self.string),
+            DataType::UnicodeCharacter => Cell::UnicodeCharacter(&self.unicode),
+            DataType::Integer16 => Cell::Integer16(&self.i16s),
+            DataType::Integer32 => Cell::Integer32(&self.i32s),
+            DataType::Integer64 => Cell::Integer64(&self.i64s),
+            DataType::Float32 => Cell::Float32(&self.f32s),
+            DataType::Float64 => Cell::Float64(&self.f64s),
+            DataType::Complex32 => Cell::Complex32(&self.c32s),
+            DataType::Complex64 => Cell::Complex64(&self.c64s),
+        }
+    }
+}
+
+fn round_trip(table: &Table) -> VOTable {
+    let mut xml = Vec::new();
+    VOTable::from(table.clone()).write(&mut xml).unwrap();
+    VOTable::parse(&xml[..]).unwrap()
+}
+
+fn assert_same_rows(read: &Table, table: &Table) {
+    assert_eq!(read.len(), table.len());
+    for (read, row) in read.rows().unwrap().zip(table.rows().unwrap()) {
+        assert_eq!(
+            read.cells().collect::<Vec<_>>(),
+            row.cells().collect::<Vec<_>>()
+        );
+    }
+}
+
+/// Check that a column of each datatype with `arraysize` is read back as
+/// written, with rows of the lengths in `lens`.
+fn check_round_trip(arraysize: Option<ArraySize>, lens: &[usize]) {
+    let fields = DATATYPES
+        .iter()
+        .map(|datatype| {
+            let field = Field::new(&format!("{}", datatype), *datatype);
+            match arraysize {
+                Some(arraysize) => field.with_arraysize(arraysize),
+                None => field,
+            }
+        })
+        .collect();
+    let mut table = Table::new(fields);
+    for len in lens {
+        let values = Values::new(*len);
+        let cells: Vec<_> = DATATYPES
+            .iter()
+            .map(|datatype| values.cell(*datatype))
+            .collect();
+        table.push_row(&cells).unwrap();
+    }
+
+    let votable = round_trip(&table);
+    let read = votable.tables().next().unwrap();
+    for (field, datatype) in read.fields().iter().zip(&DATATYPES) {
+        assert_eq!(field.datatype(), Some(*datatype));
+        assert_eq!(field.arraysize(), arraysize);
+    }
+    assert_same_rows(read, &table);
+}
+
+#[test]
+fn round_trips_scalars() {
+    check_round_trip(None, &[1, 1, 1]);
+}
+
+#[test]
+fn round_trips_fixed_size_arrays() {
+    check_round_trip(Some(ArraySize::Fixed(1)), &[1, 1]);
+    check_round_trip(Some(ArraySize::Fixed(3)), &[3, 3, 3]);
+    // Bits are packed over several bytes
+    check_round_trip(Some(ArraySize::Fixed(10)), &[10, 10]);
+}
+
+#[test]
+fn round_trips_variable_size_arrays() {
+    check_round_trip(Some(ArraySize::Variable { max: 4 }), &[0, 1, 4, 2]);
+    check_round_trip(Some(ArraySize::Unbounded), &[0, 3, 10, 1]);
+}
+
+#[test]
+fn truncates_strings_at_nul() {
+    let mut table = Table::new(vec![
+        Field::new("s", DataType::Character).with_arraysize(ArraySize::Fixed(8)),
+        Field::new("u", DataType::UnicodeCharacter).with_arraysize(ArraySize::Fixed(4)),
+    ]);
+    table
+        .push_row(&[Cell::Character("abc"), Cell::UnicodeCharacter("αβ")])
+        .unwrap();
+    table
+        .push_row(&[Cell::Character(""), Cell::UnicodeCharacter("")])
+        .unwrap();
+    let votable = round_trip(&table);
+    assert_same_rows(votable.tables().next().unwrap(), &table);
+}
+
+/// Table of `rows` rows of fixed size, large enough to be decoded in
+/// parallel with the `parallel` feature.
+fn large_table(rows: usize) -> Table {
+    let mut table = Table::new(vec![
+        Field::new("id", DataType::Integer32),
+        Field::new("pos", DataType::Float64).with_arraysize(ArraySize::Fixed(2)),
+        Field::new("format", DataType::Character).with_arraysize(ArraySize::Fixed(8)),
+        Field::new("flags", DataType::BitArray).with_arraysize(ArraySize::Fixed(3)),
+    ]);
+    for i in 0..rows {
+        let id = if i % 7 == 0 { None } else { Some(i as i32) };
+        let format = ["fits", "jpeg", "png"][i % 3];
+        table
+            .push_row(&[
+                Cell::Integer32(&[id]),
+                Cell::Float64(&[i as f64 * 0.001, -(i as f64)]),
+                Cell::Character(format),
+                Cell::Bit(&[i % 2 == 0, i % 3 == 0, i % 5 == 0]),
+            ])
+            .unwrap();
+    }
+    table
+}
+
+#[test]
+fn decodes_large_streams() {
+    // 29 bytes per row, for 1.4 MB
+    let table = large_table(50_000);
+    let votable = round_trip(&table);
+    assert_same_rows(votable.tables().next().unwrap(), &table);
+}
+
+#[test]
+fn rejects_truncated_large_streams() {
+    let xml = r#"<?xml version="1.0"?>
+<VOTABLE version="1.3" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+  <RESOURCE>
+    <TABLE>
+      <FIELD name="x" datatype="double" arraysize="2"/>
+      <DATA><BINARY><STREAM href="data.bin"/></BINARY></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#;
+    let mut resolver = MemoryResolver::new();
+    // The last row misses a value
+    resolver.insert("data.bin", vec![0; 16 * 100_000 - 8]);
+    let result = vo_table::parse_with_resolver(xml.as_bytes(), ParseOptions::default(), &resolver);
+    assert!(result.is_err());
+
+    resolver.insert("data.bin", vec![0; 16 * 100_000]);
+    let votable =
+        vo_table::parse_with_resolver(xml.as_bytes(), ParseOptions::default(), &resolver).unwrap();
+    assert_eq!(votable.tables().next().unwrap().len(), 100_000);
+}
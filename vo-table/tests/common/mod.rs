@@ -0,0 +1,18 @@
+//! Documents shared by the tests.
+
+/// VOTable with a single table, made of the `fields` and of the `rows` of
+/// its TABLEDATA.
+pub fn votable(fields: &str, rows: &str) -> String {
+    format!(
+        r#"<?xml version="1.0"?>
+<VOTABLE version="1.3" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+  <RESOURCE>
+    <TABLE>
+      {}
+      <DATA><TABLEDATA>{}</TABLEDATA></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#,
+        fields, rows
+    )
+}
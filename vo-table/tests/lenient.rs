@@ -0,0 +1,134 @@
+//! Recovery from non-conformances in lenient mode.
+
+extern crate vo_table;
+
+mod common;
+
+use common::votable;
+use vo_table::{Cell, DataType, ParseOptions, VOTable, Warning};
+
+fn lenient(xml: &str) -> VOTable {
+    VOTable::parse_with_options(xml.as_bytes(), ParseOptions::lenient()).unwrap()
+}
+
+#[test]
+fn reads_unknown_datatypes_as_char() {
+    let xml = votable(
+        r#"<FIELD name="x" datatype="float128" arraysize="*"/>
+      <FIELD name="y" datatype="int"/>"#,
+        "<TR><TD>1.5e4000</TD><TD>3</TD></TR>",
+    );
+    assert!(VOTable::parse(xml.as_bytes()).is_err());
+
+    let votable = lenient(&xml);
+    assert_eq!(
+        votable.warnings(),
+        &[Warning::DataTypeFallback {
+            field: Some("x".to_owned()),
+            got: Some("float128".to_owned()),
+        }]
+    );
+    let table = votable.tables().next().unwrap();
+    assert_eq!(table.fields()[0].datatype(), Some(DataType::Character));
+    let row = table.rows().unwrap().next().unwrap();
+    assert_eq!(row.get_by_name("x"), Some(Cell::Character("1.5e4000")));
+    assert_eq!(row.get_by_name("y").unwrap().as_i64(), Some(3));
+}
+
+#[test]
+fn reads_missing_datatypes_as_char() {
+    let field = r#"<FIELD name="x" arraysize="*"/>"#;
+    // Tables without data are accepted in strict mode too
+    let empty = votable(field, "").replace("<DATA><TABLEDATA></TABLEDATA></DATA>", "");
+    let empty = VOTable::parse(empty.as_bytes()).unwrap();
+    assert!(empty.warnings().is_empty());
+
+    let xml = votable(field, "<TR><TD>abc</TD></TR>");
+    assert!(VOTable::parse(xml.as_bytes()).is_err());
+
+    let votable = lenient(&xml);
+    assert_eq!(
+        votable.warnings(),
+        &[Warning::DataTypeFallback {
+            field: Some("x".to_owned()),
+            got: None,
+        }]
+    );
+    let table = votable.tables().next().unwrap();
+    let row = table.rows().unwrap().next().unwrap();
+    assert_eq!(row.get_by_name("x"), Some(Cell::Character("abc")));
+}
+
+#[test]
+fn ignores_invalid_attributes() {
+    let xml = votable(
+        r#"<FIELD name="s" datatype="char" arraysize="*" width="wide"/>
+      <FIELD name="d" datatype="double" precision="G"/>
+      <FIELD name="i" datatype="int"><VALUES null="none"/></FIELD>"#,
+        "<TR><TD>abcdef</TD><TD>1.5</TD><TD>2</TD></TR>",
+    );
+    assert!(VOTable::parse(xml.as_bytes()).is_err());
+
+    let votable = lenient(&xml);
+    let ignored = |field: &str, attribute, got: &str| Warning::IgnoredAttribute {
+        field: Some(field.to_owned()),
+        attribute,
+        got: got.to_owned(),
+    };
+    assert_eq!(
+        votable.warnings(),
+        &[
+            ignored("s", "width", "wide"),
+            ignored("d", "precision", "G"),
+            ignored("i", "null", "none"),
+        ]
+    );
+    let table = votable.tables().next().unwrap();
+    let fields = table.fields();
+    assert_eq!(fields[0].width(), None);
+    assert_eq!(fields[1].precision(), None);
+    let row = table.rows().unwrap().next().unwrap();
+    assert_eq!(row.get_by_name("s"), Some(Cell::Character("abcdef")));
+    assert_eq!(row.get_by_name("i").unwrap().as_i64(), Some(2));
+}
+
+#[test]
+fn skips_data_with_invalid_arraysize() {
+    let xml = votable(
+        r#"<FIELD name="s" datatype="char" arraysize="many"/>
+      <FIELD name="i" datatype="int"/>"#,
+        "<TR><TD>abcdef</TD><TD>2</TD></TR>",
+    );
+    assert!(VOTable::parse(xml.as_bytes()).is_err());
+
+    let votable = lenient(&xml);
+    assert_eq!(
+        votable.warnings(),
+        &[
+            Warning::IgnoredAttribute {
+                field: Some("s".to_owned()),
+                attribute: "arraysize",
+                got: "many".to_owned(),
+            },
+            Warning::SkippedData {
+                field: Some("s".to_owned()),
+            },
+        ]
+    );
+    let table = votable.tables().next().unwrap();
+    // The size of the cells is not guessed
+    assert_eq!(table.fields()[0].arraysize(), None);
+    assert!(table.rows().is_none());
+}
+
+#[test]
+fn describes_warnings() {
+    let warning = Warning::DataTypeFallback {
+        field: Some("x".to_owned()),
+        got: None,
+    };
+    assert_eq!(
+        warning.to_string(),
+        "Missing datatype on field Some(\"x\"). Read as char."
+    );
+}
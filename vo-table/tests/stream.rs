@@ -0,0 +1,162 @@
+//! External streams read through resolvers.
+
+extern crate base64;
+extern crate flate2;
+extern crate vo_table;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use vo_table::{Error, FileSystemResolver, MemoryResolver, ParseOptions, VOTable};
+
+fn document(href: &str) -> String {
+    encoded_document(href, None)
+}
+
+fn encoded_document(href: &str, encoding: Option<&str>) -> String {
+    let encoding = encoding
+        .map(|encoding| format!(r#" encoding="{}""#, encoding))
+        .unwrap_or_default();
+    format!(
+        r#"<?xml version="1.0"?>
+<VOTABLE version="1.3" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+  <RESOURCE>
+    <TABLE>
+      <FIELD name="x" datatype="double"/>
+      <DATA><BINARY><STREAM href="{}"{}/></BINARY></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#,
+        href, encoding
+    )
+}
+
+/// A directory with `base/data.bin`, and `secret.bin` next to `base`.
+fn directory(name: &str) -> PathBuf {
+    let root = env::temp_dir().join(format!("vo-table-{}-{}", name, process::id()));
+    let base = root.join("base");
+    fs::create_dir_all(&base).unwrap();
+    fs::write(base.join("data.bin"), 1.5f64.to_be_bytes()).unwrap();
+    fs::write(root.join("secret.bin"), 2.5f64.to_be_bytes()).unwrap();
+    base
+}
+
+fn values(votable: &VOTable) -> Vec<f64> {
+    let table = votable.tables().next().unwrap();
+    table
+        .rows()
+        .unwrap()
+        .map(|row| row.get_by_name("x").unwrap().as_f64().unwrap())
+        .collect()
+}
+
+#[test]
+fn reads_files_under_base() {
+    let base = directory("under");
+    let resolver = FileSystemResolver::new(&base);
+    for href in &["data.bin", "./data.bin"] {
+        let votable = VOTable::parse_with_resolver(
+            document(href).as_bytes(),
+            ParseOptions::default(),
+            &resolver,
+        )
+        .unwrap();
+        assert_eq!(values(&votable), vec![1.5]);
+    }
+    let url = format!("file://{}", base.join("data.bin").display());
+    let votable = VOTable::parse_with_resolver(
+        document(&url).as_bytes(),
+        ParseOptions::default(),
+        &resolver,
+    )
+    .unwrap();
+    assert_eq!(values(&votable), vec![1.5]);
+}
+
+#[test]
+fn rejects_files_outside_base() {
+    let base = directory("outside");
+    let resolver = FileSystemResolver::new(&base);
+    let secret = base.parent().unwrap().join("secret.bin");
+    let hrefs = vec![
+        "../secret.bin".to_owned(),
+        secret.display().to_string(),
+        format!("file://{}", secret.display()),
+        "http://example.com/data.bin".to_owned(),
+    ];
+    for href in &hrefs {
+        match VOTable::parse_with_resolver(
+            document(href).as_bytes(),
+            ParseOptions::default(),
+            &resolver,
+        ) {
+            Err(Error::StreamUnavailable { href: got, .. }) => assert_eq!(&got, href),
+            other => panic!("Unexpected result for {}: {:?}", href, other),
+        }
+    }
+}
+
+#[test]
+fn reads_streams_in_memory() {
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("mem:x", 3.5f64.to_be_bytes().to_vec());
+    let votable = VOTable::parse_with_resolver(
+        document("mem:x").as_bytes(),
+        ParseOptions::default(),
+        &resolver,
+    )
+    .unwrap();
+    assert_eq!(values(&votable), vec![3.5]);
+    assert!(matches!(
+        VOTable::parse(document("mem:x").as_bytes()),
+        Err(Error::CannotParse { .. })
+    ));
+}
+
+#[test]
+fn decodes_stream_encodings() {
+    let data: Vec<u8> = [1.5f64, -2.0]
+        .iter()
+        .flat_map(|x| x.to_be_bytes().to_vec())
+        .collect();
+    let mut gzip = GzEncoder::new(vec![], Compression::default());
+    gzip.write_all(&data).unwrap();
+    let gzip = gzip.finish().unwrap();
+
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("raw", data.clone());
+    resolver.insert("gzip", gzip);
+    resolver.insert("base64", base64::encode(&data).into_bytes());
+    let parse = |href, encoding| {
+        VOTable::parse_with_resolver(
+            encoded_document(href, encoding).as_bytes(),
+            ParseOptions::default(),
+            &resolver,
+        )
+    };
+    let streams = [
+        ("raw", None),
+        ("gzip", Some("gzip")),
+        ("base64", Some("base64")),
+        // Compression is recognized with dynamic encoding
+        ("raw", Some("dynamic")),
+        ("gzip", Some("dynamic")),
+    ];
+    for &(href, encoding) in &streams {
+        assert_eq!(values(&parse(href, encoding).unwrap()), vec![1.5, -2.0]);
+    }
+
+    match parse("raw", Some("gzip")) {
+        Err(Error::StreamUnavailable { href, .. }) => assert_eq!(href, "raw"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert!(matches!(
+        parse("raw", Some("zstd")),
+        Err(Error::CannotParse { .. })
+    ));
+}
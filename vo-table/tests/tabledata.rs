@@ -0,0 +1,173 @@
+//! Decoding of the TABLEDATA serialization.
+
+extern crate vo_table;
+
+mod common;
+
+use common::votable;
+use vo_table::{Cell, Error, ParseOptions, VOTable, Warning};
+
+#[test]
+fn decodes_every_datatype() {
+    let xml = votable(
+        r#"<FIELD name="b" datatype="boolean"/>
+      <FIELD name="bits" datatype="bit" arraysize="5"/>
+      <FIELD name="u8" datatype="unsignedByte"/>
+      <FIELD name="i16" datatype="short"><VALUES null="-1"/></FIELD>
+      <FIELD name="i32" datatype="int" arraysize="2"/>
+      <FIELD name="i64" datatype="long" arraysize="*"/>
+      <FIELD name="s" datatype="char" arraysize="*"/>
+      <FIELD name="u" datatype="unicodeChar" arraysize="8"/>
+      <FIELD name="f32" datatype="float"/>
+      <FIELD name="f64" datatype="double" arraysize="3"/>
+      <FIELD name="c32" datatype="floatComplex"/>
+      <FIELD name="c64" datatype="doubleComplex" arraysize="*"/>"#,
+        "<TR><TD>T</TD><TD>1 0 1 1 0</TD><TD>0xff</TD><TD>12</TD><TD>1 -2</TD>\
+         <TD>1 2 3</TD><TD> Vega &amp; co </TD><TD>Ωmega</TD><TD>1.5</TD>\
+         <TD>NaN +Inf -Inf</TD><TD>1 2</TD><TD>1 2 3 4</TD></TR>\
+         <TR><TD>false</TD><TD>01000</TD><TD>7</TD><TD>-1</TD><TD/>\
+         <TD></TD><TD/><TD/><TD/><TD/><TD/><TD/></TR>",
+    );
+    let votable = VOTable::parse(xml.as_bytes()).unwrap();
+    let table = votable.tables().next().unwrap();
+    let rows: Vec<_> = table.rows().unwrap().collect();
+    assert_eq!(rows.len(), 2);
+
+    let (first, second) = (&rows[0], &rows[1]);
+    assert_eq!(first.get_by_name("b"), Some(Cell::Logical(&[Some(true)])));
+    assert_eq!(
+        first.get_by_name("bits"),
+        Some(Cell::Bit(&[true, false, true, true, false]))
+    );
+    assert_eq!(first.get_by_name("u8"), Some(Cell::Byte(&[255])));
+    assert_eq!(first.get_by_name("i16"), Some(Cell::Integer16(&[Some(12)])));
+    assert_eq!(
+        first.get_by_name("i32"),
+        Some(Cell::Integer32(&[Some(1), Some(-2)]))
+    );
+    assert_eq!(
+        first.get_by_name("i64"),
+        Some(Cell::Integer64(&[Some(1), Some(2), Some(3)]))
+    );
+    // Whitespace is significant in strings
+    assert_eq!(first.get_by_name("s"), Some(Cell::Character(" Vega & co ")));
+    assert_eq!(
+        first.get_by_name("u"),
+        Some(Cell::UnicodeCharacter("Ωmega"))
+    );
+    assert_eq!(first.get_by_name("f32"), Some(Cell::Float32(&[1.5])));
+    match first.get_by_name("f64") {
+        Some(Cell::Float64(&[nan, inf, neg_inf])) => {
+            assert!(nan.is_nan());
+            assert_eq!((inf, neg_inf), (f64::INFINITY, f64::NEG_INFINITY));
+        }
+        other => panic!("Unexpected cell {:?}", other),
+    }
+    assert_eq!(
+        first.get_by_name("c32"),
+        Some(Cell::Complex32(&[(1.0, 2.0)]))
+    );
+    assert_eq!(
+        first.get_by_name("c64"),
+        Some(Cell::Complex64(&[(1.0, 2.0), (3.0, 4.0)]))
+    );
+
+    // Empty cells are null, and fill fixed-size arrays
+    assert_eq!(second.get_by_name("b"), Some(Cell::Logical(&[Some(false)])));
+    assert_eq!(
+        second.get_by_name("bits"),
+        Some(Cell::Bit(&[false, true, false, false, false]))
+    );
+    assert_eq!(second.get_by_name("i16"), Some(Cell::Integer16(&[None])));
+    assert_eq!(
+        second.get_by_name("i32"),
+        Some(Cell::Integer32(&[None, None]))
+    );
+    assert_eq!(second.get_by_name("i64"), Some(Cell::Integer64(&[])));
+    assert_eq!(second.get_by_name("s"), Some(Cell::Character("")));
+    match second.get_by_name("f64") {
+        Some(Cell::Float64(values)) => {
+            assert_eq!(values.len(), 3);
+            assert!(values.iter().all(|value| value.is_nan()));
+        }
+        other => panic!("Unexpected cell {:?}", other),
+    }
+    assert_eq!(second.get_by_name("c64"), Some(Cell::Complex64(&[])));
+}
+
+#[test]
+fn rejects_invalid_cells() {
+    let cases = [
+        (
+            r#"<FIELD name="n" datatype="int"/>"#,
+            "<TR><TD>1.5</TD></TR>",
+        ),
+        (
+            r#"<FIELD name="n" datatype="short"/>"#,
+            "<TR><TD>70000</TD></TR>",
+        ),
+        (
+            r#"<FIELD name="n" datatype="int" arraysize="3"/>"#,
+            "<TR><TD>1 2</TD></TR>",
+        ),
+        (
+            r#"<FIELD name="c" datatype="floatComplex"/>"#,
+            "<TR><TD>1</TD></TR>",
+        ),
+        (
+            r#"<FIELD name="a" datatype="int"/><FIELD name="b" datatype="int"/>"#,
+            "<TR><TD>1</TD></TR>",
+        ),
+    ];
+    for (fields, rows) in &cases {
+        match VOTable::parse(votable(fields, rows).as_bytes()) {
+            Err(Error::CannotParse { .. }) => (),
+            other => panic!("Unexpected result {:?} for {}", other, rows),
+        }
+    }
+}
+
+#[test]
+fn warns_about_long_arrays() {
+    let xml = votable(
+        r#"<FIELD name="s" datatype="char" arraysize="3*"/>"#,
+        "<TR><TD>abcdef</TD></TR>",
+    );
+    // Accepted without warnings in strict mode
+    let strict = VOTable::parse(xml.as_bytes()).unwrap();
+    assert!(strict.warnings().is_empty());
+    assert_eq!(strict.tables().next().unwrap().len(), 1);
+
+    let votable = VOTable::parse_with_options(xml.as_bytes(), ParseOptions::lenient()).unwrap();
+    assert_eq!(
+        votable.warnings(),
+        &[Warning::ArrayTooLong {
+            field: Some("s".to_owned()),
+            max: 3,
+            len: 6,
+        }]
+    );
+    let table = votable.tables().next().unwrap();
+    let row = table.rows().unwrap().next().unwrap();
+    assert_eq!(row.get_by_name("s"), Some(Cell::Character("abcdef")));
+}
+
+#[test]
+fn rejects_unsupported_serializations() {
+    for serialization in &["FITS", "BINARY2"] {
+        let xml = format!(
+            r#"<VOTABLE version="1.3"><RESOURCE><TABLE>
+                <FIELD name="n" datatype="int"/>
+                <DATA><{0}><STREAM href="data"/></{0}></DATA>
+            </TABLE></RESOURCE></VOTABLE>"#,
+            serialization
+        );
+        match VOTable::parse(xml.as_bytes()) {
+            Err(Error::CannotParse { got, target }) => {
+                assert_eq!(target, "DATA");
+                assert!(got.contains(serialization));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Serialization of tables built in memory.
+
+extern crate vo_table;
+
+use vo_table::{Cell, DataType, Error, Field, Table, VOTable};
+
+fn round_trip(table: Table) -> VOTable {
+    let mut xml = Vec::new();
+    VOTable::from(table).write(&mut xml).unwrap();
+    VOTable::parse(&xml[..]).unwrap()
+}
+
+fn int16_table(values: &[Option<i16>]) -> Table {
+    let mut table = Table::new(vec![Field::new("n", DataType::Integer16)]);
+    for value in values {
+        table.push_row(&[Cell::Integer16(&[*value])]).unwrap();
+    }
+    table
+}
+
+#[test]
+fn null_value_does_not_collide_with_values() {
+    let values = [
+        None,
+        Some(i16::MIN),
+        Some(i16::MAX),
+        Some(i16::MIN + 1),
+        Some(0),
+    ];
+    let votable = round_trip(int16_table(&values));
+    let table = votable.tables().next().unwrap();
+    let read: Vec<_> = table
+        .rows()
+        .unwrap()
+        .map(|row| match row.get_by_name("n") {
+            Some(Cell::Integer16(&[value])) => value,
+            other => panic!("Unexpected cell {:?}", other),
+        })
+        .collect();
+    assert_eq!(read, values);
+}
+
+#[test]
+fn fails_without_free_null_value() {
+    let values: Vec<_> = (i16::MIN..=i16::MAX).map(Some).chain(Some(None)).collect();
+    let mut xml = Vec::new();
+    match VOTable::from(int16_table(&values)).write(&mut xml) {
+        Err(Error::NoNullValue { field }) => assert_eq!(field.as_deref(), Some("n")),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
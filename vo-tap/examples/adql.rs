@@ -0,0 +1,22 @@
+use std::env;
+
+use vo_tap::TapService;
+
+fn main() {
+    let query = env::args().nth(1).unwrap_or_else(|| {
+        "SELECT TOP 5 main_id, ra, dec FROM basic WHERE otype = 'QSO' ORDER BY ra".to_owned()
+    });
+    let query = TapService::SIMBAD.create_query(query).with_maxrec(100);
+
+    match query.execute_sync() {
+        Err(e) => eprintln!("Error: {}", e),
+        Ok(results) => {
+            for (i, row) in results.rows().enumerate() {
+                println!("{}. {:?}", i, row);
+            }
+            if results.is_overflow() {
+                println!("(truncated to {} rows)", results.len());
+            }
+        }
+    };
+}
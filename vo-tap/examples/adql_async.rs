@@ -0,0 +1,36 @@
+use std::env;
+use std::time::Duration;
+
+use vo_tap::{Phase, TapService};
+
+fn main() {
+    let query = env::args().nth(1).unwrap_or_else(|| {
+        "SELECT otype, COUNT(*) AS n FROM basic GROUP BY otype ORDER BY n DESC".to_owned()
+    });
+    let job = match TapService::SIMBAD.create_query(query).submit_sync() {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    println!("Submitted {}", job.job().url());
+
+    match job.wait_sync(Duration::from_secs(600)) {
+        Ok(details) if details.phase() == Phase::Completed || details.phase() == Phase::Error => {
+            match job.results_sync() {
+                Ok(results) => {
+                    for (i, row) in results.rows().enumerate() {
+                        println!("{}. {:?}", i, row);
+                    }
+                }
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        Ok(details) => eprintln!("Still {} after 10 minutes", details.phase()),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+    if let Err(e) = job.job().delete_sync() {
+        eprintln!("Could not delete the job: {}", e);
+    }
+}
@@ -0,0 +1,45 @@
+use std::env;
+use std::error::Error;
+
+use vo_sia::Coord;
+use vo_tap::adql::{col, distance, float, point, within, Order, Pos, Query};
+use vo_tap::TapService;
+
+/// Find the brightest stars of Gaia DR3 around a position, e.g.
+/// "10h44m06.6s -59d41m04s", within a radius in degrees.
+fn main() {
+    let mut args = env::args().skip(1);
+    let coord = args
+        .next()
+        .unwrap_or_else(|| "10h44m06.6s -59d41m04s".to_owned());
+    let radius = args.next().map_or(Ok(0.1), |radius| radius.parse());
+    let (coord, radius) = match (coord.parse::<Coord>(), radius) {
+        (Ok(coord), Ok(radius)) => (coord, radius),
+        _ => return eprintln!("Usage: cone [POSITION] [RADIUS]"),
+    };
+    let query = match cone_query(coord, radius) {
+        Ok(query) => query,
+        Err(e) => return eprintln!("Error: {}", e),
+    };
+    println!("{}\n", query);
+
+    match TapService::GAVO.create_query(query).execute_sync() {
+        Err(e) => eprintln!("Error: {}", e),
+        Ok(results) => {
+            for row in results.rows() {
+                println!("{:?}", row);
+            }
+        }
+    }
+}
+
+fn cone_query(coord: Coord, radius: f64) -> Result<Query, Box<dyn Error>> {
+    let pos = Pos::cone(coord, radius)?;
+    let center = point(float(coord.ra())?, float(coord.dec())?);
+    Ok(Query::new("gaia.dr3lite")
+        .with_top(20)
+        .with_columns(vec![col("source_id"), col("phot_g_mean_mag")])
+        .with_column_as(distance(point(col("ra"), col("dec")), center), "dist")
+        .with_where(within(col("ra"), col("dec"), &pos)?)
+        .with_order_by(col("phot_g_mean_mag"), Order::Asc))
+}
@@ -0,0 +1,32 @@
+use std::env;
+
+use vo_tap::TapService;
+
+/// List the tables of a schema of GAVO, "ivoa" by default, with their
+/// principal columns.
+fn main() {
+    let schema = env::args().nth(1).unwrap_or_else(|| "ivoa".to_owned());
+    let tables = match TapService::GAVO.tables_sync() {
+        Ok(tables) => tables,
+        Err(e) => return eprintln!("Error: {}", e),
+    };
+    let schema = match tables.schema(&schema) {
+        Some(schema) => schema,
+        None => return eprintln!("No schema {}", schema),
+    };
+    for table in schema.tables() {
+        println!("{}: {}", table.name(), table.description().unwrap_or(""));
+        for column in table
+            .columns()
+            .iter()
+            .filter(|column| column.is_principal())
+        {
+            println!(
+                "    {} ({}) [{}]",
+                column.name(),
+                column.datatype().unwrap_or("?"),
+                column.unit().unwrap_or("")
+            );
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use vo_table::{Cell, DataType, Field, Table};
+use vo_tap::TapService;
+
+/// Find the SIMBAD objects within 10 arcseconds of local positions.
+fn main() {
+    let mut positions = Table::new(vec![
+        Field::new("label", DataType::Character).with_arraysize(vo_table::ArraySize::Unbounded),
+        Field::new("ra", DataType::Float64).with_unit("deg"),
+        Field::new("dec", DataType::Float64).with_unit("deg"),
+    ]);
+    for (label, ra, dec) in &[("M31", 10.6847, 41.2690), ("M42", 83.8221, -5.3911)] {
+        positions
+            .push_row(&[
+                Cell::Character(label),
+                Cell::Float64(&[*ra]),
+                Cell::Float64(&[*dec]),
+            ])
+            .unwrap();
+    }
+
+    let query = TapService::SIMBAD
+        .create_query(
+            "SELECT p.label, b.main_id, b.ra, b.dec \
+             FROM basic AS b JOIN TAP_UPLOAD.positions AS p \
+             ON 1 = CONTAINS(POINT('ICRS', b.ra, b.dec), CIRCLE('ICRS', p.ra, p.dec, 10.0 / 3600))",
+        )
+        .with_table_upload("positions", positions)
+        .with_maxrec(100);
+
+    match query.execute_sync() {
+        Err(e) => eprintln!("Error: {}", e),
+        Ok(results) => {
+            for (i, row) in results.rows().enumerate() {
+                println!("{}. {:?}", i, row);
+            }
+        }
+    }
+}
@@ -0,0 +1,1283 @@
+//! Queries in ADQL 2.1, built as a syntax tree rather than by concatenating
+//! strings.
+//!
+//! Identifiers are quoted when they are not regular identifiers or are
+//! reserved words, and string literals are escaped, so that names and values
+//! coming from users cannot change the structure of a query. Queries are
+//! printed on several lines, one per clause:
+//!
+//! ```
+//! # extern crate vo_tap;
+//! # fn main() {
+//! use vo_tap::adql::{col, lit, Order, Query};
+//!
+//! let query = Query::new("gaia.dr3lite")
+//!     .with_top(10)
+//!     .with_columns(vec![col("source_id"), col("ra"), col("dec")])
+//!     .with_where(col("phot_g_mean_mag").lt(12))
+//!     .with_where(col("designation").like(lit("Gaia DR3 '4%")))
+//!     .with_order_by(col("phot_g_mean_mag"), Order::Desc);
+//! assert_eq!(
+//!     query.to_string(),
+//!     "SELECT TOP 10 source_id, ra, dec\n\
+//!      FROM gaia.dr3lite\n\
+//!      WHERE phot_g_mean_mag < 12\n  \
+//!        AND designation LIKE 'Gaia DR3 ''4%'\n\
+//!      ORDER BY phot_g_mean_mag DESC"
+//! );
+//! # }
+//! ```
+//!
+//! Regions of the sky given as a [`Pos`](../../vo_sia/enum.Pos.html) convert
+//! to ADQL geometries, and [`within`](fn.within.html) selects the positions
+//! inside them.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops;
+
+use crate::Error;
+
+pub use vo_sia::Pos;
+
+/// Reserved words of SQL, which ADQL identifiers must not be unless quoted,
+/// separated by spaces.
+const SQL_RESERVED_WORDS: &str = "\
+    ABSOLUTE ACTION ADD ALL ALLOCATE ALTER AND ANY ARE AS ASC ASSERTION AT AUTHORIZATION AVG \
+    BEGIN BETWEEN BIT BIT_LENGTH BOTH BY CASCADE CASCADED CASE CAST CATALOG CHAR CHARACTER \
+    CHAR_LENGTH CHARACTER_LENGTH CHECK CLOSE COALESCE COLLATE COLLATION COLUMN COMMIT CONNECT \
+    CONNECTION CONSTRAINT CONSTRAINTS CONTINUE CONVERT CORRESPONDING COUNT CREATE CROSS \
+    CURRENT CURRENT_DATE CURRENT_TIME CURRENT_TIMESTAMP CURRENT_USER CURSOR DATE DAY \
+    DEALLOCATE DECIMAL DECLARE DEFAULT DEFERRABLE DEFERRED DELETE DESC DESCRIBE DESCRIPTOR \
+    DIAGNOSTICS DISCONNECT DISTINCT DOMAIN DOUBLE DROP ELSE END ESCAPE EXCEPT EXCEPTION EXEC \
+    EXECUTE EXISTS EXTERNAL EXTRACT FALSE FETCH FIRST FLOAT FOR FOREIGN FOUND FROM FULL GET \
+    GLOBAL GO GOTO GRANT GROUP HAVING HOUR IDENTITY IMMEDIATE IN INDICATOR INITIALLY INNER \
+    INPUT INSENSITIVE INSERT INT INTEGER INTERSECT INTERVAL INTO IS ISOLATION JOIN KEY \
+    LANGUAGE LAST LEADING LEFT LEVEL LIKE LOCAL LOWER MATCH MAX MIN MINUTE MODULE MONTH NAMES \
+    NATIONAL NATURAL NCHAR NEXT NO NOT NULL NULLIF NUMERIC OCTET_LENGTH OF ON ONLY OPEN OPTION \
+    OR ORDER OUTER OUTPUT OVERLAPS PAD PARTIAL POSITION PRECISION PREPARE PRESERVE PRIMARY \
+    PRIOR PRIVILEGES PROCEDURE PUBLIC READ REAL REFERENCES RELATIVE RESTRICT REVOKE RIGHT \
+    ROLLBACK ROWS SCHEMA SCROLL SECOND SECTION SELECT SESSION SESSION_USER SET SIZE SMALLINT \
+    SOME SPACE SQL SQLCODE SQLERROR SQLSTATE SUBSTRING SUM SYSTEM_USER TABLE TEMPORARY THEN \
+    TIME TIMESTAMP TIMEZONE_HOUR TIMEZONE_MINUTE TO TRAILING TRANSACTION TRANSLATE TRANSLATION \
+    TRIM TRUE UNION UNIQUE UNKNOWN UPDATE UPPER USAGE USER USING VALUE VALUES VARCHAR VARYING \
+    VIEW WHEN WHENEVER WHERE WITH WORK WRITE YEAR ZONE";
+
+/// Reserved words added by ADQL, separated by spaces.
+const ADQL_RESERVED_WORDS: &str = "\
+    ABS ACOS AREA ASIN ATAN ATAN2 BIT_AND BIT_NOT BIT_OR BIT_XOR BOX CENTROID CEILING CIRCLE \
+    CONTAINS COORD1 COORD2 COORDSYS COS COT DEGREES DISTANCE EXP FLOOR ILIKE INTERSECTS \
+    IN_UNIT LOG LOG10 MOD OFFSET PI POINT POLYGON POWER RADIANS REGION RAND ROUND SIN SQRT TAN \
+    TOP TRUNCATE";
+
+/// Coordinate system given to geometries. ADQL 2.1 deprecates it, but
+/// services implementing ADQL 2.0 require it.
+const COORDSYS: &str = "ICRS";
+
+/// A SELECT query.
+///
+/// All columns are selected unless some are given. Successive conditions
+/// given with [`with_where`](#method.with_where) must all be true.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    distinct: bool,
+    top: Option<u64>,
+    columns: Vec<SelectItem>,
+    from: TableRef,
+    joins: Vec<Join>,
+    condition: Option<Expr>,
+    order_by: Vec<(Expr, Order)>,
+    offset: Option<u64>,
+}
+
+/// A selected expression, with its name in the results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectItem {
+    pub expr: Expr,
+    pub alias: Option<Identifier>,
+}
+
+/// A table of the FROM clause, with its alias in the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRef {
+    pub name: TableName,
+    pub alias: Option<Identifier>,
+}
+
+/// A table joined to the tables of the FROM clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: TableRef,
+    pub condition: JoinCondition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinCondition {
+    On(Expr),
+    /// Columns of the same names in both tables are equal.
+    Using(Vec<Identifier>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// Name of a schema, table or column, quoted if needed.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::adql::Identifier;
+/// # fn main() {
+/// assert_eq!(Identifier::new("ra").to_string(), "ra");
+/// assert_eq!(Identifier::new("size").to_string(), "\"size\"");
+/// assert_eq!(Identifier::new("B-V").to_string(), "\"B-V\"");
+/// assert_eq!(Identifier::new("a\"b").to_string(), "\"a\"\"b\"");
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identifier(String);
+
+/// Name of a table, possibly qualified by its schema.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableName(Vec<Identifier>);
+
+/// A column, possibly qualified by its table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnRef {
+    pub table: Option<TableName>,
+    pub column: Identifier,
+}
+
+/// Value expression or condition.
+///
+/// Expressions are combined with the methods of comparison and the
+/// arithmetic operators, and printed with the parentheses needed to keep
+/// their structure:
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::adql::{col, float};
+/// # fn main() {
+/// let color = col("bp") - col("rp");
+/// let condition = (color.clone() * 2)
+///     .gt(float(1.5).unwrap())
+///     .or(color.is_null())
+///     .and(col("parallax").gt(0));
+/// assert_eq!(
+///     condition.to_string(),
+///     "((bp - rp) * 2 > 1.5 OR bp - rp IS NULL) AND parallax > 0"
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(ColumnRef),
+    Literal(Literal),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    In {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    Function(Function, Vec<Expr>),
+}
+
+/// Constant value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Integer(i64),
+    Float(Float),
+}
+
+/// Finite floating-point number, since ADQL has no literal for infinities
+/// and NaN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Float(f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    NotLike,
+    /// Case-insensitive LIKE, new in ADQL 2.1.
+    ILike,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Concat,
+}
+
+/// Geometric functions of ADQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Point,
+    Circle,
+    Polygon,
+    Contains,
+    Intersects,
+    Distance,
+}
+
+impl Query {
+    /// Query selecting from `table`, e.g. "gaia.dr3lite".
+    pub fn new<T: Into<TableRef>>(table: T) -> Self {
+        Query {
+            distinct: false,
+            top: None,
+            columns: vec![],
+            from: table.into(),
+            joins: vec![],
+            condition: None,
+            order_by: vec![],
+            offset: None,
+        }
+    }
+
+    /// Remove duplicate rows from the results.
+    pub fn with_distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// Return at most `top` rows.
+    pub fn with_top(mut self, top: u64) -> Self {
+        self.top = Some(top);
+        self
+    }
+
+    /// Skip the first `offset` rows, new in ADQL 2.1.
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_column<E: Into<Expr>>(mut self, expr: E) -> Self {
+        self.columns.push(SelectItem {
+            expr: expr.into(),
+            alias: None,
+        });
+        self
+    }
+
+    /// Select `expr`, named `alias` in the results.
+    pub fn with_column_as<E, A>(mut self, expr: E, alias: A) -> Self
+    where
+        E: Into<Expr>,
+        A: Into<Identifier>,
+    {
+        self.columns.push(SelectItem {
+            expr: expr.into(),
+            alias: Some(alias.into()),
+        });
+        self
+    }
+
+    pub fn with_columns<I, E>(self, exprs: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<Expr>,
+    {
+        exprs
+            .into_iter()
+            .fold(self, |query, expr| query.with_column(expr))
+    }
+
+    pub fn with_join(mut self, join: Join) -> Self {
+        self.joins.push(join);
+        self
+    }
+
+    /// Add a condition on the rows, which must be true along with the
+    /// previous ones.
+    pub fn with_where(mut self, condition: Expr) -> Self {
+        self.condition = Some(match self.condition.take() {
+            Some(previous) => previous.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    /// Sort the rows by `expr`, after the previous sort keys.
+    pub fn with_order_by<E: Into<Expr>>(mut self, expr: E, order: Order) -> Self {
+        self.order_by.push((expr.into(), order));
+        self
+    }
+
+    pub fn is_distinct(&self) -> bool {
+        self.distinct
+    }
+
+    pub fn top(&self) -> Option<u64> {
+        self.top
+    }
+
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Selected expressions, empty if all columns are selected.
+    pub fn columns(&self) -> &[SelectItem] {
+        &self.columns
+    }
+
+    pub fn from(&self) -> &TableRef {
+        &self.from
+    }
+
+    pub fn joins(&self) -> &[Join] {
+        &self.joins
+    }
+
+    /// Conditions of the WHERE clause, joined with AND.
+    pub fn condition(&self) -> Option<&Expr> {
+        self.condition.as_ref()
+    }
+
+    pub fn order_by(&self) -> &[(Expr, Order)] {
+        &self.order_by
+    }
+}
+
+/// The query in ADQL, e.g. to
+/// [create a TAP query](../struct.TapService.html#method.create_query).
+impl From<Query> for String {
+    fn from(query: Query) -> String {
+        query.to_string()
+    }
+}
+
+impl From<&Query> for String {
+    fn from(query: &Query) -> String {
+        query.to_string()
+    }
+}
+
+impl TableRef {
+    pub fn new<T: Into<TableName>>(name: T) -> Self {
+        TableRef {
+            name: name.into(),
+            alias: None,
+        }
+    }
+
+    /// Name by which the table is referred to in the query.
+    pub fn with_alias<A: Into<Identifier>>(mut self, alias: A) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+}
+
+impl<T: Into<TableName>> From<T> for TableRef {
+    fn from(name: T) -> Self {
+        TableRef::new(name)
+    }
+}
+
+impl Join {
+    pub fn new<T: Into<TableRef>>(kind: JoinKind, table: T, condition: JoinCondition) -> Self {
+        Join {
+            kind,
+            table: table.into(),
+            condition,
+        }
+    }
+
+    /// Inner join of `table` on the condition `on`.
+    ///
+    /// ```
+    /// # extern crate vo_tap;
+    /// # use vo_tap::adql::{col, Join, Query, TableRef};
+    /// # fn main() {
+    /// let query = Query::new(TableRef::new("gaia.dr3lite").with_alias("g"))
+    ///     .with_join(Join::inner(
+    ///         TableRef::new("TAP_UPLOAD.mine").with_alias("m"),
+    ///         col("g.source_id").eq(col("m.id")),
+    ///     ));
+    /// assert_eq!(
+    ///     query.to_string(),
+    ///     "SELECT *\n\
+    ///      FROM gaia.dr3lite AS g\n\
+    ///      JOIN TAP_UPLOAD.mine AS m ON g.source_id = m.id"
+    /// );
+    /// # }
+    /// ```
+    pub fn inner<T: Into<TableRef>>(table: T, on: Expr) -> Self {
+        Join::new(JoinKind::Inner, table, JoinCondition::On(on))
+    }
+
+    /// Left outer join of `table` on the condition `on`.
+    pub fn left<T: Into<TableRef>>(table: T, on: Expr) -> Self {
+        Join::new(JoinKind::Left, table, JoinCondition::On(on))
+    }
+}
+
+impl Identifier {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Identifier(name.into())
+    }
+
+    /// Name, unquoted.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the name can be written without quotes: names starting
+    /// with a letter, followed by letters, digits and underscores, which are
+    /// not reserved words.
+    pub fn is_regular(&self) -> bool {
+        let mut chars = self.0.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !SQL_RESERVED_WORDS
+                .split(' ')
+                .chain(ADQL_RESERVED_WORDS.split(' '))
+                .any(|word| word.eq_ignore_ascii_case(&self.0))
+    }
+}
+
+impl From<&str> for Identifier {
+    fn from(name: &str) -> Self {
+        Identifier::new(name)
+    }
+}
+
+impl From<String> for Identifier {
+    fn from(name: String) -> Self {
+        Identifier::new(name)
+    }
+}
+
+impl TableName {
+    /// Name of `table` in `schema`.
+    pub fn qualified<S, T>(schema: S, table: T) -> Self
+    where
+        S: Into<Identifier>,
+        T: Into<Identifier>,
+    {
+        TableName(vec![schema.into(), table.into()])
+    }
+
+    /// Parts of the name, e.g. the schema and the table.
+    pub fn parts(&self) -> &[Identifier] {
+        &self.0
+    }
+}
+
+/// Name split at its dots, e.g. "gaia.dr3lite". Use
+/// [`qualified`](#method.qualified) for names containing dots.
+impl From<&str> for TableName {
+    fn from(name: &str) -> Self {
+        TableName(name.split('.').map(Identifier::from).collect())
+    }
+}
+
+impl From<Identifier> for TableName {
+    fn from(name: Identifier) -> Self {
+        TableName(vec![name])
+    }
+}
+
+impl ColumnRef {
+    pub fn new<C: Into<Identifier>>(table: Option<TableName>, column: C) -> Self {
+        ColumnRef {
+            table,
+            column: column.into(),
+        }
+    }
+}
+
+/// Column named `name`, qualified by the table before its last dot if any,
+/// e.g. "ra" or "g.ra".
+///
+/// Use [`ColumnRef::new`](struct.ColumnRef.html#method.new) for names
+/// containing dots.
+pub fn col(name: &str) -> Expr {
+    let column = match name.rsplit_once('.') {
+        Some((table, column)) => ColumnRef::new(Some(table.into()), column),
+        None => ColumnRef::new(None, name),
+    };
+    Expr::Column(column)
+}
+
+/// Constant value, e.g. a string.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::adql::lit;
+/// # fn main() {
+/// assert_eq!(lit("O'Brien").to_string(), "'O''Brien'");
+/// assert_eq!(lit(-2).to_string(), "-2");
+/// # }
+/// ```
+pub fn lit<L: Into<Literal>>(value: L) -> Expr {
+    Expr::Literal(value.into())
+}
+
+/// Floating-point constant. Fails if `value` is not finite.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::adql::float;
+/// # fn main() {
+/// assert_eq!(float(1e-7).unwrap().to_string(), "1e-7");
+/// assert!(float(f64::NAN).is_err());
+/// # }
+/// ```
+pub fn float(value: f64) -> Result<Expr, Error> {
+    Float::new(value).map(|value| lit(Literal::Float(value)))
+}
+
+/// Point at (`ra`, `dec`), in degrees.
+pub fn point<R: Into<Expr>, D: Into<Expr>>(ra: R, dec: D) -> Expr {
+    Expr::Function(Function::Point, vec![lit(COORDSYS), ra.into(), dec.into()])
+}
+
+/// Circle of `radius` around (`ra`, `dec`), in degrees.
+pub fn circle<R, D, S>(ra: R, dec: D, radius: S) -> Expr
+where
+    R: Into<Expr>,
+    D: Into<Expr>,
+    S: Into<Expr>,
+{
+    Expr::Function(
+        Function::Circle,
+        vec![lit(COORDSYS), ra.into(), dec.into(), radius.into()],
+    )
+}
+
+/// Polygon with the given (`ra`, `dec`) vertices, in degrees.
+pub fn polygon<I, R, D>(vertices: I) -> Expr
+where
+    I: IntoIterator<Item = (R, D)>,
+    R: Into<Expr>,
+    D: Into<Expr>,
+{
+    let mut args = vec![lit(COORDSYS)];
+    for (ra, dec) in vertices {
+        args.push(ra.into());
+        args.push(dec.into());
+    }
+    Expr::Function(Function::Polygon, args)
+}
+
+/// 1 if the geometry `inner` is inside `outer`, 0 otherwise.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::adql::{circle, col, contains, float, point};
+/// # fn main() {
+/// let region = circle(float(10.68).unwrap(), float(41.27).unwrap(), float(0.5).unwrap());
+/// let condition = contains(point(col("ra"), col("dec")), region).eq(1);
+/// assert_eq!(
+///     condition.to_string(),
+///     "CONTAINS(POINT('ICRS', ra, dec), CIRCLE('ICRS', 10.68, 41.27, 0.5)) = 1"
+/// );
+/// # }
+/// ```
+pub fn contains<I: Into<Expr>, O: Into<Expr>>(inner: I, outer: O) -> Expr {
+    Expr::Function(Function::Contains, vec![inner.into(), outer.into()])
+}
+
+/// 1 if the geometries `a` and `b` overlap, 0 otherwise.
+pub fn intersects<A: Into<Expr>, B: Into<Expr>>(a: A, b: B) -> Expr {
+    Expr::Function(Function::Intersects, vec![a.into(), b.into()])
+}
+
+/// Angular distance between the points `a` and `b`, in degrees.
+pub fn distance<A: Into<Expr>, B: Into<Expr>>(a: A, b: B) -> Expr {
+    Expr::Function(Function::Distance, vec![a.into(), b.into()])
+}
+
+/// Condition true if (`ra`, `dec`) is in the region `pos`.
+///
+/// Ranges, which have no ADQL geometry, are conditions on the bounds of
+/// `ra` and `dec`. Fails if a coordinate of the region is NaN.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::adql::{col, within, Pos};
+/// # fn main() {
+/// let cone = Pos::circle(10.68, 41.27, 0.5).unwrap();
+/// assert_eq!(
+///     within(col("ra"), col("dec"), &cone).unwrap().to_string(),
+///     "CONTAINS(POINT('ICRS', ra, dec), CIRCLE('ICRS', 10.68, 41.27, 0.5)) = 1"
+/// );
+///
+/// let cap = Pos::range((0.0, 360.0), (80.0, f64::INFINITY)).unwrap();
+/// assert_eq!(
+///     within(col("ra"), col("dec"), &cap).unwrap().to_string(),
+///     "ra BETWEEN 0.0 AND 360.0 AND dec >= 80.0"
+/// );
+/// # }
+/// ```
+pub fn within<R: Into<Expr>, D: Into<Expr>>(ra: R, dec: D, pos: &Pos) -> Result<Expr, Error> {
+    let (ra, dec) = (ra.into(), dec.into());
+    match *pos {
+        Pos::Range {
+            longitude1,
+            longitude2,
+            latitude1,
+            latitude2,
+        } => Ok(vec![
+            bounded(ra, longitude1, longitude2)?,
+            bounded(dec, latitude1, latitude2)?,
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(Expr::and)
+        .unwrap_or_else(|| lit(1).eq(1))),
+        _ => Ok(contains(point(ra, dec), Expr::try_from(pos)?).eq(1)),
+    }
+}
+
+/// Condition on the finite bounds of `expr`, if any.
+fn bounded(expr: Expr, min: f64, max: f64) -> Result<Option<Expr>, Error> {
+    let bound = |value: f64, unbounded: f64| {
+        if value == unbounded {
+            Ok(None)
+        } else {
+            float(value).map(Some)
+        }
+    };
+    Ok(
+        match (bound(min, f64::NEG_INFINITY)?, bound(max, f64::INFINITY)?) {
+            (Some(min), Some(max)) => Some(expr.between(min, max)),
+            (Some(min), None) => Some(expr.ge(min)),
+            (None, Some(max)) => Some(expr.le(max)),
+            (None, None) => None,
+        },
+    )
+}
+
+/// Circles and polygons, as CIRCLE and POLYGON geometries. Fails for
+/// ranges, and for coordinates which are NaN: use
+/// [`within`](fn.within.html) to select the positions in a range.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use std::convert::TryFrom;
+/// # use vo_tap::adql::{Expr, Pos};
+/// # fn main() {
+/// let polygon = Pos::polygon(vec![(12.0, 34.0), (14.0, 35.0), (14.0, 36.0)]).unwrap();
+/// assert_eq!(
+///     Expr::try_from(&polygon).unwrap().to_string(),
+///     "POLYGON('ICRS', 12.0, 34.0, 14.0, 35.0, 14.0, 36.0)"
+/// );
+///
+/// let range = Pos::range((0.0, 10.0), (0.0, 10.0)).unwrap();
+/// assert!(Expr::try_from(&range).is_err());
+/// # }
+/// ```
+impl TryFrom<&Pos> for Expr {
+    type Error = Error;
+
+    fn try_from(pos: &Pos) -> Result<Self, Error> {
+        match *pos {
+            Pos::Circle {
+                longitude,
+                latitude,
+                radius,
+            } => Ok(circle(float(longitude)?, float(latitude)?, float(radius)?)),
+            Pos::Range { .. } => Err(Error::UnsupportedRegion(pos.clone())),
+            Pos::Polygon(ref vertices) => {
+                let vertices = vertices
+                    .vertices()
+                    .iter()
+                    .map(|&(ra, dec)| Ok((float(ra)?, float(dec)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(polygon(vertices))
+            }
+        }
+    }
+}
+
+impl TryFrom<Pos> for Expr {
+    type Error = Error;
+
+    fn try_from(pos: Pos) -> Result<Self, Error> {
+        Expr::try_from(&pos)
+    }
+}
+
+impl Expr {
+    pub fn and(self, other: Expr) -> Expr {
+        self.binary(BinaryOp::And, other)
+    }
+
+    pub fn or(self, other: Expr) -> Expr {
+        self.binary(BinaryOp::Or, other)
+    }
+
+    pub fn eq<E: Into<Expr>>(self, other: E) -> Expr {
+        self.binary(BinaryOp::Eq, other)
+    }
+
+    pub fn ne<E: Into<Expr>>(self, other: E) -> Expr {
+        self.binary(BinaryOp::Ne, other)
+    }
+
+    pub fn lt<E: Into<Expr>>(self, other: E) -> Expr {
+        self.binary(BinaryOp::Lt, other)
+    }
+
+    pub fn le<E: Into<Expr>>(self, other: E) -> Expr {
+        self.binary(BinaryOp::Le, other)
+    }
+
+    pub fn gt<E: Into<Expr>>(self, other: E) -> Expr {
+        self.binary(BinaryOp::Gt, other)
+    }
+
+    pub fn ge<E: Into<Expr>>(self, other: E) -> Expr {
+        self.binary(BinaryOp::Ge, other)
+    }
+
+    /// Match `pattern`, where `%` matches any characters and `_` any single
+    /// character.
+    pub fn like(self, pattern: Expr) -> Expr {
+        self.binary(BinaryOp::Like, pattern)
+    }
+
+    pub fn not_like(self, pattern: Expr) -> Expr {
+        self.binary(BinaryOp::NotLike, pattern)
+    }
+
+    /// Case-insensitive [`like`](#method.like).
+    pub fn ilike(self, pattern: Expr) -> Expr {
+        self.binary(BinaryOp::ILike, pattern)
+    }
+
+    /// Concatenation of strings.
+    pub fn concat(self, other: Expr) -> Expr {
+        self.binary(BinaryOp::Concat, other)
+    }
+
+    /// Between `low` and `high`, inclusive.
+    pub fn between<L: Into<Expr>, H: Into<Expr>>(self, low: L, high: H) -> Expr {
+        self.range(low, high, false)
+    }
+
+    pub fn not_between<L: Into<Expr>, H: Into<Expr>>(self, low: L, high: H) -> Expr {
+        self.range(low, high, true)
+    }
+
+    /// Equal to one of `values`. Always false if there is no value.
+    pub fn in_list<I, E>(self, values: I) -> Expr
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<Expr>,
+    {
+        self.list(values, false)
+    }
+
+    /// Different from all `values`. Always true if there is no value.
+    pub fn not_in_list<I, E>(self, values: I) -> Expr
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<Expr>,
+    {
+        self.list(values, true)
+    }
+
+    pub fn is_null(self) -> Expr {
+        Expr::IsNull {
+            expr: Box::new(self),
+            negated: false,
+        }
+    }
+
+    pub fn is_not_null(self) -> Expr {
+        Expr::IsNull {
+            expr: Box::new(self),
+            negated: true,
+        }
+    }
+
+    fn binary<E: Into<Expr>>(self, op: BinaryOp, other: E) -> Expr {
+        Expr::Binary(Box::new(self), op, Box::new(other.into()))
+    }
+
+    fn range<L: Into<Expr>, H: Into<Expr>>(self, low: L, high: H, negated: bool) -> Expr {
+        Expr::Between {
+            expr: Box::new(self),
+            low: Box::new(low.into()),
+            high: Box::new(high.into()),
+            negated,
+        }
+    }
+
+    /// ADQL has no empty lists, which are replaced by constant conditions.
+    fn list<I, E>(self, values: I, negated: bool) -> Expr
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<Expr>,
+    {
+        let list: Vec<_> = values.into_iter().map(Into::into).collect();
+        if list.is_empty() {
+            return if negated { lit(1).eq(1) } else { lit(1).ne(1) };
+        }
+        Expr::In {
+            expr: Box::new(self),
+            list,
+            negated,
+        }
+    }
+
+    /// Binding strength of the expression, parenthesized in the operands of
+    /// stronger operators.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(_, op, _) => op.precedence(),
+            Expr::Unary(UnaryOp::Not, _) => 3,
+            Expr::Between { .. } | Expr::In { .. } | Expr::IsNull { .. } => 4,
+            // Negative numbers are written with a sign, like negations
+            Expr::Unary(UnaryOp::Neg, _) => 7,
+            Expr::Literal(Literal::Integer(value)) if *value < 0 => 7,
+            Expr::Literal(Literal::Float(value)) if value.0.is_sign_negative() => 7,
+            _ => 8,
+        }
+    }
+}
+
+impl BinaryOp {
+    fn precedence(self) -> u8 {
+        use BinaryOp::*;
+        match self {
+            Or => 1,
+            And => 2,
+            Eq | Ne | Lt | Le | Gt | Ge | Like | NotLike | ILike => 4,
+            Add | Sub | Concat => 5,
+            Mul | Div => 6,
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        self.precedence() == 4
+    }
+
+    fn symbol(self) -> &'static str {
+        use BinaryOp::*;
+        match self {
+            Or => "OR",
+            And => "AND",
+            Eq => "=",
+            Ne => "<>",
+            Lt => "<",
+            Le => "<=",
+            Gt => ">",
+            Ge => ">=",
+            Like => "LIKE",
+            NotLike => "NOT LIKE",
+            ILike => "ILIKE",
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Concat => "||",
+        }
+    }
+}
+
+impl<E: Into<Expr>> ops::Add<E> for Expr {
+    type Output = Expr;
+
+    fn add(self, other: E) -> Expr {
+        self.binary(BinaryOp::Add, other)
+    }
+}
+
+impl<E: Into<Expr>> ops::Sub<E> for Expr {
+    type Output = Expr;
+
+    fn sub(self, other: E) -> Expr {
+        self.binary(BinaryOp::Sub, other)
+    }
+}
+
+impl<E: Into<Expr>> ops::Mul<E> for Expr {
+    type Output = Expr;
+
+    fn mul(self, other: E) -> Expr {
+        self.binary(BinaryOp::Mul, other)
+    }
+}
+
+impl<E: Into<Expr>> ops::Div<E> for Expr {
+    type Output = Expr;
+
+    fn div(self, other: E) -> Expr {
+        self.binary(BinaryOp::Div, other)
+    }
+}
+
+impl ops::Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::Unary(UnaryOp::Neg, Box::new(self))
+    }
+}
+
+impl ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Unary(UnaryOp::Not, Box::new(self))
+    }
+}
+
+impl From<ColumnRef> for Expr {
+    fn from(column: ColumnRef) -> Self {
+        Expr::Column(column)
+    }
+}
+
+impl From<Literal> for Expr {
+    fn from(literal: Literal) -> Self {
+        Expr::Literal(literal)
+    }
+}
+
+impl From<i32> for Expr {
+    fn from(value: i32) -> Self {
+        lit(value)
+    }
+}
+
+impl From<i64> for Expr {
+    fn from(value: i64) -> Self {
+        lit(value)
+    }
+}
+
+impl From<u32> for Expr {
+    fn from(value: u32) -> Self {
+        lit(value)
+    }
+}
+
+impl From<&str> for Literal {
+    fn from(value: &str) -> Self {
+        Literal::String(value.to_owned())
+    }
+}
+
+impl From<String> for Literal {
+    fn from(value: String) -> Self {
+        Literal::String(value)
+    }
+}
+
+impl From<i32> for Literal {
+    fn from(value: i32) -> Self {
+        Literal::Integer(value.into())
+    }
+}
+
+impl From<i64> for Literal {
+    fn from(value: i64) -> Self {
+        Literal::Integer(value)
+    }
+}
+
+impl From<u32> for Literal {
+    fn from(value: u32) -> Self {
+        Literal::Integer(value.into())
+    }
+}
+
+impl From<Float> for Literal {
+    fn from(value: Float) -> Self {
+        Literal::Float(value)
+    }
+}
+
+impl Float {
+    /// Fails if `value` is infinite or NaN.
+    pub fn new(value: f64) -> Result<Self, Error> {
+        if value.is_finite() {
+            Ok(Float(value))
+        } else {
+            Err(Error::InvalidNumber(value))
+        }
+    }
+
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for Float {
+    type Error = Error;
+
+    fn try_from(value: f64) -> Result<Self, Error> {
+        Float::new(value)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SELECT")?;
+        if self.distinct {
+            f.write_str(" DISTINCT")?;
+        }
+        if let Some(top) = self.top {
+            write!(f, " TOP {}", top)?;
+        }
+        if self.columns.is_empty() {
+            f.write_str(" *")?;
+        }
+        for (i, column) in self.columns.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { ", " })?;
+            write!(f, "{}", column.expr)?;
+            if let Some(alias) = &column.alias {
+                write!(f, " AS {}", alias)?;
+            }
+        }
+        write!(f, "\nFROM {}", self.from)?;
+        for join in &self.joins {
+            write!(f, "\n{}", join)?;
+        }
+        if let Some(condition) = &self.condition {
+            // One line per condition joined with AND
+            let mut conditions = vec![];
+            let mut rest = condition;
+            while let Expr::Binary(left, BinaryOp::And, right) = rest {
+                conditions.push(&**right);
+                rest = left;
+            }
+            conditions.push(rest);
+            for (i, condition) in conditions.iter().rev().enumerate() {
+                f.write_str(if i == 0 { "\nWHERE " } else { "\n  AND " })?;
+                write_operand(f, condition, BinaryOp::And.precedence() + 1)?;
+            }
+        }
+        for (i, (expr, order)) in self.order_by.iter().enumerate() {
+            f.write_str(if i == 0 { "\nORDER BY " } else { ", " })?;
+            write!(f, "{}", expr)?;
+            if *order == Order::Desc {
+                f.write_str(" DESC")?;
+            }
+        }
+        if let Some(offset) = self.offset {
+            write!(f, "\nOFFSET {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TableRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(alias) = &self.alias {
+            write!(f, " AS {}", alias)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Join {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            JoinKind::Inner => "JOIN",
+            JoinKind::Left => "LEFT OUTER JOIN",
+            JoinKind::Right => "RIGHT OUTER JOIN",
+            JoinKind::Full => "FULL OUTER JOIN",
+        };
+        write!(f, "{} {}", kind, self.table)?;
+        match &self.condition {
+            JoinCondition::On(condition) => write!(f, " ON {}", condition),
+            JoinCondition::Using(columns) => {
+                f.write_str(" USING (")?;
+                write_list(f, columns)?;
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_regular() {
+            f.write_str(&self.0)
+        } else {
+            write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+        }
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, part) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(table) = &self.table {
+            write!(f, "{}.", table)?;
+        }
+        write!(f, "{}", self.column)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::String(value) => write!(f, "'{}'", value.replace('\'', "''")),
+            Literal::Integer(value) => write!(f, "{}", value),
+            // Debug keeps the fractional part and uses exponents for large
+            // and small numbers
+            Literal::Float(value) => write!(f, "{:?}", value.0),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Function::Point => "POINT",
+            Function::Circle => "CIRCLE",
+            Function::Polygon => "POLYGON",
+            Function::Contains => "CONTAINS",
+            Function::Intersects => "INTERSECTS",
+            Function::Distance => "DISTANCE",
+        })
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precedence = self.precedence();
+        match self {
+            Expr::Column(column) => write!(f, "{}", column),
+            Expr::Literal(literal) => write!(f, "{}", literal),
+            Expr::Unary(UnaryOp::Not, expr) => {
+                f.write_str("NOT ")?;
+                write_operand(f, expr, precedence)
+            }
+            Expr::Unary(UnaryOp::Neg, expr) => {
+                // A sign followed by another would start a comment
+                f.write_str("-")?;
+                write_operand(f, expr, precedence + 1)
+            }
+            Expr::Binary(left, op, right) => {
+                let left_precedence = if op.is_comparison() {
+                    precedence + 1
+                } else {
+                    precedence
+                };
+                write_operand(f, left, left_precedence)?;
+                write!(f, " {} ", op.symbol())?;
+                let right = operand(right, precedence + 1);
+                if *op == BinaryOp::Sub && right.starts_with('-') {
+                    // Two minus signs would start a comment
+                    write!(f, "({})", right)
+                } else {
+                    f.write_str(&right)
+                }
+            }
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                write_operand(f, expr, precedence + 1)?;
+                f.write_str(if *negated {
+                    " NOT BETWEEN "
+                } else {
+                    " BETWEEN "
+                })?;
+                write_operand(f, low, precedence + 1)?;
+                f.write_str(" AND ")?;
+                write_operand(f, high, precedence + 1)
+            }
+            Expr::In {
+                expr,
+                list,
+                negated,
+            } => {
+                write_operand(f, expr, precedence + 1)?;
+                f.write_str(if *negated { " NOT IN (" } else { " IN (" })?;
+                write_list(f, list)?;
+                f.write_str(")")
+            }
+            Expr::IsNull { expr, negated } => {
+                write_operand(f, expr, precedence + 1)?;
+                f.write_str(if *negated { " IS NOT NULL" } else { " IS NULL" })
+            }
+            Expr::Function(function, args) => {
+                write!(f, "{}(", function)?;
+                write_list(f, args)?;
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter, expr: &Expr, precedence: u8) -> fmt::Result {
+    f.write_str(&operand(expr, precedence))
+}
+
+/// `expr`, in parentheses if it binds less than `precedence`.
+fn operand(expr: &Expr, precedence: u8) -> String {
+    if expr.precedence() < precedence {
+        format!("({})", expr)
+    } else {
+        expr.to_string()
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Client(vo_client::Error),
+    InvalidUri(http::uri::InvalidUri),
+    VOTable(vo_table::Error),
+    /// The name of a table uploaded with a query is invalid.
+    InvalidUpload(String),
+    Uws(vo_uws::Error),
+    /// An asynchronous query failed, with the message of the service.
+    JobFailed(String),
+    /// The results of an asynchronous query were requested before its
+    /// completion.
+    JobNotCompleted(vo_uws::Phase),
+    /// The VOSI description of the tables of a service is invalid.
+    InvalidTableSet(String),
+    /// The region has no ADQL geometry.
+    UnsupportedRegion(vo_sia::Pos),
+    /// ADQL has no literal for infinite numbers and NaN.
+    InvalidNumber(f64),
+}
+
+impl From<vo_client::Error> for Error {
+    fn from(e: vo_client::Error) -> Self {
+        Error::Client(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+        match self {
+            Client(e) => write!(f, "{}", e),
+            InvalidUri(e) => write!(f, "Invalid URL. {}", e),
+            VOTable(e) => write!(f, "VOTable error. {}", e),
+            InvalidUpload(name) => write!(
+                f,
+                "Invalid name '{}' for an uploaded table. Names must start with a letter, \
+                 followed by letters, digits or underscores.",
+                name
+            ),
+            Uws(e) => write!(f, "{}", e),
+            JobFailed(message) => write!(f, "Query failed. {}", message),
+            JobNotCompleted(phase) => write!(f, "Query not completed, in phase {}", phase),
+            InvalidTableSet(reason) => write!(f, "Invalid description of the tables. {}", reason),
+            UnsupportedRegion(pos) => write!(f, "No ADQL geometry for the region {}", pos),
+            InvalidNumber(value) => write!(f, "Invalid number {} in ADQL", value),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Client(e) => e.source(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Asynchronous queries, run as UWS jobs.
+
+use std::time::Duration;
+
+use vo_client::service::{self, Request};
+#[cfg(all(feature = "tokio", not(feature = "blocking")))]
+use vo_client::Runtime;
+use vo_uws::{Job, JobResult, Phase, UwsJob};
+
+use crate::{Error, TapResults};
+
+/// A query running as a job on a TAP service.
+///
+/// The job keeps running on the service, even after its results are
+/// fetched, until it is [deleted](struct.UwsJob.html#method.delete) or
+/// destroyed by the service.
+#[derive(Debug, Clone)]
+pub struct TapJob {
+    job: UwsJob,
+}
+
+impl TapJob {
+    /// Refer to a job created by a TAP service.
+    pub fn new(job: UwsJob) -> Self {
+        TapJob { job }
+    }
+
+    /// The UWS job, e.g. to abort or delete it.
+    pub fn job(&self) -> &UwsJob {
+        &self.job
+    }
+
+    pub fn into_job(self) -> UwsJob {
+        self.job
+    }
+
+    /// Wait until the query is over, for at most `timeout`.
+    ///
+    /// See [`UwsJob::wait`](struct.UwsJob.html#method.wait).
+    #[cfg(feature = "tokio")]
+    pub async fn wait(&self, timeout: Duration) -> Result<Job, Error> {
+        self.job.wait(timeout).await.map_err(Error::Uws)
+    }
+
+    /// Blocking version of [`wait`](#method.wait).
+    pub fn wait_sync(&self, timeout: Duration) -> Result<Job, Error> {
+        self.job.wait_sync(timeout).map_err(Error::Uws)
+    }
+
+    /// Fetch the results of the completed query.
+    ///
+    /// Fails with [`Error::JobFailed`](enum.Error.html) if the query failed,
+    /// or [`Error::JobNotCompleted`](enum.Error.html) if it is not over.
+    #[cfg(feature = "tokio")]
+    pub async fn results(&self) -> Result<TapResults, Error> {
+        let details = self.job.details().await.map_err(Error::Uws)?;
+        if details.phase() == Phase::Error {
+            let detail = self.job.error_detail().await.ok();
+            return Err(job_error(&details, detail));
+        }
+        let request = self.result_request(completed_result(&details)?);
+        let table = self.job.client().query(&request).await?;
+        Ok(TapResults::new(table))
+    }
+
+    /// Blocking version of [`results`](#method.results).
+    pub fn results_sync(&self) -> Result<TapResults, Error> {
+        let details = self.job.details_sync().map_err(Error::Uws)?;
+        if details.phase() == Phase::Error {
+            let detail = self.job.error_detail_sync().ok();
+            return Err(job_error(&details, detail));
+        }
+        let request = self.result_request(completed_result(&details)?);
+        let client = self.job.client();
+        #[cfg(feature = "blocking")]
+        let table = client.query_blocking(&request)?;
+        #[cfg(not(feature = "blocking"))]
+        let table = Runtime::shared()?.block_on(client.query(&request))??;
+        Ok(TapResults::new(table))
+    }
+
+    /// The request for the VOTable of `result`.
+    fn result_request(&self, result: &JobResult) -> Request {
+        Request::get(self.job.result_url(result))
+    }
+}
+
+/// The result of a completed job, named "result" by TAP services.
+fn completed_result(details: &Job) -> Result<&JobResult, Error> {
+    if details.phase() != Phase::Completed {
+        return Err(Error::JobNotCompleted(details.phase()));
+    }
+    details
+        .result("result")
+        .or_else(|| details.results().first())
+        .ok_or_else(|| {
+            Error::Uws(vo_uws::Error::InvalidDocument(
+                "Completed job without result".to_owned(),
+            ))
+        })
+}
+
+/// The error of a failed job, from the TAP error document at the `error`
+/// resource of the job, or else from its error summary.
+fn job_error(details: &Job, detail: Option<Vec<u8>>) -> Error {
+    let message = detail
+        .and_then(|body| service::error_message(&body))
+        .or_else(|| {
+            details
+                .error_summary()
+                .map(|summary| summary.message().to_owned())
+                .filter(|message| !message.is_empty())
+        })
+        .unwrap_or_else(|| "Unknown error".to_owned());
+    Error::JobFailed(message)
+}
@@ -0,0 +1,562 @@
+//! Client for the Table Access Protocol, version 1.1.
+//!
+//! Queries in ADQL are sent to the `/sync` endpoint of a service, which
+//! answers with a VOTable once the query is complete:
+//!
+//! ```no_run
+//! # extern crate vo_tap;
+//! # use vo_tap::TapService;
+//! # fn main() {
+//! let results = TapService::GAVO
+//!     .create_query("SELECT TOP 10 ra, dec, phot_g_mean_mag FROM gaia.dr3lite ORDER BY phot_g_mean_mag")
+//!     .execute_sync()
+//!     .unwrap();
+//! for row in results.rows() {
+//!     println!("{:?}", row.get_by_name("phot_g_mean_mag"));
+//! }
+//! # }
+//! ```
+//!
+//! Long queries can be [submitted](struct.TapQuery.html#method.submit) as
+//! jobs to the `/async` endpoint instead, which run on the service until
+//! their results are fetched.
+//!
+//! Queries can be [built](adql/index.html) from a syntax tree, which
+//! takes care of quoting names and escaping values.
+//!
+//! The schemas, tables and columns of a service are described by its
+//! [VOSI tables](struct.TapService.html#method.tables) or its
+//! [TAP_SCHEMA](struct.TapService.html#method.tap_schema).
+//!
+//! Queries can be executed asynchronously on a Tokio runtime, with the
+//! `tokio` feature enabled by default, or from blocking code with
+//! [`execute_sync`](struct.TapQuery.html#method.execute_sync). With the
+//! `blocking` feature, blocking functions send their requests without any
+//! asynchronous runtime.
+
+#[cfg(not(any(feature = "tokio", feature = "blocking")))]
+compile_error!("Either the `tokio` or the `blocking` feature of vo-tap must be enabled.");
+
+pub mod adql;
+mod err;
+mod job;
+mod schema;
+
+use std::sync::Arc;
+
+use vo_client::service::{self, Request};
+#[cfg(all(feature = "tokio", not(feature = "blocking")))]
+use vo_client::Runtime;
+use vo_client::{Multipart, ResponseHook};
+use vo_table::{Row, VOTable};
+use vo_uws::UwsService;
+
+pub use crate::err::Error;
+pub use crate::job::TapJob;
+pub use crate::schema::{ColumnMeta, ForeignKey, Schema, TableMeta, TableSet};
+pub use vo_client::{Error as ClientError, RawResponse, VoClient, VoClientBuilder};
+pub use vo_uws::{Job, JobRequest, Phase, UwsJob};
+
+/// Format of the results requested by default: VOTables in the BINARY
+/// serialization, which vo-table decodes.
+pub const DEFAULT_FORMAT: &str = "application/x-votable+xml;serialization=BINARY";
+
+/// Content type of the parameters of a query
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// Content type of the tables uploaded with a query
+const VOTABLE_CONTENT_TYPE: &str = "application/x-votable+xml";
+
+/// A TAP service, identified by its base URL.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TapService<U> {
+    url: U,
+}
+
+impl TapService<&'static str> {
+    pub const GAVO: TapService<&'static str> = TapService {
+        url: "http://dc.zah.uni-heidelberg.de/tap",
+    };
+    pub const SIMBAD: TapService<&'static str> = TapService {
+        url: "https://simbad.cds.unistra.fr/simbad/sim-tap",
+    };
+}
+
+impl<U> TapService<U>
+where
+    U: ToString,
+{
+    pub fn new(url: U) -> TapService<U> {
+        TapService { url }
+    }
+
+    pub fn map<F, V>(self, mut f: F) -> TapService<V>
+    where
+        F: FnMut(U) -> V,
+        V: ToString,
+    {
+        TapService { url: f(self.url) }
+    }
+
+    /// Create a query in ADQL.
+    pub fn create_query<'k, S: Into<String>>(&self, query: S) -> TapQuery<'k> {
+        TapQuery {
+            base_url: self.url.to_string().trim_end_matches('/').to_owned(),
+            query: query.into(),
+            lang: "ADQL".to_owned(),
+            maxrec: None,
+            format: None,
+            uploads: vec![],
+            table_uploads: vec![],
+            keywords: vec![],
+            response_hook: None,
+        }
+    }
+
+    /// URL of the VOSI description of the tables of the service.
+    pub fn tables_url(&self) -> String {
+        format!("{}/tables", self.url.to_string().trim_end_matches('/'))
+    }
+
+    /// Fetch the VOSI description of the tables of the service with a new
+    /// client.
+    #[cfg(feature = "tokio")]
+    pub async fn tables(&self) -> Result<TableSet, Error> {
+        self.tables_with(&VoClient::new()).await
+    }
+
+    /// Fetch the VOSI description of the tables of the service with
+    /// `client`.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn tables_with(&self, client: &VoClient) -> Result<TableSet, Error> {
+        let response = client.send(&Request::get(self.tables_url())).await?;
+        TableSet::parse_vosi(&response.body[..])
+    }
+
+    /// Blocking version of [`tables`](#method.tables).
+    pub fn tables_sync(&self) -> Result<TableSet, Error> {
+        self.tables_sync_with(&VoClient::new())
+    }
+
+    /// Blocking version of [`tables_with`](#method.tables_with).
+    pub fn tables_sync_with(&self, client: &VoClient) -> Result<TableSet, Error> {
+        #[cfg(feature = "blocking")]
+        {
+            let response = client.send_blocking(&Request::get(self.tables_url()))?;
+            TableSet::parse_vosi(&response.body[..])
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            Runtime::shared()?.block_on(self.tables_with(client))?
+        }
+    }
+
+    /// Query the tables of TAP_SCHEMA describing the tables of the service
+    /// with a new client.
+    ///
+    /// TAP_SCHEMA may describe the tables in more detail than VOSI, e.g. the
+    /// order of their columns.
+    #[cfg(feature = "tokio")]
+    pub async fn tap_schema(&self) -> Result<TableSet, Error> {
+        self.tap_schema_with(&VoClient::new()).await
+    }
+
+    /// Query the tables of TAP_SCHEMA describing the tables of the service
+    /// with `client`.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn tap_schema_with(&self, client: &VoClient) -> Result<TableSet, Error> {
+        let mut results = Vec::with_capacity(schema::TAP_SCHEMA_TABLES.len());
+        for table in &schema::TAP_SCHEMA_TABLES {
+            results.push(self.tap_schema_query(table).execute_with(client).await?);
+        }
+        Ok(TableSet::from_tap_schema(&results))
+    }
+
+    /// Blocking version of [`tap_schema`](#method.tap_schema).
+    pub fn tap_schema_sync(&self) -> Result<TableSet, Error> {
+        self.tap_schema_sync_with(&VoClient::new())
+    }
+
+    /// Blocking version of [`tap_schema_with`](#method.tap_schema_with).
+    pub fn tap_schema_sync_with(&self, client: &VoClient) -> Result<TableSet, Error> {
+        let results = schema::TAP_SCHEMA_TABLES
+            .iter()
+            .map(|table| self.tap_schema_query(table).execute_sync_with(client))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(TableSet::from_tap_schema(&results))
+    }
+
+    fn tap_schema_query(&self, table: &str) -> TapQuery<'static> {
+        self.create_query(format!("SELECT * FROM TAP_SCHEMA.{}", table))
+            .with_maxrec(schema::TAP_SCHEMA_MAXREC)
+    }
+}
+
+/// A query to a TAP service, executed synchronously or submitted as an
+/// asynchronous job.
+///
+/// ```
+/// # extern crate vo_tap;
+/// # use vo_tap::TapService;
+/// # fn main() {
+/// let query = TapService::new("http://example.com/tap/")
+///     .create_query("SELECT * FROM stars AS s JOIN TAP_UPLOAD.mine AS m ON s.id = m.id")
+///     .with_maxrec(1000)
+///     .with_upload("mine", "http://example.com/mine.xml");
+/// assert_eq!(query.sync_url(), "http://example.com/tap/sync");
+/// assert_eq!(query.async_url(), "http://example.com/tap/async");
+/// assert_eq!(
+///     query.request_body(),
+///     "REQUEST=doQuery&LANG=ADQL\
+///      &QUERY=SELECT+*+FROM+stars+AS+s+JOIN+TAP_UPLOAD.mine+AS+m+ON+s.id+%3D+m.id\
+///      &FORMAT=application%2Fx-votable%2Bxml%3Bserialization%3DBINARY\
+///      &MAXREC=1000&UPLOAD=mine%2Chttp%3A%2F%2Fexample.com%2Fmine.xml"
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapQuery<'k> {
+    base_url: String,
+    query: String,
+    lang: String,
+    maxrec: Option<u64>,
+    format: Option<String>,
+    /// Names and URIs of the uploaded tables
+    uploads: Vec<(String, String)>,
+    /// Names and contents of the tables uploaded in the body of the request
+    table_uploads: Vec<(String, TableUpload)>,
+    keywords: Vec<(&'k str, &'k str)>,
+    response_hook: Option<ResponseHook>,
+}
+
+impl<'k> TapQuery<'k> {
+    /// Language of the query, "ADQL" by default. A version may be given,
+    /// e.g. "ADQL-2.0".
+    pub fn with_lang<S: Into<String>>(mut self, lang: S) -> Self {
+        self.lang = lang.into();
+        self
+    }
+
+    /// Maximum number of records to return. The results are truncated, with
+    /// an "OVERFLOW" status, if the query matches more records.
+    pub fn with_maxrec(mut self, maxrec: u64) -> Self {
+        self.maxrec = Some(maxrec);
+        self
+    }
+
+    /// Format of the results, [`DEFAULT_FORMAT`](constant.DEFAULT_FORMAT.html)
+    /// by default. The results are parsed as a VOTable, so the format must
+    /// be a VOTable serialization decoded by vo-table.
+    pub fn with_format<S: Into<String>>(mut self, format: S) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Upload the VOTable at `uri` along with the query, where it can be
+    /// queried as `TAP_UPLOAD.name`.
+    pub fn with_upload<S: Into<String>, T: Into<String>>(mut self, name: S, uri: T) -> Self {
+        self.uploads.push((name.into(), uri.into()));
+        self
+    }
+
+    /// Upload `table` in the body of the request, where it can be queried
+    /// as `TAP_UPLOAD.name`, e.g. to cross-match a local table with the
+    /// tables of the service.
+    ///
+    /// The table is sent as a VOTable with a single resource if a
+    /// [`Table`](../vo_table/struct.Table.html) is given.
+    pub fn with_table_upload<S: Into<String>, T: Into<VOTable>>(
+        mut self,
+        name: S,
+        table: T,
+    ) -> Self {
+        self.table_uploads
+            .push((name.into(), TableUpload(Arc::new(table.into()))));
+        self
+    }
+
+    /// Add a parameter not defined by the standard.
+    pub fn with_keyword(mut self, key: &'k str, value: &'k str) -> Self {
+        self.keywords.push((key, value));
+        self
+    }
+
+    /// Call `hook` with the raw response of the service when the query is
+    /// executed synchronously, e.g. to log or archive it.
+    pub fn with_response_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RawResponse) + Send + Sync + 'static,
+    {
+        self.response_hook = Some(ResponseHook::new(hook));
+        self
+    }
+
+    /// Execute the query with a new client.
+    ///
+    /// Prefer [`execute_with`](#method.execute_with) to share the
+    /// connections and configuration of a client between queries.
+    #[cfg(feature = "tokio")]
+    pub async fn execute(&self) -> Result<TapResults, Error> {
+        self.execute_with(&VoClient::new()).await
+    }
+
+    /// Execute the query with `client`.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_with(&self, client: &VoClient) -> Result<TapResults, Error> {
+        let table = client.query(&self.check_request()?).await?;
+        Ok(TapResults { table })
+    }
+
+    /// Blocking version of [`execute`](#method.execute).
+    pub fn execute_sync(&self) -> Result<TapResults, Error> {
+        self.execute_sync_with(&VoClient::new())
+    }
+
+    /// Blocking version of [`execute_with`](#method.execute_with).
+    ///
+    /// Requests are sent by the blocking client with the `blocking` feature,
+    /// or else on a Tokio runtime shared by the blocking functions.
+    pub fn execute_sync_with(&self, client: &VoClient) -> Result<TapResults, Error> {
+        #[cfg(feature = "blocking")]
+        {
+            let table = client.query_blocking(&self.check_request()?)?;
+            Ok(TapResults { table })
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            Runtime::shared()?.block_on(self.execute_with(client))?
+        }
+    }
+
+    /// Prepare an asynchronous job running the query. The job can be given
+    /// other parameters, e.g. the standard UWS parameter `RUNID`, before
+    /// being submitted.
+    ///
+    /// Fails if an uploaded table cannot be serialized.
+    pub fn create_job(&self) -> Result<JobRequest, Error> {
+        let job = self.parameters().into_iter().fold(
+            UwsService::new(self.async_url()).create_job(),
+            |job, (key, value)| job.with_parameter(key, value),
+        );
+        let files = self.serialized_tables()?.into_iter();
+        Ok(files.fold(job, |job, (name, content)| {
+            job.with_file(name, VOTABLE_CONTENT_TYPE, content)
+        }))
+    }
+
+    /// Submit the query as an asynchronous job with a new client, and start
+    /// it.
+    #[cfg(feature = "tokio")]
+    pub async fn submit(&self) -> Result<TapJob, Error> {
+        self.submit_with(&VoClient::new()).await
+    }
+
+    /// Submit the query as an asynchronous job with `client`, and start it.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn submit_with(&self, client: &VoClient) -> Result<TapJob, Error> {
+        self.check()?;
+        let job = self
+            .create_job()?
+            .with_run()
+            .submit_with(client)
+            .await
+            .map_err(Error::Uws)?;
+        Ok(TapJob::new(job))
+    }
+
+    /// Blocking version of [`submit`](#method.submit).
+    pub fn submit_sync(&self) -> Result<TapJob, Error> {
+        self.submit_sync_with(&VoClient::new())
+    }
+
+    /// Blocking version of [`submit_with`](#method.submit_with).
+    pub fn submit_sync_with(&self, client: &VoClient) -> Result<TapJob, Error> {
+        self.check()?;
+        let job = self
+            .create_job()?
+            .with_run()
+            .submit_sync_with(client)
+            .map_err(Error::Uws)?;
+        Ok(TapJob::new(job))
+    }
+
+    /// The request of the query, checked before it is sent.
+    ///
+    /// Parameters are form-urlencoded, unless tables are uploaded in the
+    /// body, which is then multipart/form-data.
+    fn check_request(&self) -> Result<Request, Error> {
+        self.check()?;
+        if self.table_uploads.is_empty() {
+            let body = self.request_body().into_bytes();
+            return Ok(Request::post(self.sync_url(), FORM_CONTENT_TYPE, body)
+                .with_response_hook(self.response_hook.clone()));
+        }
+        let multipart = self
+            .parameters()
+            .iter()
+            .fold(Multipart::new(), |multipart, (key, value)| {
+                multipart.with_text(key, value)
+            });
+        let multipart =
+            self.serialized_tables()?
+                .into_iter()
+                .fold(multipart, |multipart, (name, content)| {
+                    let filename = format!("{}.xml", name);
+                    multipart.with_file(name, &filename, VOTABLE_CONTENT_TYPE, content)
+                });
+        Ok(Request::post_multipart(self.sync_url(), &multipart)
+            .with_response_hook(self.response_hook.clone()))
+    }
+
+    /// The names and contents of the tables uploaded in the body.
+    fn serialized_tables(&self) -> Result<Vec<(&str, Vec<u8>)>, Error> {
+        self.table_uploads
+            .iter()
+            .map(|(name, table)| {
+                let mut content = Vec::new();
+                table.0.write(&mut content).map_err(Error::VOTable)?;
+                Ok((name.as_str(), content))
+            })
+            .collect()
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        let names = self
+            .uploads
+            .iter()
+            .map(|(name, _)| name)
+            .chain(self.table_uploads.iter().map(|(name, _)| name));
+        for name in names {
+            let mut chars = name.chars();
+            let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(Error::InvalidUpload(name.clone()));
+            }
+        }
+        self.base_url
+            .parse::<http::Uri>()
+            .map_err(Error::InvalidUri)?;
+        Ok(())
+    }
+
+    /// URL of the synchronous queries of the service.
+    pub fn sync_url(&self) -> String {
+        format!("{}/sync", self.base_url)
+    }
+
+    /// URL of the asynchronous jobs of the service.
+    pub fn async_url(&self) -> String {
+        format!("{}/async", self.base_url)
+    }
+
+    /// Parameters of the query.
+    pub fn parameters(&self) -> Vec<(&str, String)> {
+        let mut parameters = vec![
+            ("REQUEST", "doQuery".to_owned()),
+            ("LANG", self.lang.clone()),
+            ("QUERY", self.query.clone()),
+            (
+                "FORMAT",
+                self.format.as_deref().unwrap_or(DEFAULT_FORMAT).to_owned(),
+            ),
+        ];
+        if let Some(maxrec) = self.maxrec {
+            parameters.push(("MAXREC", maxrec.to_string()));
+        }
+        let uploads: Vec<_> = self
+            .uploads
+            .iter()
+            .map(|(name, uri)| format!("{},{}", name, uri))
+            .chain(
+                self.table_uploads
+                    .iter()
+                    .map(|(name, _)| format!("{},param:{}", name, name)),
+            )
+            .collect();
+        if !uploads.is_empty() {
+            parameters.push(("UPLOAD", uploads.join(";")));
+        }
+        parameters.extend(
+            self.keywords
+                .iter()
+                .map(|&(key, value)| (key, value.to_owned())),
+        );
+        parameters
+    }
+
+    /// Parameters of the query, as sent in the body of a synchronous
+    /// request without [uploaded tables](#method.with_table_upload).
+    pub fn request_body(&self) -> String {
+        url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(self.parameters())
+            .finish()
+    }
+}
+
+/// A table uploaded with a query, compared by identity so that queries can
+/// be compared without comparing their tables.
+#[derive(Debug, Clone)]
+struct TableUpload(Arc<VOTable>);
+
+impl PartialEq for TableUpload {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Results of a query.
+#[derive(Debug, Clone)]
+pub struct TapResults {
+    table: VOTable,
+}
+
+impl TapResults {
+    /// Wrap a VOTable returned by a TAP service.
+    pub fn new(table: VOTable) -> Self {
+        TapResults { table }
+    }
+
+    /// Rows of the results.
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        self.table
+            .tables()
+            .filter_map(|table| table.rows())
+            .flatten()
+    }
+
+    /// Number of rows of the results.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Value of the QUERY_STATUS INFO of the results, "OK" or "OVERFLOW".
+    pub fn query_status(&self) -> Option<&str> {
+        service::query_status(&self.table).map(vo_table::Info::value)
+    }
+
+    /// Whether the results were truncated to the maximum number of records.
+    pub fn is_overflow(&self) -> bool {
+        self.query_status() == Some("OVERFLOW")
+    }
+
+    pub fn table(&self) -> &VOTable {
+        &self.table
+    }
+
+    pub fn into_table(self) -> VOTable {
+        self.table
+    }
+}
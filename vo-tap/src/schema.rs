@@ -0,0 +1,532 @@
+//! Tables published by a service, read from TAP_SCHEMA or from the VOSI
+//! `/tables` resource.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Read;
+
+use log::debug;
+use vo_table::{Cell, Field, Row, VOTable};
+use vo_uws::document::Element;
+
+use crate::{Error, TapResults};
+
+/// Tables of TAP_SCHEMA describing the tables of a service, queried in
+/// this order.
+pub(crate) const TAP_SCHEMA_TABLES: [&str; 5] =
+    ["schemas", "tables", "columns", "keys", "key_columns"];
+
+/// MAXREC of the queries to TAP_SCHEMA, above the limit of most services so
+/// that the whole schema is returned.
+pub(crate) const TAP_SCHEMA_MAXREC: u64 = 1_000_000;
+
+/// The schemas of a TAP service, with their tables and columns.
+///
+/// Names are compared case-insensitively by the lookups, as in ADQL.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableSet {
+    schemas: Vec<Schema>,
+    overflow: bool,
+}
+
+/// A schema of a service, grouping tables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    name: String,
+    description: Option<String>,
+    utype: Option<String>,
+    tables: Vec<TableMeta>,
+}
+
+/// Description of a table of a service.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableMeta {
+    /// Name of the table, usually qualified by the name of its schema
+    name: String,
+    table_type: Option<String>,
+    description: Option<String>,
+    utype: Option<String>,
+    columns: Vec<ColumnMeta>,
+    foreign_keys: Vec<ForeignKey>,
+}
+
+/// Description of a column of a table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnMeta {
+    name: String,
+    description: Option<String>,
+    unit: Option<String>,
+    ucd: Option<String>,
+    utype: Option<String>,
+    datatype: Option<String>,
+    arraysize: Option<String>,
+    xtype: Option<String>,
+    principal: bool,
+    indexed: bool,
+    std: bool,
+}
+
+/// A foreign key from the columns of a table to the columns of
+/// `target_table`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForeignKey {
+    id: Option<String>,
+    target_table: String,
+    description: Option<String>,
+    utype: Option<String>,
+    /// Names of the column of the table and of the target column
+    columns: Vec<(String, String)>,
+}
+
+impl TableSet {
+    /// Parse a VOSI tableset, e.g. the document returned by the `/tables`
+    /// resource of a TAP service.
+    pub fn parse_vosi<R: Read>(r: R) -> Result<Self, Error> {
+        let root = Element::parse(r).map_err(invalid_tableset)?;
+        if root.name() != "tableset" {
+            return Err(Error::InvalidTableSet(format!(
+                "Expected tableset, found {}",
+                root.name()
+            )));
+        }
+        let schemas = root
+            .children("schema")
+            .map(|schema| Schema {
+                name: schema.child_string("name").unwrap_or_default(),
+                description: schema.child_string("description"),
+                utype: schema.child_string("utype"),
+                tables: schema.children("table").map(TableMeta::from_vosi).collect(),
+            })
+            .collect();
+        Ok(TableSet {
+            schemas,
+            overflow: false,
+        })
+    }
+
+    /// Build the tableset from the results of the queries to
+    /// `TAP_SCHEMA_TABLES`, in order.
+    pub(crate) fn from_tap_schema(results: &[TapResults]) -> Self {
+        let records = |index: usize| Records::new(results[index].table());
+        let overflow = results.iter().any(TapResults::is_overflow);
+
+        let mut schemas: Vec<_> = records(0)
+            .sorted_by("schema_index")
+            .map(|record| Schema {
+                name: record.string("schema_name").unwrap_or_default(),
+                description: record.string("description"),
+                utype: record.string("utype"),
+                tables: vec![],
+            })
+            .collect();
+
+        let mut columns: HashMap<String, Vec<ColumnMeta>> = HashMap::new();
+        for record in records(2).sorted_by("column_index") {
+            let table = record.string("table_name").unwrap_or_default();
+            columns.entry(table).or_default().push(ColumnMeta {
+                name: record.string("column_name").unwrap_or_default(),
+                description: record.string("description"),
+                unit: record.string("unit"),
+                ucd: record.string("ucd"),
+                utype: record.string("utype"),
+                datatype: record.string("datatype"),
+                arraysize: record
+                    .string("arraysize")
+                    .or_else(|| record.int("size").map(|size| size.to_string())),
+                xtype: record.string("xtype"),
+                principal: record.flag("principal"),
+                indexed: record.flag("indexed"),
+                std: record.flag("std"),
+            });
+        }
+
+        let mut key_columns: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        for record in records(4) {
+            key_columns
+                .entry(record.string("key_id").unwrap_or_default())
+                .or_default()
+                .push((
+                    record.string("from_column").unwrap_or_default(),
+                    record.string("target_column").unwrap_or_default(),
+                ));
+        }
+        let mut foreign_keys: HashMap<String, Vec<ForeignKey>> = HashMap::new();
+        for record in records(3) {
+            let id = record.string("key_id");
+            let columns = id
+                .as_ref()
+                .and_then(|id| key_columns.remove(id))
+                .unwrap_or_default();
+            foreign_keys
+                .entry(record.string("from_table").unwrap_or_default())
+                .or_default()
+                .push(ForeignKey {
+                    id,
+                    target_table: record.string("target_table").unwrap_or_default(),
+                    description: record.string("description"),
+                    utype: record.string("utype"),
+                    columns,
+                });
+        }
+
+        for record in records(1).sorted_by("table_index") {
+            let name = record.string("table_name").unwrap_or_default();
+            let table = TableMeta {
+                columns: columns.remove(&name).unwrap_or_default(),
+                foreign_keys: foreign_keys.remove(&name).unwrap_or_default(),
+                name,
+                table_type: record.string("table_type"),
+                description: record.string("description"),
+                utype: record.string("utype"),
+            };
+            let schema_name = record.string("schema_name").unwrap_or_default();
+            let index = match schemas.iter().position(|schema| schema.name == schema_name) {
+                Some(index) => index,
+                None => {
+                    schemas.push(Schema {
+                        name: schema_name,
+                        ..Schema::default()
+                    });
+                    schemas.len() - 1
+                }
+            };
+            schemas[index].tables.push(table);
+        }
+        for table in columns.keys() {
+            debug!("Ignored columns of unknown table {} in TAP_SCHEMA", table);
+        }
+        TableSet { schemas, overflow }
+    }
+
+    pub fn schemas(&self) -> &[Schema] {
+        &self.schemas
+    }
+
+    pub fn schema(&self, name: &str) -> Option<&Schema> {
+        self.schemas
+            .iter()
+            .find(|schema| schema.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Tables of all the schemas.
+    pub fn tables(&self) -> impl Iterator<Item = &TableMeta> {
+        self.schemas.iter().flat_map(|schema| schema.tables.iter())
+    }
+
+    /// The table `name`, qualified by its schema or not.
+    pub fn table(&self, name: &str) -> Option<&TableMeta> {
+        self.tables()
+            .find(|table| table.name.eq_ignore_ascii_case(name))
+            .or_else(|| {
+                self.tables()
+                    .find(|table| table.unqualified_name().eq_ignore_ascii_case(name))
+            })
+    }
+
+    /// Whether the description of the service was truncated, because it
+    /// exceeds the maximum number of records returned by the service.
+    pub fn is_overflow(&self) -> bool {
+        self.overflow
+    }
+}
+
+impl Schema {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn utype(&self) -> Option<&str> {
+        self.utype.as_deref()
+    }
+
+    pub fn tables(&self) -> &[TableMeta] {
+        &self.tables
+    }
+
+    /// The table `name`, qualified by the schema or not.
+    pub fn table(&self, name: &str) -> Option<&TableMeta> {
+        self.tables.iter().find(|table| {
+            table.name.eq_ignore_ascii_case(name)
+                || table.unqualified_name().eq_ignore_ascii_case(name)
+        })
+    }
+}
+
+impl TableMeta {
+    fn from_vosi(table: &Element) -> Self {
+        TableMeta {
+            name: table.child_string("name").unwrap_or_default(),
+            table_type: table.attribute("type").map(str::to_owned),
+            description: table.child_string("description"),
+            utype: table.child_string("utype"),
+            columns: table
+                .children("column")
+                .map(ColumnMeta::from_vosi)
+                .collect(),
+            foreign_keys: table
+                .children("foreignKey")
+                .map(ForeignKey::from_vosi)
+                .collect(),
+        }
+    }
+
+    /// Name of the table, as used in queries.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of the table without the name of its schema.
+    pub fn unqualified_name(&self) -> &str {
+        self.name.rsplit('.').next().unwrap_or(&self.name)
+    }
+
+    /// Type of the table, e.g. "table" or "view".
+    pub fn table_type(&self) -> Option<&str> {
+        self.table_type.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn utype(&self) -> Option<&str> {
+        self.utype.as_deref()
+    }
+
+    pub fn columns(&self) -> &[ColumnMeta] {
+        &self.columns
+    }
+
+    pub fn column(&self, name: &str) -> Option<&ColumnMeta> {
+        self.columns
+            .iter()
+            .find(|column| column.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn foreign_keys(&self) -> &[ForeignKey] {
+        &self.foreign_keys
+    }
+}
+
+impl ColumnMeta {
+    fn from_vosi(column: &Element) -> Self {
+        let datatype = column.child("dataType");
+        let flags: Vec<_> = column
+            .children("flag")
+            .map(|flag| flag.text().trim())
+            .collect();
+        ColumnMeta {
+            name: column.child_string("name").unwrap_or_default(),
+            description: column.child_string("description"),
+            unit: column.child_string("unit"),
+            ucd: column.child_string("ucd"),
+            utype: column.child_string("utype"),
+            datatype: column.child_string("dataType"),
+            arraysize: datatype
+                .and_then(|datatype| datatype.attribute("arraysize"))
+                .map(str::to_owned),
+            xtype: datatype
+                .and_then(|datatype| datatype.attribute("extendedType"))
+                .map(str::to_owned),
+            principal: flags.contains(&"primary"),
+            indexed: flags.contains(&"indexed"),
+            std: column.attribute("std") == Some("true"),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref()
+    }
+
+    pub fn ucd(&self) -> Option<&str> {
+        self.ucd.as_deref()
+    }
+
+    pub fn utype(&self) -> Option<&str> {
+        self.utype.as_deref()
+    }
+
+    /// Datatype of the column, e.g. "double" or "char".
+    pub fn datatype(&self) -> Option<&str> {
+        self.datatype.as_deref()
+    }
+
+    pub fn arraysize(&self) -> Option<&str> {
+        self.arraysize.as_deref()
+    }
+
+    pub fn xtype(&self) -> Option<&str> {
+        self.xtype.as_deref()
+    }
+
+    /// Whether the column is among the most relevant columns of the table.
+    pub fn is_principal(&self) -> bool {
+        self.principal
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+
+    /// Whether the column is defined by a standard.
+    pub fn is_std(&self) -> bool {
+        self.std
+    }
+}
+
+impl ForeignKey {
+    fn from_vosi(key: &Element) -> Self {
+        ForeignKey {
+            id: None,
+            target_table: key.child_string("targetTable").unwrap_or_default(),
+            description: key.child_string("description"),
+            utype: key.child_string("utype"),
+            columns: key
+                .children("fkColumn")
+                .map(|column| {
+                    (
+                        column.child_string("fromColumn").unwrap_or_default(),
+                        column.child_string("targetColumn").unwrap_or_default(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Identifier of the key in TAP_SCHEMA.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn target_table(&self) -> &str {
+        &self.target_table
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn utype(&self) -> Option<&str> {
+        self.utype.as_deref()
+    }
+
+    /// Pairs of the names of a column of the table and of the column of the
+    /// target table it refers to.
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.columns
+            .iter()
+            .map(|(from, target)| (from.as_str(), target.as_str()))
+    }
+}
+
+/// Rows of the results of a query to TAP_SCHEMA.
+struct Records<'a> {
+    fields: &'a [Field],
+    rows: Vec<Row<'a>>,
+    /// Index of the next row
+    next: usize,
+}
+
+/// A row of a TAP_SCHEMA table, whose columns are found by name
+/// case-insensitively.
+struct Record<'a> {
+    fields: &'a [Field],
+    row: Row<'a>,
+}
+
+impl<'a> Records<'a> {
+    fn new(table: &'a VOTable) -> Self {
+        match table.tables().next() {
+            Some(table) => Records {
+                fields: table.fields(),
+                rows: table.rows().map(Iterator::collect).unwrap_or_default(),
+                next: 0,
+            },
+            None => Records {
+                fields: &[],
+                rows: vec![],
+                next: 0,
+            },
+        }
+    }
+
+    /// Rows sorted by the column `index`, e.g. "column_index" defined by
+    /// TAP 1.1, if any. Rows without index come last.
+    fn sorted_by(mut self, index: &str) -> Self {
+        let fields = self.fields;
+        let index_of = |row: &Row<'a>| Record { fields, row: *row }.int(index);
+        self.rows.sort_by(|a, b| match (index_of(a), index_of(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        self
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Record<'a>;
+
+    fn next(&mut self) -> Option<Record<'a>> {
+        let row = *self.rows.get(self.next)?;
+        self.next += 1;
+        Some(Record {
+            fields: self.fields,
+            row,
+        })
+    }
+}
+
+impl<'a> Record<'a> {
+    fn cell(&self, name: &str) -> Option<Cell<'a>> {
+        let index = self.fields.iter().position(|field| {
+            field
+                .name()
+                .is_some_and(|field| field.eq_ignore_ascii_case(name))
+        })?;
+        self.row.cells().nth(index)
+    }
+
+    /// The trimmed value of a string column, unless it is null or empty.
+    fn string(&self, name: &str) -> Option<String> {
+        self.cell(name)
+            .and_then(|cell| cell.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+    }
+
+    fn int(&self, name: &str) -> Option<i64> {
+        self.cell(name).and_then(|cell| cell.as_i64())
+    }
+
+    /// The value of a boolean column, given as an integer by most services.
+    fn flag(&self, name: &str) -> bool {
+        match self.cell(name) {
+            Some(Cell::Logical([value])) => value.unwrap_or(false),
+            Some(cell) => cell.as_i64().is_some_and(|value| value != 0),
+            None => false,
+        }
+    }
+}
+
+fn invalid_tableset(e: vo_uws::Error) -> Error {
+    match e {
+        vo_uws::Error::Xml(e) => Error::InvalidTableSet(e.to_string()),
+        vo_uws::Error::InvalidDocument(reason) => Error::InvalidTableSet(reason),
+        e => Error::InvalidTableSet(e.to_string()),
+    }
+}
@@ -0,0 +1,229 @@
+//! Queries built from ADQL syntax trees.
+
+mod common;
+
+use std::convert::TryFrom;
+
+use vo_mock::{MockService, Reply};
+use vo_tap::adql::{
+    circle, col, distance, float, lit, point, within, ColumnRef, Expr, Identifier, Join,
+    JoinCondition, JoinKind, Order, Pos, Query, TableName, TableRef,
+};
+use vo_tap::{Error, TapService};
+
+use crate::common::results;
+
+#[test]
+fn quotes_identifiers() {
+    let query = Query::new(TableName::qualified("my schema", "select"))
+        .with_column(col("ra"))
+        .with_column(col("Size"))
+        .with_column(Expr::Column(ColumnRef::new(None, "flux.g")))
+        .with_column_as(col("_id"), "zone")
+        .with_where(col("t.\"x").ne(0));
+    assert_eq!(
+        query.to_string(),
+        "SELECT ra, \"Size\", \"flux.g\", \"_id\" AS \"zone\"\n\
+         FROM \"my schema\".\"select\"\n\
+         WHERE t.\"\"\"x\" <> 0"
+    );
+    assert!(Identifier::new("absolute").to_string().starts_with('"'));
+    assert!(Identifier::new("Zone").to_string().starts_with('"'));
+    assert!(Identifier::new("zones").is_regular());
+    assert!(!Identifier::new("").is_regular());
+}
+
+#[test]
+fn escapes_literals() {
+    let name = "x' OR 1=1 --";
+    let condition = col("name")
+        .eq(lit(name))
+        .or(col("name").in_list(vec![lit("a'"), lit("")]));
+    assert_eq!(
+        condition.to_string(),
+        "name = 'x'' OR 1=1 --' OR name IN ('a''', '')"
+    );
+    assert_eq!(float(0.5).unwrap().to_string(), "0.5");
+    assert_eq!(float(12.0).unwrap().to_string(), "12.0");
+    assert_eq!(float(1e300).unwrap().to_string(), "1e300");
+    assert_eq!(lit(i64::MIN).to_string(), "-9223372036854775808");
+}
+
+#[test]
+fn rejects_non_finite_numbers() {
+    for value in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(matches!(float(*value), Err(Error::InvalidNumber(_))));
+    }
+    let cone = Pos::Circle {
+        longitude: f64::NAN,
+        latitude: 0.0,
+        radius: 1.0,
+    };
+    assert!(matches!(
+        Expr::try_from(&cone),
+        Err(Error::InvalidNumber(_))
+    ));
+    let range = Pos::Range {
+        longitude1: 0.0,
+        longitude2: f64::NAN,
+        latitude1: f64::NEG_INFINITY,
+        latitude2: f64::INFINITY,
+    };
+    assert!(matches!(
+        within(col("ra"), col("dec"), &range),
+        Err(Error::InvalidNumber(_))
+    ));
+}
+
+#[test]
+fn keeps_structure_of_expressions() {
+    let a = || col("a");
+    assert_eq!((a() - (a() - 1)).to_string(), "a - (a - 1)");
+    assert_eq!((a() - a() - 1).to_string(), "a - a - 1");
+    assert_eq!(
+        ((a() + 1) * 2 / (a() * 3)).to_string(),
+        "(a + 1) * 2 / (a * 3)"
+    );
+    assert_eq!((-lit(-2) - -a()).to_string(), "-(-2) - (-a)");
+    assert_eq!((a() - lit(-2)).lt(12).to_string(), "a - (-2) < 12");
+    assert_eq!((a() - lit(-2) * a()).to_string(), "a - (-2 * a)");
+    assert_eq!((a() + lit(-2)).to_string(), "a + -2");
+    assert_eq!((a() - float(-2.5).unwrap()).to_string(), "a - (-2.5)");
+    assert_eq!(
+        (!a().eq(1).or(a().eq(2))).to_string(),
+        "NOT (a = 1 OR a = 2)"
+    );
+    assert_eq!(
+        a().eq(1)
+            .or(a().eq(2))
+            .and(a().eq(3).and(a().eq(4)))
+            .to_string(),
+        "(a = 1 OR a = 2) AND (a = 3 AND a = 4)"
+    );
+    assert_eq!(
+        a().between(a() - 1, a() + 1).to_string(),
+        "a BETWEEN a - 1 AND a + 1"
+    );
+    assert_eq!(a().gt(1).eq(a().lt(2)).to_string(), "(a > 1) = (a < 2)");
+    assert_eq!(
+        col("b")
+            .not_like(lit("x%"))
+            .and(a().is_not_null())
+            .to_string(),
+        "b NOT LIKE 'x%' AND a IS NOT NULL"
+    );
+    assert_eq!(a().in_list(Vec::<Expr>::new()).to_string(), "1 <> 1");
+    assert_eq!(a().not_in_list(vec![1, 2]).to_string(), "a NOT IN (1, 2)");
+}
+
+#[test]
+fn pretty_prints_queries() {
+    let query = Query::new(TableRef::new("gaia.dr3lite").with_alias("g"))
+        .with_distinct()
+        .with_top(100)
+        .with_column(col("g.source_id"))
+        .with_column_as(
+            distance(
+                point(col("g.ra"), col("g.dec")),
+                point(float(10.68).unwrap(), float(41.27).unwrap()),
+            ),
+            "dist",
+        )
+        .with_join(Join::left(
+            TableRef::new("TAP_UPLOAD.mine").with_alias("m"),
+            col("g.source_id").eq(col("m.id")),
+        ))
+        .with_join(Join::new(
+            JoinKind::Full,
+            "gaia.extra",
+            JoinCondition::Using(vec!["source_id".into(), "order".into()]),
+        ))
+        .with_where(
+            within(
+                col("g.ra"),
+                col("g.dec"),
+                &Pos::circle(10.68, 41.27, 0.5).unwrap(),
+            )
+            .unwrap(),
+        )
+        .with_where(col("m.flag").eq(1).or(col("m.flag").is_null()))
+        .with_order_by(col("dist"), Order::Asc)
+        .with_order_by(col("g.source_id"), Order::Desc)
+        .with_offset(10);
+    assert_eq!(
+        query.to_string(),
+        "SELECT DISTINCT TOP 100 g.source_id, \
+         DISTANCE(POINT('ICRS', g.ra, g.dec), POINT('ICRS', 10.68, 41.27)) AS dist\n\
+         FROM gaia.dr3lite AS g\n\
+         LEFT OUTER JOIN TAP_UPLOAD.mine AS m ON g.source_id = m.id\n\
+         FULL OUTER JOIN gaia.extra USING (source_id, \"order\")\n\
+         WHERE CONTAINS(POINT('ICRS', g.ra, g.dec), CIRCLE('ICRS', 10.68, 41.27, 0.5)) = 1\n  \
+           AND (m.flag = 1 OR m.flag IS NULL)\n\
+         ORDER BY dist, g.source_id DESC\n\
+         OFFSET 10"
+    );
+    assert_eq!(query.top(), Some(100));
+    assert_eq!(query.columns().len(), 2);
+    assert_eq!(query.joins().len(), 2);
+}
+
+#[test]
+fn converts_regions() {
+    let cone = Pos::circle(10.68, 41.27, 0.5).unwrap();
+    assert_eq!(
+        Expr::try_from(&cone).unwrap(),
+        circle(
+            float(10.68).unwrap(),
+            float(41.27).unwrap(),
+            float(0.5).unwrap()
+        )
+    );
+    let polygon = Pos::polygon(vec![(359.0, 0.0), (1.0, 0.0), (1.0, 1.0)]).unwrap();
+    assert_eq!(
+        Expr::try_from(polygon.clone()).unwrap().to_string(),
+        "POLYGON('ICRS', 359.0, 0.0, 1.0, 0.0, 1.0, 1.0)"
+    );
+    assert_eq!(
+        within(col("ra"), col("dec"), &polygon).unwrap().to_string(),
+        "CONTAINS(POINT('ICRS', ra, dec), POLYGON('ICRS', 359.0, 0.0, 1.0, 0.0, 1.0, 1.0)) = 1"
+    );
+
+    let range = Pos::range((10.0, 20.0), (f64::NEG_INFINITY, f64::INFINITY)).unwrap();
+    match Expr::try_from(&range) {
+        Err(Error::UnsupportedRegion(pos)) => assert_eq!(pos, range),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(
+        within(col("ra"), col("dec"), &range).unwrap().to_string(),
+        "ra BETWEEN 10.0 AND 20.0"
+    );
+    let everywhere = Pos::range(
+        (f64::NEG_INFINITY, f64::INFINITY),
+        (f64::NEG_INFINITY, f64::INFINITY),
+    )
+    .unwrap();
+    assert_eq!(
+        within(col("ra"), col("dec"), &everywhere)
+            .unwrap()
+            .to_string(),
+        "1 = 1"
+    );
+}
+
+#[test]
+fn sends_built_queries() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let query = Query::new("stars")
+        .with_columns(vec![col("ra"), col("dec"), col("name")])
+        .with_where(col("name").eq(lit("Vega")));
+    TapService::new(service.url.as_str())
+        .create_query(&query)
+        .execute_sync()
+        .unwrap();
+
+    let requests = service.requests();
+    assert_eq!(
+        requests[0].param("QUERY"),
+        Some("SELECT ra, dec, name\nFROM stars\nWHERE name = 'Vega'")
+    );
+}
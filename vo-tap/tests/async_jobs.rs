@@ -0,0 +1,164 @@
+//! Asynchronous queries to a mock TAP service.
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use vo_mock::{MockService, Reply, Request};
+use vo_table::{ArraySize, Cell, DataType, Field, Table};
+use vo_tap::{Error, Phase, TapService};
+
+use crate::common::{error_document, results};
+
+fn job(phase: &str) -> String {
+    let (results, error) = match phase {
+        "COMPLETED" => (
+            r#"<uws:result id="result" xlink:href="results/result"/>"#,
+            "",
+        ),
+        "ERROR" => (
+            "",
+            r#"<uws:errorSummary type="fatal" hasDetail="true"><uws:message>Query failed</uws:message></uws:errorSummary>"#,
+        ),
+        _ => ("", ""),
+    };
+    format!(
+        r#"<?xml version="1.0"?>
+<uws:job xmlns:uws="http://www.ivoa.net/xml/UWS/v1.0" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <uws:jobId>1</uws:jobId>
+  <uws:phase>{}</uws:phase>
+  <uws:results>{}</uws:results>
+  {}
+</uws:job>"#,
+        phase, results, error
+    )
+}
+
+/// A service running a single job, which ends in the phase given by its
+/// query.
+fn start_service() -> MockService {
+    let query = Arc::new(Mutex::new(String::new()));
+    MockService::start_at("/tap", move |request: &Request| {
+        let mut query = query.lock().unwrap();
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/tap/async") => {
+                *query = request.param("QUERY").unwrap_or_default().to_owned();
+                Reply::see_other("/tap/async/1")
+            }
+            ("GET", "/tap/async/1") => {
+                let phase = match query.as_str() {
+                    "fail" => "ERROR",
+                    "forever" => "EXECUTING",
+                    _ => "COMPLETED",
+                };
+                Reply::votable(job(phase))
+            }
+            ("GET", "/tap/async/1/results/result") => Reply::votable(results("OVERFLOW")),
+            ("GET", "/tap/async/1/error") => Reply::votable(error_document("Unknown table stars")),
+            _ => Reply::status(404),
+        }
+    })
+}
+
+#[test]
+fn submits_and_fetches_results() {
+    let service = start_service();
+    let job = TapService::new(service.url.as_str())
+        .create_query("SELECT ra, dec, name FROM stars")
+        .with_maxrec(2)
+        .submit_sync()
+        .unwrap();
+    assert_eq!(job.job().url(), format!("{}/async/1", service.url));
+    let details = job.wait_sync(Duration::from_secs(10)).unwrap();
+    assert_eq!(details.phase(), Phase::Completed);
+
+    let results = job.results_sync().unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.is_overflow());
+
+    let creation = &service.requests()[0];
+    assert_eq!(creation.method, "POST");
+    let param = |key: &str| creation.param(key);
+    assert_eq!(param("REQUEST"), Some("doQuery"));
+    assert_eq!(param("LANG"), Some("ADQL"));
+    assert_eq!(param("QUERY"), Some("SELECT ra, dec, name FROM stars"));
+    assert_eq!(param("MAXREC"), Some("2"));
+    assert_eq!(param("PHASE"), Some("RUN"));
+}
+
+#[test]
+fn submits_uploaded_table() {
+    let service = start_service();
+    let mut table = Table::new(vec![
+        Field::new("name", DataType::Character).with_arraysize(ArraySize::Unbounded)
+    ]);
+    table.push_row(&[Cell::Character("alpha")]).unwrap();
+    let job = TapService::new(service.url.as_str())
+        .create_query("SELECT s.* FROM stars AS s JOIN TAP_UPLOAD.names USING (name)")
+        .with_table_upload("names", table)
+        .submit_sync()
+        .unwrap();
+    assert_eq!(job.results_sync().unwrap().len(), 2);
+
+    let request = &service.requests()[0];
+    assert_eq!(request.path, "/tap/async");
+    assert_eq!(request.param("UPLOAD"), Some("names,param:names"));
+    assert_eq!(request.param("PHASE"), Some("RUN"));
+    let uploaded = vo_table::parse(&request.files["names"][..]).unwrap();
+    let row = uploaded.tables().next().unwrap().rows().unwrap().next();
+    assert_eq!(
+        row.and_then(|row| row.get_by_name("name"))
+            .and_then(|cell| cell.as_str()),
+        Some("alpha")
+    );
+}
+
+#[test]
+fn reports_error_document() {
+    let service = start_service();
+    let job = TapService::new(service.url.as_str())
+        .create_query("fail")
+        .submit_sync()
+        .unwrap();
+    let details = job.wait_sync(Duration::from_secs(10)).unwrap();
+    assert_eq!(details.phase(), Phase::Error);
+    match job.results_sync() {
+        Err(Error::JobFailed(message)) => assert_eq!(message, "Unknown table stars"),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn results_of_running_job() {
+    let service = start_service();
+    let job = TapService::new(service.url.as_str())
+        .create_query("forever")
+        .submit_sync()
+        .unwrap();
+    match job.results_sync() {
+        Err(Error::JobNotCompleted(phase)) => assert_eq!(phase, Phase::Executing),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn submits_asynchronously() {
+    let service = start_service();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let results = runtime
+        .block_on(async {
+            let job = TapService::new(service.url.as_str())
+                .create_query("SELECT ra, dec, name FROM stars")
+                .submit()
+                .await?;
+            job.wait(Duration::from_secs(10)).await?;
+            job.results().await
+        })
+        .unwrap();
+    assert_eq!(results.len(), 2);
+}
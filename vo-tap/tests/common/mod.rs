@@ -0,0 +1,36 @@
+//! Documents of the mock TAP services shared by the tests.
+
+#![allow(dead_code)]
+
+/// Two rows of (ra, dec, name), in the BINARY serialization
+const ROWS: &str = "QCUAAAAAAADANEAAAAAAAAAAAAVhbHBoYUAmAAAAAAAAwDUAAAAAAAAAAAAEYmV0YQ==";
+
+pub fn results(status: &str) -> String {
+    format!(
+        r#"<?xml version="1.0"?>
+<VOTABLE version="1.3" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+  <RESOURCE type="results">
+    <INFO name="QUERY_STATUS" value="{}"/>
+    <TABLE>
+      <FIELD name="ra" datatype="double" unit="deg"/>
+      <FIELD name="dec" datatype="double" unit="deg"/>
+      <FIELD name="name" datatype="char" arraysize="*"/>
+      <DATA><BINARY><STREAM encoding="base64">{}</STREAM></BINARY></DATA>
+    </TABLE>
+  </RESOURCE>
+</VOTABLE>"#,
+        status, ROWS
+    )
+}
+
+pub fn error_document(message: &str) -> String {
+    format!(
+        r#"<?xml version="1.0"?>
+<VOTABLE version="1.3" xmlns="http://www.ivoa.net/xml/VOTable/v1.3">
+  <RESOURCE type="results">
+    <INFO name="QUERY_STATUS" value="ERROR">{}</INFO>
+  </RESOURCE>
+</VOTABLE>"#,
+        message
+    )
+}
@@ -0,0 +1,312 @@
+//! Description of the tables of a mock TAP service.
+
+use vo_mock::{MockService, Reply, Request};
+use vo_table::{ArraySize, Cell, DataType, Field, Table, VOTable};
+use vo_tap::{Error, TableSet, TapService};
+
+use self::Value::{Int, Text};
+
+/// A value of a TAP_SCHEMA table.
+#[derive(Clone, Copy)]
+enum Value {
+    Text(&'static str),
+    Int(Option<i32>),
+}
+
+/// Serialize a TAP_SCHEMA table, with the types of the values of its first
+/// row.
+fn tap_schema_table(names: &[&str], rows: &[&[Value]]) -> String {
+    let fields = names
+        .iter()
+        .zip(rows[0].iter())
+        .map(|(name, value)| match value {
+            Text(_) => Field::new(name, DataType::Character).with_arraysize(ArraySize::Unbounded),
+            Int(_) => Field::new(name, DataType::Integer32),
+        })
+        .collect();
+    let mut table = Table::new(fields);
+    for row in rows {
+        let ints: Vec<_> = row
+            .iter()
+            .map(|value| match value {
+                Int(int) => [*int],
+                Text(_) => [None],
+            })
+            .collect();
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&ints)
+            .map(|(value, int)| match value {
+                Text(text) => Cell::Character(text),
+                Int(_) => Cell::Integer32(int),
+            })
+            .collect();
+        table.push_row(&cells).unwrap();
+    }
+    let mut xml = Vec::new();
+    VOTable::from(table).write(&mut xml).unwrap();
+    String::from_utf8(xml).unwrap()
+}
+
+fn tap_schema(request: &Request) -> Reply {
+    let query = request.param("QUERY");
+    let body = match query.unwrap_or_default() {
+        "SELECT * FROM TAP_SCHEMA.schemas" => tap_schema_table(
+            &["schema_name", "description"],
+            &[&[Text("gaia"), Text("Gaia data releases")]],
+        ),
+        "SELECT * FROM TAP_SCHEMA.tables" => tap_schema_table(
+            &[
+                "schema_name",
+                "table_name",
+                "table_type",
+                "description",
+                "table_index",
+            ],
+            &[
+                &[
+                    Text("gaia"),
+                    Text("gaia.dr3lite"),
+                    Text("table"),
+                    Text("A subset of Gaia DR3"),
+                    Int(Some(2)),
+                ],
+                &[
+                    Text("gaia"),
+                    Text("gaia.sources"),
+                    Text("table"),
+                    Text(""),
+                    Int(Some(1)),
+                ],
+                &[
+                    Text("ivoa"),
+                    Text("ivoa.obscore"),
+                    Text("view"),
+                    Text("Observations"),
+                    Int(None),
+                ],
+            ],
+        ),
+        "SELECT * FROM TAP_SCHEMA.columns" => tap_schema_table(
+            &[
+                "table_name",
+                "column_name",
+                "datatype",
+                "arraysize",
+                "unit",
+                "ucd",
+                "principal",
+                "indexed",
+                "std",
+                "column_index",
+            ],
+            &[
+                &[
+                    Text("gaia.dr3lite"),
+                    Text("ra"),
+                    Text("double"),
+                    Text(""),
+                    Text("deg"),
+                    Text("pos.eq.ra;meta.main"),
+                    Int(Some(1)),
+                    Int(Some(1)),
+                    Int(Some(0)),
+                    Int(Some(2)),
+                ],
+                &[
+                    Text("gaia.dr3lite"),
+                    Text("source_id"),
+                    Text("long"),
+                    Text(""),
+                    Text(""),
+                    Text("meta.id;meta.main"),
+                    Int(Some(1)),
+                    Int(Some(1)),
+                    Int(Some(0)),
+                    Int(Some(1)),
+                ],
+                &[
+                    Text("gaia.sources"),
+                    Text("source_id"),
+                    Text("long"),
+                    Text(""),
+                    Text(""),
+                    Text("meta.id;meta.main"),
+                    Int(Some(0)),
+                    Int(Some(1)),
+                    Int(Some(0)),
+                    Int(None),
+                ],
+            ],
+        ),
+        // Column names of TAP_SCHEMA are case-insensitive
+        "SELECT * FROM TAP_SCHEMA.keys" => tap_schema_table(
+            &["KEY_ID", "FROM_TABLE", "TARGET_TABLE", "DESCRIPTION"],
+            &[&[
+                Text("k1"),
+                Text("gaia.dr3lite"),
+                Text("gaia.sources"),
+                Text("Source of the lite record"),
+            ]],
+        ),
+        "SELECT * FROM TAP_SCHEMA.key_columns" => tap_schema_table(
+            &["key_id", "from_column", "target_column"],
+            &[&[Text("k1"), Text("source_id"), Text("source_id")]],
+        ),
+        _ => return Reply::status(400),
+    };
+    Reply::votable(body)
+}
+
+#[test]
+fn reads_tap_schema() {
+    let service = MockService::start_at("/tap", tap_schema);
+    let tables = TapService::new(service.url.as_str())
+        .tap_schema_sync()
+        .unwrap();
+    assert!(!tables.is_overflow());
+
+    let names: Vec<_> = tables
+        .schemas()
+        .iter()
+        .map(|schema| schema.name())
+        .collect();
+    assert_eq!(names, vec!["gaia", "ivoa"]);
+    let gaia = tables.schema("GAIA").unwrap();
+    assert_eq!(gaia.description(), Some("Gaia data releases"));
+    let names: Vec<_> = gaia.tables().iter().map(|table| table.name()).collect();
+    assert_eq!(names, vec!["gaia.sources", "gaia.dr3lite"]);
+
+    let lite = tables.table("gaia.dr3lite").unwrap();
+    assert_eq!(lite.unqualified_name(), "dr3lite");
+    assert_eq!(lite.table_type(), Some("table"));
+    assert_eq!(lite.description(), Some("A subset of Gaia DR3"));
+    let names: Vec<_> = lite.columns().iter().map(|column| column.name()).collect();
+    assert_eq!(names, vec!["source_id", "ra"]);
+    let ra = lite.column("RA").unwrap();
+    assert_eq!(ra.datatype(), Some("double"));
+    assert_eq!(ra.arraysize(), None);
+    assert_eq!(ra.unit(), Some("deg"));
+    assert_eq!(ra.ucd(), Some("pos.eq.ra;meta.main"));
+    assert!(ra.is_principal());
+    assert!(ra.is_indexed());
+    assert!(!ra.is_std());
+
+    let keys = lite.foreign_keys();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].id(), Some("k1"));
+    assert_eq!(keys[0].target_table(), "gaia.sources");
+    assert_eq!(
+        keys[0].columns().collect::<Vec<_>>(),
+        vec![("source_id", "source_id")]
+    );
+
+    let obscore = tables.table("obscore").unwrap();
+    assert_eq!(obscore.table_type(), Some("view"));
+    assert!(obscore.columns().is_empty());
+    assert!(tables.table("unknown").is_none());
+
+    let requests = service.requests();
+    assert_eq!(requests.len(), 5);
+    for request in &requests {
+        assert_eq!(request.path, "/tap/sync");
+        assert_eq!(request.param("MAXREC"), Some("1000000"));
+    }
+}
+
+const TABLESET: &str = r#"<?xml version="1.0"?>
+<vosi:tableset xmlns:vosi="http://www.ivoa.net/xml/VOSITables/v1.0"
+    xmlns:vs="http://www.ivoa.net/xml/VODataService/v1.1"
+    xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <schema>
+    <name>gaia</name>
+    <description>Gaia data releases</description>
+    <table type="table">
+      <name>gaia.dr3lite</name>
+      <description>A subset of Gaia DR3</description>
+      <column std="false">
+        <name>source_id</name>
+        <ucd>meta.id;meta.main</ucd>
+        <dataType xsi:type="vs:TAPType">BIGINT</dataType>
+        <flag>indexed</flag>
+        <flag>primary</flag>
+      </column>
+      <column>
+        <name>ra</name>
+        <description>Right ascension</description>
+        <unit>deg</unit>
+        <ucd>pos.eq.ra;meta.main</ucd>
+        <dataType xsi:type="vs:VOTableType">double</dataType>
+      </column>
+      <column>
+        <name>obs_time</name>
+        <dataType xsi:type="vs:VOTableType" arraysize="*" extendedType="timestamp">char</dataType>
+      </column>
+      <foreignKey>
+        <targetTable>gaia.sources</targetTable>
+        <fkColumn>
+          <fromColumn>source_id</fromColumn>
+          <targetColumn>source_id</targetColumn>
+        </fkColumn>
+      </foreignKey>
+    </table>
+    <table>
+      <name>gaia.sources</name>
+    </table>
+  </schema>
+</vosi:tableset>"#;
+
+#[test]
+fn reads_vosi_tables() {
+    let service = MockService::start_at("/tap", |request| {
+        if request.path == "/tap/tables" {
+            Reply::xml(TABLESET)
+        } else {
+            Reply::status(404)
+        }
+    });
+    let tables = TapService::new(format!("{}/", service.url))
+        .tables_sync()
+        .unwrap();
+    assert_eq!(tables.schemas().len(), 1);
+    assert_eq!(tables.tables().count(), 2);
+
+    let lite = tables.schema("gaia").unwrap().table("dr3lite").unwrap();
+    assert_eq!(lite.name(), "gaia.dr3lite");
+    assert_eq!(lite.table_type(), Some("table"));
+    let source_id = lite.column("source_id").unwrap();
+    assert_eq!(source_id.datatype(), Some("BIGINT"));
+    assert!(source_id.is_indexed());
+    assert!(source_id.is_principal());
+    let ra = lite.column("ra").unwrap();
+    assert_eq!(ra.description(), Some("Right ascension"));
+    assert_eq!(ra.unit(), Some("deg"));
+    assert!(!ra.is_principal());
+    let obs_time = lite.column("obs_time").unwrap();
+    assert_eq!(obs_time.arraysize(), Some("*"));
+    assert_eq!(obs_time.xtype(), Some("timestamp"));
+
+    let key = &lite.foreign_keys()[0];
+    assert_eq!(key.target_table(), "gaia.sources");
+    assert_eq!(
+        key.columns().collect::<Vec<_>>(),
+        vec![("source_id", "source_id")]
+    );
+
+    let requests = service.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+}
+
+#[test]
+fn rejects_invalid_tableset() {
+    let err = TableSet::parse_vosi(&b"<VOTABLE/>"[..]).unwrap_err();
+    match err {
+        Error::InvalidTableSet(reason) => assert_eq!(reason, "Expected tableset, found VOTABLE"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    assert!(matches!(
+        TableSet::parse_vosi(&b"<tableset>"[..]),
+        Err(Error::InvalidTableSet(_))
+    ));
+}
@@ -0,0 +1,292 @@
+//! Synchronous queries to a mock TAP service.
+
+mod common;
+
+use vo_mock::{MockService, Reply};
+use vo_table::{Cell, DataType, Field, Table, VOTable};
+use vo_tap::{ClientError, Error, TapService};
+
+use crate::common::{error_document, results};
+
+#[test]
+fn posts_query_parameters() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let results = TapService::new(service.url.as_str())
+        .create_query("SELECT ra, dec, name FROM stars")
+        .with_maxrec(2)
+        .with_upload("mine", "http://example.com/mine.xml")
+        .with_keyword("RUNID", "test")
+        .execute_sync()
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results.query_status(), Some("OK"));
+    assert!(!results.is_overflow());
+
+    let requests = service.requests();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/tap/sync");
+    assert_eq!(
+        request.content_type.as_deref(),
+        Some("application/x-www-form-urlencoded")
+    );
+    let param = |key: &str| request.param(key);
+    assert_eq!(param("REQUEST"), Some("doQuery"));
+    assert_eq!(param("LANG"), Some("ADQL"));
+    assert_eq!(param("QUERY"), Some("SELECT ra, dec, name FROM stars"));
+    assert_eq!(param("MAXREC"), Some("2"));
+    assert_eq!(param("FORMAT"), Some(vo_tap::DEFAULT_FORMAT));
+    assert_eq!(param("UPLOAD"), Some("mine,http://example.com/mine.xml"));
+    assert_eq!(param("RUNID"), Some("test"));
+}
+
+#[test]
+fn parses_rows() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let results = TapService::new(service.url.as_str())
+        .create_query("SELECT ra, dec, name FROM stars")
+        .execute_sync()
+        .unwrap();
+    let rows: Vec<_> = results
+        .rows()
+        .map(|row| {
+            (
+                row.get_by_name("ra").and_then(|cell| cell.as_f64()),
+                row.get_by_name("dec").and_then(|cell| cell.as_f64()),
+                row.get_by_name("name")
+                    .and_then(|cell| cell.as_str().map(str::to_owned)),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (Some(10.5), Some(-20.25), Some("alpha".to_owned())),
+            (Some(11.0), Some(-21.0), Some("beta".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn detects_overflow() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OVERFLOW")));
+    let results = TapService::new(service.url.as_str())
+        .create_query("SELECT ra, dec, name FROM stars")
+        .with_maxrec(2)
+        .execute_sync()
+        .unwrap();
+    assert_eq!(results.query_status(), Some("OVERFLOW"));
+    assert!(results.is_overflow());
+    assert_eq!(results.len(), 2);
+}
+
+#[test]
+fn follows_redirect_to_results() {
+    let service = MockService::start_at("/tap", |request| {
+        if request.path == "/tap/sync" {
+            Reply::see_other("/tap/results/1")
+        } else {
+            Reply::votable(results("OK"))
+        }
+    });
+    let results = TapService::new(format!("{}/", service.url))
+        .create_query("SELECT ra, dec, name FROM stars")
+        .execute_sync()
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    let requests = service.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].method, "GET");
+    assert_eq!(requests[1].path, "/tap/results/1");
+}
+
+#[test]
+fn error_document_with_error_status() {
+    let service = MockService::start_at("/tap", |_| {
+        Reply::votable(error_document("Table stars does not exist")).with_status(400)
+    });
+    let err = TapService::new(service.url.as_str())
+        .create_query("SELECT ra, dec, name FROM stars")
+        .execute_sync()
+        .unwrap_err();
+    match err {
+        Error::Client(ClientError::ServiceError { code, message }) => {
+            assert_eq!(code.as_u16(), 400);
+            assert_eq!(message, "Table stars does not exist");
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_document_with_ok_status() {
+    let service = MockService::start_at("/tap", |_| {
+        Reply::votable(error_document("Syntax error near FORM"))
+    });
+    let err = TapService::new(service.url.as_str())
+        .create_query("SELECT ra FORM stars")
+        .execute_sync()
+        .unwrap_err();
+    match err {
+        Error::Client(ClientError::ServiceError { code, message }) => {
+            assert_eq!(code.as_u16(), 200);
+            assert_eq!(message, "Syntax error near FORM");
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_status_without_document() {
+    let service = MockService::start_at("/tap", |_| {
+        Reply::text("Database unavailable").with_status(500)
+    });
+    let err = TapService::new(service.url.as_str())
+        .create_query("SELECT ra, dec, name FROM stars")
+        .execute_sync()
+        .unwrap_err();
+    match err {
+        Error::Client(ClientError::HttpStatus { code, body_excerpt }) => {
+            assert_eq!(code.as_u16(), 500);
+            assert_eq!(body_excerpt, "Database unavailable");
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+/// A local table of two sources, to cross-match with the service.
+fn sources() -> Table {
+    let mut table = Table::new(vec![
+        Field::new("id", DataType::Integer32),
+        Field::new("ra", DataType::Float64).with_unit("deg"),
+        Field::new("dec", DataType::Float64).with_unit("deg"),
+    ]);
+    table
+        .push_row(&[
+            Cell::Integer32(&[Some(1)]),
+            Cell::Float64(&[10.5]),
+            Cell::Float64(&[-20.25]),
+        ])
+        .unwrap();
+    table
+        .push_row(&[
+            Cell::Integer32(&[None]),
+            Cell::Float64(&[11.0]),
+            Cell::Float64(&[-21.0]),
+        ])
+        .unwrap();
+    table
+}
+
+#[test]
+fn uploads_local_table() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let results = TapService::new(service.url.as_str())
+        .create_query("SELECT s.* FROM stars AS s JOIN TAP_UPLOAD.mine AS m ON s.ra = m.ra")
+        .with_upload("remote", "http://example.com/remote.xml")
+        .with_table_upload("mine", sources())
+        .execute_sync()
+        .unwrap();
+    assert_eq!(results.len(), 2);
+
+    let requests = service.requests();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    assert!(request
+        .content_type
+        .as_deref()
+        .unwrap()
+        .starts_with("multipart/form-data; boundary="));
+    let param = |key: &str| request.param(key);
+    assert_eq!(param("REQUEST"), Some("doQuery"));
+    assert_eq!(
+        param("QUERY"),
+        Some("SELECT s.* FROM stars AS s JOIN TAP_UPLOAD.mine AS m ON s.ra = m.ra")
+    );
+    assert_eq!(
+        param("UPLOAD"),
+        Some("remote,http://example.com/remote.xml;mine,param:mine")
+    );
+
+    let uploaded = VOTable::parse(&request.files["mine"][..]).unwrap();
+    let table = uploaded.tables().next().unwrap();
+    let rows: Vec<_> = table
+        .rows()
+        .unwrap()
+        .map(|row| {
+            (
+                row.get_by_name("id").and_then(|cell| cell.as_i64()),
+                row.get_by_name("ra").and_then(|cell| cell.as_f64()),
+            )
+        })
+        .collect();
+    assert_eq!(rows, vec![(Some(1), Some(10.5)), (None, Some(11.0))]);
+}
+
+#[test]
+fn uploads_filtered_table() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let north = sources().filter(|row| {
+        row.get_by_name("dec")
+            .and_then(|cell| cell.as_f64())
+            .is_some_and(|dec| dec > -21.0)
+    });
+    TapService::new(service.url.as_str())
+        .create_query("SELECT * FROM TAP_UPLOAD.north")
+        .with_table_upload("north", north)
+        .execute_sync()
+        .unwrap();
+    let requests = service.requests();
+    let uploaded = VOTable::parse(&requests[0].files["north"][..]).unwrap();
+    assert_eq!(uploaded.len(), 1);
+    assert_eq!(uploaded.tables().next().unwrap().len(), 1);
+}
+
+#[test]
+fn rejects_invalid_upload_name() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let err = TapService::new(service.url.as_str())
+        .create_query("SELECT * FROM TAP_UPLOAD.mine")
+        .with_upload("my table", "http://example.com/mine.xml")
+        .execute_sync()
+        .unwrap_err();
+    match err {
+        Error::InvalidUpload(name) => assert_eq!(name, "my table"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    assert!(service.requests().is_empty());
+}
+
+#[test]
+fn rejects_invalid_table_upload_name() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OK")));
+    let err = TapService::new(service.url.as_str())
+        .create_query("SELECT * FROM TAP_UPLOAD.mine")
+        .with_table_upload("1mine", sources())
+        .execute_sync()
+        .unwrap_err();
+    match err {
+        Error::InvalidUpload(name) => assert_eq!(name, "1mine"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    assert!(service.requests().is_empty());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn executes_asynchronously() {
+    let service = MockService::start_at("/tap", |_| Reply::votable(results("OVERFLOW")));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let results = runtime
+        .block_on(
+            TapService::new(service.url.as_str())
+                .create_query("SELECT ra, dec, name FROM stars")
+                .execute(),
+        )
+        .unwrap();
+    assert!(results.is_overflow());
+}
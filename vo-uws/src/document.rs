@@ -0,0 +1,122 @@
+//! XML documents of UWS and VOSI services, read into a tree of elements.
+
+use std::io::Read;
+
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::Error;
+
+/// Namespace of the XML Schema instance attributes
+const XSI_NS: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// An XML element, identified by its local name whatever its namespace,
+/// as UWS and VOSI documents are written with or without prefixes.
+#[derive(Debug, Default)]
+pub struct Element {
+    name: String,
+    /// Namespace, local name and value of the attributes
+    attributes: Vec<(Option<String>, String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// Read the root element of a document.
+    pub fn parse<R: Read>(r: R) -> Result<Self, Error> {
+        let mut stack: Vec<Element> = vec![];
+        for event in EventReader::new(r) {
+            match event? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(Element {
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|a| (a.name.namespace, a.name.local_name, a.value))
+                        .collect(),
+                    ..Element::default()
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().expect("Elements are balanced");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(Error::InvalidDocument("Empty document".to_owned()))
+    }
+
+    /// Local name of the element.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Text content of the element, without the text of its children.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Fail unless the element is named `name`.
+    pub fn expect(&self, name: &str) -> Result<(), Error> {
+        if self.name == name {
+            Ok(())
+        } else {
+            Err(Error::InvalidDocument(format!(
+                "Expected {}, found {}",
+                name, self.name
+            )))
+        }
+    }
+
+    /// Value of the attribute `name` without namespace.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(namespace, local_name, _)| namespace.is_none() && local_name == name)
+            .map(|(_, _, value)| value.as_str())
+    }
+
+    /// Value of the attribute `name` in the namespace `ns`.
+    pub fn attribute_ns(&self, ns: &str, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(namespace, local_name, _)| {
+                namespace.as_deref() == Some(ns) && local_name == name
+            })
+            .map(|(_, _, value)| value.as_str())
+    }
+
+    /// The children named `name`.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// The first child `name`, unless it is nil.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children
+            .iter()
+            .find(|child| child.name == name)
+            .filter(|child| child.attribute_ns(XSI_NS, "nil") != Some("true"))
+    }
+
+    /// The text of the first child `name`, unless it is nil.
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.as_str())
+    }
+
+    /// The trimmed text of the child `name`, unless it is nil or empty.
+    pub fn child_string(&self, name: &str) -> Option<String> {
+        self.child_text(name)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_owned)
+    }
+}
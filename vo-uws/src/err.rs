@@ -0,0 +1,49 @@
+use std::error;
+use std::fmt;
+
+use xml::reader;
+
+#[derive(Debug)]
+pub enum Error {
+    Client(vo_client::Error),
+    InvalidUri(http::uri::InvalidUri),
+    /// The service did not tell the URL of a job it created.
+    MissingJobUrl,
+    Xml(reader::Error),
+    /// A UWS document misses a required element or has an invalid value.
+    InvalidDocument(String),
+}
+
+impl From<reader::Error> for Error {
+    fn from(e: reader::Error) -> Self {
+        Error::Xml(e)
+    }
+}
+
+impl From<vo_client::Error> for Error {
+    fn from(e: vo_client::Error) -> Self {
+        Error::Client(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+        match self {
+            Client(e) => write!(f, "{}", e),
+            InvalidUri(e) => write!(f, "Invalid URL. {}", e),
+            MissingJobUrl => write!(f, "The service did not return the URL of the new job"),
+            Xml(e) => write!(f, "Error parsing UWS document: {}", e),
+            InvalidDocument(msg) => write!(f, "Invalid UWS document. {}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Client(e) => e.source(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! UWS documents: jobs, lists of jobs and results.
+
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+use crate::document::Element;
+use crate::Error;
+
+/// Namespace of the XLink attributes
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+/// Execution phase of a job.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// The job is accepted, but not started.
+    Pending,
+    Queued,
+    Executing,
+    Completed,
+    Error,
+    Aborted,
+    Unknown,
+    /// The job is not allowed to run until the client changes its
+    /// parameters.
+    Held,
+    Suspended,
+    /// The results of the job were deleted, but not its other resources.
+    Archived,
+}
+
+impl Phase {
+    /// Whether the job was started and did not stop yet.
+    pub fn is_active(self) -> bool {
+        matches!(self, Phase::Queued | Phase::Executing)
+    }
+
+    /// Whether the job is over and will not change any more.
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            Phase::Completed | Phase::Error | Phase::Aborted | Phase::Archived
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Pending => "PENDING",
+            Phase::Queued => "QUEUED",
+            Phase::Executing => "EXECUTING",
+            Phase::Completed => "COMPLETED",
+            Phase::Error => "ERROR",
+            Phase::Aborted => "ABORTED",
+            Phase::Unknown => "UNKNOWN",
+            Phase::Held => "HELD",
+            Phase::Suspended => "SUSPENDED",
+            Phase::Archived => "ARCHIVED",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Phase {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "PENDING" => Phase::Pending,
+            "QUEUED" => Phase::Queued,
+            "EXECUTING" => Phase::Executing,
+            "COMPLETED" => Phase::Completed,
+            "ERROR" => Phase::Error,
+            "ABORTED" => Phase::Aborted,
+            "UNKNOWN" => Phase::Unknown,
+            "HELD" => Phase::Held,
+            "SUSPENDED" => Phase::Suspended,
+            "ARCHIVED" => Phase::Archived,
+            phase => return Err(Error::InvalidDocument(format!("Unknown phase '{}'", phase))),
+        })
+    }
+}
+
+/// Description of a job, as given by the `uws:job` element.
+///
+/// Dates are kept as written by the service, in ISO 8601.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    job_id: String,
+    run_id: Option<String>,
+    owner_id: Option<String>,
+    phase: Phase,
+    quote: Option<String>,
+    creation_time: Option<String>,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    execution_duration: Option<u64>,
+    destruction: Option<String>,
+    parameters: Vec<Parameter>,
+    results: Vec<JobResult>,
+    error_summary: Option<ErrorSummary>,
+}
+
+impl Job {
+    /// Parse a `uws:job` document.
+    ///
+    /// ```
+    /// # extern crate vo_uws;
+    /// # use vo_uws::{Job, Phase};
+    /// # fn main() {
+    /// let job = Job::parse(r#"
+    ///     <uws:job xmlns:uws="http://www.ivoa.net/xml/UWS/v1.0"
+    ///              xmlns:xlink="http://www.w3.org/1999/xlink">
+    ///       <uws:jobId>42</uws:jobId>
+    ///       <uws:phase>COMPLETED</uws:phase>
+    ///       <uws:executionDuration>600</uws:executionDuration>
+    ///       <uws:parameters>
+    ///         <uws:parameter id="QUERY">SELECT * FROM stars</uws:parameter>
+    ///       </uws:parameters>
+    ///       <uws:results>
+    ///         <uws:result id="result" xlink:href="http://example.com/jobs/42/results/result"/>
+    ///       </uws:results>
+    ///     </uws:job>"#.as_bytes()).unwrap();
+    /// assert_eq!(job.job_id(), "42");
+    /// assert_eq!(job.phase(), Phase::Completed);
+    /// assert_eq!(job.execution_duration(), Some(600));
+    /// assert_eq!(job.parameter("QUERY"), Some("SELECT * FROM stars"));
+    /// assert_eq!(job.result("result").and_then(|r| r.href()), Some("http://example.com/jobs/42/results/result"));
+    /// # }
+    /// ```
+    pub fn parse<R: Read>(r: R) -> Result<Self, Error> {
+        let root = Element::parse(r)?;
+        root.expect("job")?;
+        Job::from_element(&root)
+    }
+
+    fn from_element(element: &Element) -> Result<Self, Error> {
+        let phase = element
+            .child_text("phase")
+            .ok_or_else(|| missing("phase", "job"))?
+            .parse()?;
+        let execution_duration = match element.child_text("executionDuration") {
+            Some(duration) => Some(duration.trim().parse().map_err(|_| {
+                Error::InvalidDocument(format!("Invalid execution duration '{}'", duration))
+            })?),
+            None => None,
+        };
+        Ok(Job {
+            job_id: element
+                .child_text("jobId")
+                .ok_or_else(|| missing("jobId", "job"))?
+                .trim()
+                .to_owned(),
+            run_id: element.child_string("runId"),
+            owner_id: element.child_string("ownerId"),
+            phase,
+            quote: element.child_string("quote"),
+            creation_time: element.child_string("creationTime"),
+            start_time: element.child_string("startTime"),
+            end_time: element.child_string("endTime"),
+            execution_duration,
+            destruction: element.child_string("destruction"),
+            parameters: element
+                .child("parameters")
+                .map(|parameters| {
+                    parameters
+                        .children("parameter")
+                        .map(Parameter::from_element)
+                        .collect()
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            results: element
+                .child("results")
+                .map(JobResult::from_list)
+                .transpose()?
+                .unwrap_or_default(),
+            error_summary: element
+                .child("errorSummary")
+                .map(ErrorSummary::from_element)
+                .transpose()?,
+        })
+    }
+
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    /// Identifier given by the client to the job.
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
+    pub fn owner_id(&self) -> Option<&str> {
+        self.owner_id.as_deref()
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Date at which the job is expected to be completed.
+    pub fn quote(&self) -> Option<&str> {
+        self.quote.as_deref()
+    }
+
+    pub fn creation_time(&self) -> Option<&str> {
+        self.creation_time.as_deref()
+    }
+
+    pub fn start_time(&self) -> Option<&str> {
+        self.start_time.as_deref()
+    }
+
+    pub fn end_time(&self) -> Option<&str> {
+        self.end_time.as_deref()
+    }
+
+    /// Maximum duration of the execution, in seconds. A duration of 0 means
+    /// unlimited.
+    pub fn execution_duration(&self) -> Option<u64> {
+        self.execution_duration
+    }
+
+    /// Date at which the job will be deleted by the service.
+    pub fn destruction(&self) -> Option<&str> {
+        self.destruction.as_deref()
+    }
+
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// Value of the parameter `id`, whose case is ignored.
+    pub fn parameter(&self, id: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.id.eq_ignore_ascii_case(id))
+            .map(Parameter::value)
+    }
+
+    pub fn results(&self) -> &[JobResult] {
+        &self.results
+    }
+
+    pub fn result(&self, id: &str) -> Option<&JobResult> {
+        self.results.iter().find(|result| result.id == id)
+    }
+
+    /// Summary of the error of a job in the `ERROR` phase.
+    pub fn error_summary(&self) -> Option<&ErrorSummary> {
+        self.error_summary.as_ref()
+    }
+}
+
+/// A parameter of a job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    id: String,
+    value: String,
+    by_reference: bool,
+    is_post: bool,
+}
+
+impl Parameter {
+    fn from_element(element: &Element) -> Result<Self, Error> {
+        Ok(Parameter {
+            id: element
+                .attribute("id")
+                .ok_or_else(|| missing("id", "parameter"))?
+                .to_owned(),
+            value: element.text().to_owned(),
+            by_reference: element.attribute("byReference") == Some("true"),
+            is_post: element.attribute("isPost") == Some("true"),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Whether the value is the URL of the actual value.
+    pub fn by_reference(&self) -> bool {
+        self.by_reference
+    }
+
+    /// Whether the value was posted as a file.
+    pub fn is_post(&self) -> bool {
+        self.is_post
+    }
+}
+
+/// A result of a job, available at its `href`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobResult {
+    id: String,
+    href: Option<String>,
+    mime_type: Option<String>,
+    size: Option<u64>,
+}
+
+impl JobResult {
+    /// Parse a `uws:results` document.
+    pub fn parse_list<R: Read>(r: R) -> Result<Vec<Self>, Error> {
+        let root = Element::parse(r)?;
+        root.expect("results")?;
+        JobResult::from_list(&root)
+    }
+
+    fn from_list(element: &Element) -> Result<Vec<Self>, Error> {
+        element
+            .children("result")
+            .map(JobResult::from_element)
+            .collect()
+    }
+
+    fn from_element(element: &Element) -> Result<Self, Error> {
+        Ok(JobResult {
+            id: element
+                .attribute("id")
+                .ok_or_else(|| missing("id", "result"))?
+                .to_owned(),
+            href: element
+                .attribute_ns(XLINK_NS, "href")
+                .or_else(|| element.attribute("href"))
+                .map(str::to_owned),
+            mime_type: element.attribute("mime-type").map(str::to_owned),
+            size: element.attribute("size").and_then(|size| size.parse().ok()),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn href(&self) -> Option<&str> {
+        self.href.as_deref()
+    }
+
+    pub fn mime_type(&self) -> Option<&str> {
+        self.mime_type.as_deref()
+    }
+
+    /// Size of the result, in bytes.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+/// Summary of the error of a job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorSummary {
+    kind: ErrorKind,
+    has_detail: bool,
+    message: String,
+}
+
+/// Whether a failed job may succeed if run again.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Transient,
+    Fatal,
+}
+
+impl ErrorSummary {
+    fn from_element(element: &Element) -> Result<Self, Error> {
+        let kind = match element.attribute("type") {
+            Some("transient") => ErrorKind::Transient,
+            Some("fatal") | None => ErrorKind::Fatal,
+            Some(kind) => {
+                return Err(Error::InvalidDocument(format!(
+                    "Unknown error type '{}'",
+                    kind
+                )))
+            }
+        };
+        Ok(ErrorSummary {
+            kind,
+            has_detail: element.attribute("hasDetail") == Some("true"),
+            message: element
+                .child_text("message")
+                .unwrap_or("")
+                .trim()
+                .to_owned(),
+        })
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Whether the service gives a detailed error message at the `error`
+    /// resource of the job.
+    pub fn has_detail(&self) -> bool {
+        self.has_detail
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// A job in a job list, as given by the `uws:jobref` element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRef {
+    id: String,
+    href: Option<String>,
+    phase: Phase,
+    run_id: Option<String>,
+    owner_id: Option<String>,
+    creation_time: Option<String>,
+}
+
+impl JobRef {
+    /// Parse a `uws:jobs` document.
+    pub fn parse_list<R: Read>(r: R) -> Result<Vec<Self>, Error> {
+        let root = Element::parse(r)?;
+        root.expect("jobs")?;
+        root.children("jobref").map(JobRef::from_element).collect()
+    }
+
+    fn from_element(element: &Element) -> Result<Self, Error> {
+        Ok(JobRef {
+            id: element
+                .attribute("id")
+                .ok_or_else(|| missing("id", "jobref"))?
+                .to_owned(),
+            href: element
+                .attribute_ns(XLINK_NS, "href")
+                .or_else(|| element.attribute("href"))
+                .map(str::to_owned),
+            phase: element
+                .child_text("phase")
+                .ok_or_else(|| missing("phase", "jobref"))?
+                .parse()?,
+            run_id: element.child_string("runId"),
+            owner_id: element.child_string("ownerId"),
+            creation_time: element.child_string("creationTime"),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn href(&self) -> Option<&str> {
+        self.href.as_deref()
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
+    pub fn owner_id(&self) -> Option<&str> {
+        self.owner_id.as_deref()
+    }
+
+    pub fn creation_time(&self) -> Option<&str> {
+        self.creation_time.as_deref()
+    }
+}
+
+fn missing(name: &str, parent: &str) -> Error {
+    Error::InvalidDocument(format!("Missing {} of {}", name, parent))
+}
@@ -0,0 +1,561 @@
+//! Client for the Universal Worker Service pattern, version 1.1.
+//!
+//! A UWS service runs long jobs, e.g. asynchronous TAP queries, which are
+//! created with their parameters, started, then polled until they are over
+//! before their results are fetched:
+//!
+//! ```no_run
+//! # extern crate vo_uws;
+//! # use std::time::Duration;
+//! # use vo_uws::{Phase, UwsService};
+//! # fn main() {
+//! let job = UwsService::new("http://example.com/tap/async")
+//!     .create_job()
+//!     .with_parameter("LANG", "ADQL")
+//!     .with_parameter("QUERY", "SELECT * FROM stars")
+//!     .with_run()
+//!     .submit_sync()
+//!     .unwrap();
+//! let details = job.wait_sync(Duration::from_secs(600)).unwrap();
+//! if details.phase() == Phase::Completed {
+//!     for result in job.results_sync().unwrap() {
+//!         let bytes = job.fetch_result_sync(&result).unwrap();
+//!         println!("{}: {} bytes", result.id(), bytes.len());
+//!     }
+//! }
+//! job.delete_sync().unwrap();
+//! # }
+//! ```
+//!
+//! Requests can be sent asynchronously on a Tokio runtime, with the `tokio`
+//! feature enabled by default, or from blocking code with the functions
+//! ending with `_sync`. With the `blocking` feature, blocking functions send
+//! their requests without any asynchronous runtime.
+
+#[cfg(not(any(feature = "tokio", feature = "blocking")))]
+compile_error!("Either the `tokio` or the `blocking` feature of vo-uws must be enabled.");
+
+pub mod document;
+mod err;
+mod job;
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+use log::debug;
+use vo_client::service::{Request, Response};
+use vo_client::Multipart;
+#[cfg(all(feature = "tokio", not(feature = "blocking")))]
+use vo_client::Runtime;
+
+pub use crate::err::Error;
+pub use crate::job::{ErrorKind, ErrorSummary, Job, JobRef, JobResult, Parameter, Phase};
+pub use vo_client::{Error as ClientError, VoClient, VoClientBuilder};
+
+/// Longest time a service is asked to block a request until a job changes
+/// phase
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Time left to a service to answer a blocked request before the timeout of
+/// the client
+const WAIT_MARGIN: Duration = Duration::from_secs(5);
+
+/// Shortest and longest intervals between two requests for the phase of a
+/// job, when the service does not block them
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A UWS service, identified by the URL of its job list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UwsService<U> {
+    url: U,
+}
+
+impl<U> UwsService<U>
+where
+    U: ToString,
+{
+    pub fn new(url: U) -> UwsService<U> {
+        UwsService { url }
+    }
+
+    pub fn map<F, V>(self, mut f: F) -> UwsService<V>
+    where
+        F: FnMut(U) -> V,
+        V: ToString,
+    {
+        UwsService { url: f(self.url) }
+    }
+
+    /// Prepare the creation of a job.
+    pub fn create_job(&self) -> JobRequest {
+        JobRequest {
+            jobs_url: self.url.to_string(),
+            parameters: vec![],
+            files: vec![],
+            run: false,
+        }
+    }
+
+    /// List the jobs of the service visible to the client.
+    #[cfg(feature = "tokio")]
+    pub async fn jobs_with(&self, client: &VoClient) -> Result<Vec<JobRef>, Error> {
+        let response = client.send(&Request::get(self.url.to_string())).await?;
+        JobRef::parse_list(&response.body[..])
+    }
+
+    /// Blocking version of [`jobs_with`](#method.jobs_with).
+    pub fn jobs_sync_with(&self, client: &VoClient) -> Result<Vec<JobRef>, Error> {
+        let response = send_sync(client, &Request::get(self.url.to_string()))?;
+        JobRef::parse_list(&response.body[..])
+    }
+}
+
+/// The parameters of a job to create.
+///
+/// ```
+/// # extern crate vo_uws;
+/// # use vo_uws::UwsService;
+/// # fn main() {
+/// let request = UwsService::new("http://example.com/async")
+///     .create_job()
+///     .with_parameter("QUERY", "SELECT * FROM stars")
+///     .with_run();
+/// assert_eq!(
+///     request.parameters().collect::<Vec<_>>(),
+///     vec![("QUERY", "SELECT * FROM stars"), ("PHASE", "RUN")]
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRequest {
+    jobs_url: String,
+    parameters: Vec<(String, String)>,
+    /// Name, content type and content of each file
+    files: Vec<(String, String, Vec<u8>)>,
+    run: bool,
+}
+
+impl JobRequest {
+    pub fn with_parameter<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.parameters.push((key.into(), value.into()));
+        self
+    }
+
+    /// Upload `content` as the file parameter `name`.
+    ///
+    /// Jobs with files are created with a multipart/form-data request.
+    pub fn with_file<K: Into<String>>(
+        mut self,
+        name: K,
+        content_type: &str,
+        content: Vec<u8>,
+    ) -> Self {
+        self.files
+            .push((name.into(), content_type.to_owned(), content));
+        self
+    }
+
+    /// Start the job as soon as it is created, instead of leaving it in the
+    /// `PENDING` phase until [`run`](struct.UwsJob.html#method.run) is
+    /// called.
+    pub fn with_run(mut self) -> Self {
+        self.run = true;
+        self
+    }
+
+    /// Parameters posted to create the job.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        let run = if self.run {
+            Some(("PHASE", "RUN"))
+        } else {
+            None
+        };
+        self.parameters
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .chain(run)
+    }
+
+    /// Create the job with a new client.
+    #[cfg(feature = "tokio")]
+    pub async fn submit(&self) -> Result<UwsJob, Error> {
+        self.submit_with(&VoClient::new()).await
+    }
+
+    /// Create the job with `client`, which is kept to send the following
+    /// requests about the job.
+    ///
+    /// Must be called from a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn submit_with(&self, client: &VoClient) -> Result<UwsJob, Error> {
+        let request = self.check_request()?;
+        let response = client.send(&request).await?;
+        self.created(client, response)
+    }
+
+    /// Blocking version of [`submit`](#method.submit).
+    pub fn submit_sync(&self) -> Result<UwsJob, Error> {
+        self.submit_sync_with(&VoClient::new())
+    }
+
+    /// Blocking version of [`submit_with`](#method.submit_with).
+    pub fn submit_sync_with(&self, client: &VoClient) -> Result<UwsJob, Error> {
+        let request = self.check_request()?;
+        let response = send_sync(client, &request)?;
+        self.created(client, response)
+    }
+
+    fn check_request(&self) -> Result<Request, Error> {
+        self.jobs_url
+            .parse::<http::Uri>()
+            .map_err(Error::InvalidUri)?;
+        let url = self.jobs_url.clone();
+        let request = if self.files.is_empty() {
+            Request::post_form(url, self.parameters())
+        } else {
+            let multipart = self
+                .parameters()
+                .fold(Multipart::new(), |multipart, (key, value)| {
+                    multipart.with_text(key, value)
+                });
+            let multipart =
+                self.files
+                    .iter()
+                    .fold(multipart, |multipart, (name, content_type, content)| {
+                        multipart.with_file(name, name, content_type, content.clone())
+                    });
+            Request::post_multipart(url, &multipart)
+        };
+        Ok(request.without_redirects())
+    }
+
+    /// The job created by the service, which should redirect to it. Services
+    /// which answer with the job itself give its identifier instead.
+    fn created(&self, client: &VoClient, response: Response) -> Result<UwsJob, Error> {
+        let url = match response.location()? {
+            Some(url) => url,
+            None => match Job::parse(&response.body[..]) {
+                Ok(job) => format!("{}/{}", self.jobs_url.trim_end_matches('/'), job.job_id()),
+                Err(e) => {
+                    debug!("Could not read the job created at {}: {}", response.url, e);
+                    return Err(Error::MissingJobUrl);
+                }
+            },
+        };
+        debug!("Created job {}", url);
+        Ok(UwsJob::new(url, client.clone()))
+    }
+}
+
+/// A job of a UWS service, identified by its URL.
+///
+/// Requests about the job are sent by the client which created it.
+#[derive(Debug, Clone)]
+pub struct UwsJob {
+    url: String,
+    client: VoClient,
+}
+
+impl UwsJob {
+    /// Refer to an existing job.
+    pub fn new<S: Into<String>>(url: S, client: VoClient) -> Self {
+        UwsJob {
+            url: url.into().trim_end_matches('/').to_owned(),
+            client,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn client(&self) -> &VoClient {
+        &self.client
+    }
+
+    fn resource(&self, name: &str) -> String {
+        format!("{}/{}", self.url, name)
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        Ok(self.client.send(&request).await?)
+    }
+
+    fn send_sync(&self, request: Request) -> Result<Response, Error> {
+        send_sync(&self.client, &request)
+    }
+
+    /// Fetch the description of the job.
+    #[cfg(feature = "tokio")]
+    pub async fn details(&self) -> Result<Job, Error> {
+        let response = self.send(Request::get(self.url.clone())).await?;
+        Job::parse(&response.body[..])
+    }
+
+    /// Blocking version of [`details`](#method.details).
+    pub fn details_sync(&self) -> Result<Job, Error> {
+        let response = self.send_sync(Request::get(self.url.clone()))?;
+        Job::parse(&response.body[..])
+    }
+
+    /// Fetch the phase of the job alone.
+    #[cfg(feature = "tokio")]
+    pub async fn phase(&self) -> Result<Phase, Error> {
+        let response = self.send(Request::get(self.resource("phase"))).await?;
+        String::from_utf8_lossy(&response.body).parse()
+    }
+
+    /// Blocking version of [`phase`](#method.phase).
+    pub fn phase_sync(&self) -> Result<Phase, Error> {
+        let response = self.send_sync(Request::get(self.resource("phase")))?;
+        String::from_utf8_lossy(&response.body).parse()
+    }
+
+    fn parameters_request<'a, I>(&self, parameters: I) -> Request
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        Request::post_form(self.resource("parameters"), parameters).without_redirects()
+    }
+
+    /// Set parameters of a job which was not started yet.
+    #[cfg(feature = "tokio")]
+    pub async fn set_parameters<'a, I>(&self, parameters: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        self.send(self.parameters_request(parameters)).await?;
+        Ok(())
+    }
+
+    /// Blocking version of [`set_parameters`](#method.set_parameters).
+    pub fn set_parameters_sync<'a, I>(&self, parameters: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        self.send_sync(self.parameters_request(parameters))?;
+        Ok(())
+    }
+
+    fn phase_request(&self, phase: &str) -> Request {
+        Request::post_form(self.resource("phase"), vec![("PHASE", phase)]).without_redirects()
+    }
+
+    /// Start the job.
+    #[cfg(feature = "tokio")]
+    pub async fn run(&self) -> Result<(), Error> {
+        self.send(self.phase_request("RUN")).await?;
+        Ok(())
+    }
+
+    /// Blocking version of [`run`](#method.run).
+    pub fn run_sync(&self) -> Result<(), Error> {
+        self.send_sync(self.phase_request("RUN"))?;
+        Ok(())
+    }
+
+    /// Stop the job, which moves to the `ABORTED` phase.
+    #[cfg(feature = "tokio")]
+    pub async fn abort(&self) -> Result<(), Error> {
+        self.send(self.phase_request("ABORT")).await?;
+        Ok(())
+    }
+
+    /// Blocking version of [`abort`](#method.abort).
+    pub fn abort_sync(&self) -> Result<(), Error> {
+        self.send_sync(self.phase_request("ABORT"))?;
+        Ok(())
+    }
+
+    fn delete_request(&self) -> Request {
+        Request::post_form(self.url.clone(), vec![("ACTION", "DELETE")]).without_redirects()
+    }
+
+    /// Delete the job and its results, aborting it if needed.
+    #[cfg(feature = "tokio")]
+    pub async fn delete(&self) -> Result<(), Error> {
+        self.send(self.delete_request()).await?;
+        Ok(())
+    }
+
+    /// Blocking version of [`delete`](#method.delete).
+    pub fn delete_sync(&self) -> Result<(), Error> {
+        self.send_sync(self.delete_request())?;
+        Ok(())
+    }
+
+    /// Wait until the job is neither queued nor executing, for at most
+    /// `timeout`, and return its last description. A job which was not
+    /// started is returned at once, and a job still running after `timeout`
+    /// is returned in its current phase.
+    ///
+    /// The service is asked to block the requests until the phase of the
+    /// job changes, for less than the timeout of the client. Requests
+    /// timing out anyway are sent again. Services which do not support it
+    /// are polled at increasing intervals.
+    #[cfg(feature = "tokio")]
+    pub async fn wait(&self, timeout: Duration) -> Result<Job, Error> {
+        let mut poller = Poller::new(timeout, self.client.timeout());
+        let mut job = self.details().await?;
+        while let Some(request) = poller.next_request(&self.url, &job) {
+            let start = Instant::now();
+            let response = match self.send(request).await {
+                // The service blocked the request for longer than the client
+                // waits, so the job is still in the same phase
+                Err(Error::Client(ClientError::Timeout)) => continue,
+                response => response?,
+            };
+            let next = Job::parse(&response.body[..])?;
+            if let Some(delay) = poller.delay(start.elapsed(), &job, &next) {
+                tokio::time::sleep(delay).await;
+            }
+            job = next;
+        }
+        Ok(job)
+    }
+
+    /// Blocking version of [`wait`](#method.wait).
+    pub fn wait_sync(&self, timeout: Duration) -> Result<Job, Error> {
+        #[cfg(feature = "blocking")]
+        {
+            let mut poller = Poller::new(timeout, self.client.timeout());
+            let mut job = self.details_sync()?;
+            while let Some(request) = poller.next_request(&self.url, &job) {
+                let start = Instant::now();
+                let response = match self.send_sync(request) {
+                    // The service blocked the request for longer than the client
+                    // waits, so the job is still in the same phase
+                    Err(Error::Client(ClientError::Timeout)) => continue,
+                    response => response?,
+                };
+                let next = Job::parse(&response.body[..])?;
+                if let Some(delay) = poller.delay(start.elapsed(), &job, &next) {
+                    std::thread::sleep(delay);
+                }
+                job = next;
+            }
+            Ok(job)
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            Runtime::shared()?.block_on(self.wait(timeout))?
+        }
+    }
+
+    /// Fetch the list of the results of the job.
+    #[cfg(feature = "tokio")]
+    pub async fn results(&self) -> Result<Vec<JobResult>, Error> {
+        let response = self.send(Request::get(self.resource("results"))).await?;
+        JobResult::parse_list(&response.body[..])
+    }
+
+    /// Blocking version of [`results`](#method.results).
+    pub fn results_sync(&self) -> Result<Vec<JobResult>, Error> {
+        let response = self.send_sync(Request::get(self.resource("results")))?;
+        JobResult::parse_list(&response.body[..])
+    }
+
+    /// The URL of `result`, which may be relative to the job.
+    pub fn result_url(&self, result: &JobResult) -> String {
+        match result.href() {
+            Some(href) => url::Url::parse(&format!("{}/", self.url))
+                .and_then(|base| base.join(href))
+                .map(url::Url::into_string)
+                .unwrap_or_else(|_| href.to_owned()),
+            None => format!("{}/results/{}", self.url, result.id()),
+        }
+    }
+
+    /// Fetch the content of `result`.
+    #[cfg(feature = "tokio")]
+    pub async fn fetch_result(&self, result: &JobResult) -> Result<Vec<u8>, Error> {
+        let response = self.send(Request::get(self.result_url(result))).await?;
+        Ok(response.body)
+    }
+
+    /// Blocking version of [`fetch_result`](#method.fetch_result).
+    pub fn fetch_result_sync(&self, result: &JobResult) -> Result<Vec<u8>, Error> {
+        let response = self.send_sync(Request::get(self.result_url(result)))?;
+        Ok(response.body)
+    }
+
+    /// Fetch the detailed error message of a job in the `ERROR` phase, whose
+    /// format depends on the service.
+    #[cfg(feature = "tokio")]
+    pub async fn error_detail(&self) -> Result<Vec<u8>, Error> {
+        let response = self.send(Request::get(self.resource("error"))).await?;
+        Ok(response.body)
+    }
+
+    /// Blocking version of [`error_detail`](#method.error_detail).
+    pub fn error_detail_sync(&self) -> Result<Vec<u8>, Error> {
+        let response = self.send_sync(Request::get(self.resource("error")))?;
+        Ok(response.body)
+    }
+}
+
+/// Send `request` from blocking code.
+fn send_sync(client: &VoClient, request: &Request) -> Result<Response, Error> {
+    #[cfg(feature = "blocking")]
+    {
+        Ok(client.send_blocking(request)?)
+    }
+    #[cfg(not(feature = "blocking"))]
+    {
+        Ok(Runtime::shared()?.block_on(client.send(request))??)
+    }
+}
+
+/// Requests for the description of a job until it stops.
+struct Poller {
+    deadline: Instant,
+    interval: Duration,
+    /// Longest time a request may be blocked by the service, below the
+    /// timeout of the client
+    max_wait: Duration,
+}
+
+impl Poller {
+    fn new(timeout: Duration, client_timeout: Option<Duration>) -> Self {
+        let max_wait = client_timeout
+            .map(|client_timeout| client_timeout.saturating_sub(WAIT_MARGIN))
+            .map_or(MAX_WAIT, |max_wait| cmp::min(max_wait, MAX_WAIT));
+        Poller {
+            deadline: Instant::now() + timeout,
+            interval: MIN_POLL_INTERVAL,
+            max_wait,
+        }
+    }
+
+    /// The next request for the description of `job`, blocked by the service
+    /// while the job stays in its phase, or `None` to stop waiting.
+    fn next_request(&self, url: &str, job: &Job) -> Option<Request> {
+        let remaining = self.deadline.checked_duration_since(Instant::now())?;
+        if !job.phase().is_active() || remaining.is_zero() {
+            return None;
+        }
+        let wait = cmp::min(remaining, self.max_wait).as_secs().max(1);
+        let separator = if url.contains('?') { '&' } else { '?' };
+        Some(Request::get(format!(
+            "{}{}WAIT={}&PHASE={}",
+            url,
+            separator,
+            wait,
+            job.phase()
+        )))
+    }
+
+    /// Time to sleep before the next request, if the service answered
+    /// before the job changed phase.
+    fn delay(&mut self, elapsed: Duration, previous: &Job, next: &Job) -> Option<Duration> {
+        if next.phase() != previous.phase() || elapsed >= self.interval {
+            return None;
+        }
+        let remaining = self.deadline.checked_duration_since(Instant::now())?;
+        let delay = cmp::min(self.interval - elapsed, remaining);
+        self.interval = cmp::min(self.interval * 2, MAX_POLL_INTERVAL);
+        Some(delay)
+    }
+}
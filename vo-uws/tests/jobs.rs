@@ -0,0 +1,416 @@
+//! Jobs of a stub UWS service.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use vo_mock::{MockService, Reply, Request};
+use vo_uws::{ClientError, Error, ErrorKind, Phase, UwsJob, UwsService, VoClient};
+
+#[derive(Debug, Clone)]
+struct StubJob {
+    phase: Phase,
+    parameters: Vec<(String, String)>,
+}
+
+impl StubJob {
+    fn query(&self) -> &str {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == "QUERY")
+            .map_or("", |(_, v)| v.as_str())
+    }
+
+    /// Phase of the job once it is over, if it ever ends
+    fn outcome(&self) -> Option<Phase> {
+        match self.query() {
+            "forever" => None,
+            "fail" => Some(Phase::Error),
+            _ => Some(Phase::Completed),
+        }
+    }
+
+    fn to_xml(&self, id: usize) -> String {
+        let parameters: String = self
+            .parameters
+            .iter()
+            .map(|(k, v)| format!(r#"<uws:parameter id="{}">{}</uws:parameter>"#, k, v))
+            .collect();
+        let results = if self.phase == Phase::Completed {
+            r#"<uws:result id="result" xlink:href="results/result" mime-type="text/plain"/>"#
+        } else {
+            ""
+        };
+        let error = if self.phase == Phase::Error {
+            r#"<uws:errorSummary type="transient" hasDetail="true">
+                 <uws:message>Database unavailable</uws:message>
+               </uws:errorSummary>"#
+        } else {
+            ""
+        };
+        format!(
+            r#"<?xml version="1.0"?>
+<uws:job xmlns:uws="http://www.ivoa.net/xml/UWS/v1.0"
+         xmlns:xlink="http://www.w3.org/1999/xlink"
+         xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" version="1.1">
+  <uws:jobId>{}</uws:jobId>
+  <uws:runId>test</uws:runId>
+  <uws:ownerId xsi:nil="true"/>
+  <uws:phase>{}</uws:phase>
+  <uws:quote xsi:nil="true"/>
+  <uws:creationTime>2024-01-01T00:00:00Z</uws:creationTime>
+  <uws:startTime xsi:nil="true"/>
+  <uws:endTime xsi:nil="true"/>
+  <uws:executionDuration>600</uws:executionDuration>
+  <uws:destruction>2024-01-08T00:00:00Z</uws:destruction>
+  <uws:parameters>{}</uws:parameters>
+  <uws:results>{}</uws:results>
+  {}
+</uws:job>"#,
+            id, self.phase, parameters, results, error
+        )
+    }
+}
+
+/// A UWS service managing its jobs in memory, which records the requests it
+/// receives.
+struct StubService {
+    url: String,
+    service: MockService,
+    jobs: Arc<Mutex<BTreeMap<usize, StubJob>>>,
+}
+
+impl StubService {
+    fn start() -> Self {
+        let jobs = Arc::new(Mutex::new(BTreeMap::new()));
+        let state = jobs.clone();
+        let service = MockService::start_at("/uws/jobs", move |request| {
+            respond(&mut state.lock().unwrap(), request)
+        });
+        StubService {
+            url: service.url.clone(),
+            service,
+            jobs,
+        }
+    }
+
+    fn requests(&self) -> Vec<Request> {
+        self.service.requests()
+    }
+
+    fn phase(&self, id: usize) -> Option<Phase> {
+        self.jobs.lock().unwrap().get(&id).map(|job| job.phase)
+    }
+}
+
+fn not_found() -> Reply {
+    Reply::text("No such job").with_status(404)
+}
+
+fn respond(jobs: &mut BTreeMap<usize, StubJob>, request: &Request) -> Reply {
+    let path: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+    let method = request.method.as_str();
+    let id = match path.as_slice() {
+        ["uws", "jobs"] if method == "POST" => {
+            let id = jobs.len() + 1;
+            let mut job = StubJob {
+                phase: Phase::Pending,
+                parameters: vec![],
+            };
+            for (key, value) in &request.params {
+                if key == "PHASE" && value == "RUN" {
+                    job.phase = Phase::Executing;
+                } else {
+                    job.parameters.push((key.clone(), value.clone()));
+                }
+            }
+            jobs.insert(id, job);
+            return Reply::see_other(&format!("/uws/jobs/{}", id));
+        }
+        ["uws", "jobs"] => {
+            let jobrefs: String = jobs
+                .iter()
+                .map(|(id, job)| {
+                    format!(
+                        r#"<uws:jobref id="{0}" xlink:href="/uws/jobs/{0}"><uws:phase>{1}</uws:phase></uws:jobref>"#,
+                        id, job.phase
+                    )
+                })
+                .collect();
+            return Reply::xml(format!(
+                r#"<uws:jobs xmlns:uws="http://www.ivoa.net/xml/UWS/v1.0"
+                             xmlns:xlink="http://www.w3.org/1999/xlink">{}</uws:jobs>"#,
+                jobrefs
+            ));
+        }
+        ["uws", "jobs", id, ..] => id.parse::<usize>().ok(),
+        _ => None,
+    };
+    let (id, job) = match id.and_then(|id| jobs.get_mut(&id).map(|job| (id, job))) {
+        Some(found) => found,
+        None => return not_found(),
+    };
+    match (method, &path[3..]) {
+        ("GET", []) => {
+            // The job ends while the service blocks the request
+            if request.param("WAIT").is_some() && job.phase == Phase::Executing {
+                if let Some(outcome) = job.outcome() {
+                    job.phase = outcome;
+                }
+            }
+            Reply::xml(job.to_xml(id))
+        }
+        ("POST", []) if request.param("ACTION") == Some("DELETE") => {
+            jobs.remove(&id);
+            Reply::see_other("/uws/jobs")
+        }
+        ("GET", ["phase"]) => Reply::text(job.phase.to_string()),
+        ("POST", ["phase"]) => {
+            match request.param("PHASE") {
+                Some("RUN") => job.phase = Phase::Executing,
+                Some("ABORT") => job.phase = Phase::Aborted,
+                _ => return Reply::text("Invalid phase").with_status(400),
+            }
+            Reply::see_other(&format!("/uws/jobs/{}", id))
+        }
+        ("POST", ["parameters"]) => {
+            job.parameters.extend(request.params.iter().cloned());
+            Reply::see_other(&format!("/uws/jobs/{}", id))
+        }
+        ("GET", ["results"]) => Reply::xml(format!(
+            r#"<uws:results xmlns:uws="http://www.ivoa.net/xml/UWS/v1.0"
+                            xmlns:xlink="http://www.w3.org/1999/xlink">
+                 <uws:result id="result" xlink:href="/uws/jobs/{}/results/result" size="19"/>
+               </uws:results>"#,
+            id
+        )),
+        ("GET", ["results", "result"]) => Reply::text(format!("Result of job {}", id)),
+        ("GET", ["error"]) => Reply::text("Could not connect to the database"),
+        _ => not_found(),
+    }
+}
+
+#[test]
+fn creates_and_runs_job() {
+    let service = StubService::start();
+    let job = UwsService::new(service.url.as_str())
+        .create_job()
+        .with_parameter("LANG", "ADQL")
+        .submit_sync()
+        .unwrap();
+    assert_eq!(job.url(), format!("{}/1", service.url));
+    assert_eq!(job.phase_sync().unwrap(), Phase::Pending);
+
+    job.set_parameters_sync(vec![("QUERY", "SELECT * FROM stars")])
+        .unwrap();
+    let details = job.details_sync().unwrap();
+    assert_eq!(details.job_id(), "1");
+    assert_eq!(details.run_id(), Some("test"));
+    assert_eq!(details.owner_id(), None);
+    assert_eq!(details.phase(), Phase::Pending);
+    assert_eq!(details.start_time(), None);
+    assert_eq!(details.execution_duration(), Some(600));
+    assert_eq!(details.destruction(), Some("2024-01-08T00:00:00Z"));
+    assert_eq!(details.parameter("lang"), Some("ADQL"));
+    assert_eq!(details.parameter("QUERY"), Some("SELECT * FROM stars"));
+
+    job.run_sync().unwrap();
+    assert_eq!(service.phase(1), Some(Phase::Executing));
+    let details = job.wait_sync(Duration::from_secs(10)).unwrap();
+    assert_eq!(details.phase(), Phase::Completed);
+
+    let wait = service.requests().pop().unwrap();
+    assert_eq!(wait.method, "GET");
+    let seconds: u64 = wait.param("WAIT").unwrap().parse().unwrap();
+    assert!(seconds == 9 || seconds == 10, "{}", seconds);
+    assert_eq!(wait.param("PHASE"), Some("EXECUTING"));
+}
+
+#[test]
+fn fetches_results() {
+    let service = StubService::start();
+    let job = UwsService::new(service.url.as_str())
+        .create_job()
+        .with_parameter("QUERY", "SELECT * FROM stars")
+        .with_run()
+        .submit_sync()
+        .unwrap();
+    assert_eq!(service.phase(1), Some(Phase::Executing));
+    let details = job.wait_sync(Duration::from_secs(10)).unwrap();
+
+    // Relative to the job in its description
+    let result = details.result("result").unwrap();
+    assert_eq!(result.href(), Some("results/result"));
+    assert_eq!(result.mime_type(), Some("text/plain"));
+    assert_eq!(
+        job.result_url(result),
+        format!("{}/1/results/result", service.url)
+    );
+    assert_eq!(job.fetch_result_sync(result).unwrap(), b"Result of job 1");
+
+    // Relative to the service in the result list
+    let results = job.results_sync().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id(), "result");
+    assert_eq!(results[0].size(), Some(19));
+    assert_eq!(
+        job.fetch_result_sync(&results[0]).unwrap(),
+        b"Result of job 1"
+    );
+}
+
+#[test]
+fn reports_errors() {
+    let service = StubService::start();
+    let job = UwsService::new(service.url.as_str())
+        .create_job()
+        .with_parameter("QUERY", "fail")
+        .with_run()
+        .submit_sync()
+        .unwrap();
+    let details = job.wait_sync(Duration::from_secs(10)).unwrap();
+    assert_eq!(details.phase(), Phase::Error);
+    assert!(details.results().is_empty());
+    let summary = details.error_summary().unwrap();
+    assert_eq!(summary.kind(), ErrorKind::Transient);
+    assert!(summary.has_detail());
+    assert_eq!(summary.message(), "Database unavailable");
+    assert_eq!(
+        job.error_detail_sync().unwrap(),
+        b"Could not connect to the database"
+    );
+}
+
+#[test]
+fn wait_times_out() {
+    let service = StubService::start();
+    let job = UwsService::new(service.url.as_str())
+        .create_job()
+        .with_parameter("QUERY", "forever")
+        .with_run()
+        .submit_sync()
+        .unwrap();
+    let start = Instant::now();
+    let details = job.wait_sync(Duration::from_millis(1500)).unwrap();
+    assert_eq!(details.phase(), Phase::Executing);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(1500), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+}
+
+// ureq does not time out while reading the headers of a response on a
+// reused connection
+#[cfg(not(feature = "blocking"))]
+#[test]
+fn wait_outlasts_client_timeout() {
+    let service = MockService::start_at("/uws/jobs", |request| {
+        let job = StubJob {
+            phase: Phase::Executing,
+            parameters: vec![],
+        };
+        let reply = Reply::xml(job.to_xml(1));
+        // The service blocks requests for longer than the client waits
+        if request.param("WAIT").is_some() {
+            reply.with_delay(Duration::from_secs(3))
+        } else {
+            reply
+        }
+    });
+    let client = VoClient::builder()
+        .with_timeout(Some(Duration::from_secs(1)))
+        .build()
+        .unwrap();
+    let job = UwsJob::new(format!("{}/1", service.url), client);
+    let details = job.wait_sync(Duration::from_millis(2500)).unwrap();
+    assert_eq!(details.phase(), Phase::Executing);
+    let waits: Vec<_> = service
+        .requests()
+        .iter()
+        .filter_map(|request| request.param("WAIT").map(str::to_owned))
+        .collect();
+    assert!(waits.len() >= 2, "{:?}", waits);
+    assert!(waits.iter().all(|wait| wait == "1"), "{:?}", waits);
+}
+
+#[test]
+fn wait_returns_pending_job() {
+    let service = StubService::start();
+    let job = UwsService::new(service.url.as_str())
+        .create_job()
+        .submit_sync()
+        .unwrap();
+    let details = job.wait_sync(Duration::from_secs(10)).unwrap();
+    assert_eq!(details.phase(), Phase::Pending);
+    assert_eq!(service.requests().len(), 2);
+}
+
+#[test]
+fn aborts_and_deletes_jobs() {
+    let service = StubService::start();
+    let uws = UwsService::new(service.url.as_str());
+    let client = VoClient::new();
+    let first = uws
+        .create_job()
+        .with_parameter("QUERY", "forever")
+        .with_run()
+        .submit_sync_with(&client)
+        .unwrap();
+    let second = uws.create_job().submit_sync_with(&client).unwrap();
+
+    first.abort_sync().unwrap();
+    assert_eq!(first.phase_sync().unwrap(), Phase::Aborted);
+
+    let jobs = uws.jobs_sync_with(&client).unwrap();
+    let phases: Vec<_> = jobs.iter().map(|job| (job.id(), job.phase())).collect();
+    assert_eq!(phases, vec![("1", Phase::Aborted), ("2", Phase::Pending)]);
+    assert_eq!(jobs[1].href(), Some("/uws/jobs/2"));
+
+    second.delete_sync().unwrap();
+    assert_eq!(service.phase(2), None);
+    let jobs = uws.jobs_sync_with(&client).unwrap();
+    assert_eq!(jobs.len(), 1);
+    match second.details_sync() {
+        Err(Error::Client(ClientError::HttpStatus { code, .. })) => assert_eq!(code.as_u16(), 404),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn refers_to_existing_job() {
+    let service = StubService::start();
+    UwsService::new(service.url.as_str())
+        .create_job()
+        .submit_sync()
+        .unwrap();
+    let job = UwsJob::new(format!("{}/1/", service.url), VoClient::new());
+    assert_eq!(job.url(), format!("{}/1", service.url));
+    assert_eq!(job.phase_sync().unwrap(), Phase::Pending);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn runs_job_asynchronously() {
+    let service = StubService::start();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let job = UwsService::new(service.url.as_str())
+            .create_job()
+            .with_parameter("QUERY", "SELECT * FROM stars")
+            .submit()
+            .await
+            .unwrap();
+        job.run().await.unwrap();
+        let details = job.wait(Duration::from_secs(10)).await.unwrap();
+        assert_eq!(details.phase(), Phase::Completed);
+        let results = job.results().await.unwrap();
+        assert_eq!(
+            job.fetch_result(&results[0]).await.unwrap(),
+            b"Result of job 1"
+        );
+        job.delete().await.unwrap();
+    });
+    assert_eq!(service.phase(1), None);
+}